@@ -20,9 +20,45 @@ pub enum Commands {
         #[arg(long)]
         verbose: bool,
 
-        #[arg(long, value_name = "HOST[::GUEST][:ro|:rw]")]
+        #[arg(long, value_name = "HOST[::GUEST][:ro|:rw|:cow]")]
         mount: Vec<String>,
 
+        #[arg(long, value_name = "DIR")]
+        export_changes: Option<String>,
+
+        #[arg(long, value_name = "DIR")]
+        outputs_dir: Option<String>,
+
+        #[arg(long, value_name = "FILE")]
+        env_file: Vec<String>,
+
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+
+        #[arg(long)]
+        inherit_stdio: bool,
+
+        #[arg(long, value_name = "FILE")]
+        stdin_file: Option<String>,
+
+        #[arg(long)]
+        deterministic: bool,
+
+        #[arg(long, value_name = "SEED")]
+        seed: Option<u64>,
+
+        #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+        record: Option<String>,
+
+        #[arg(long, value_name = "FILE")]
+        replay: Option<String>,
+
+        #[arg(long = "kwarg", value_name = "KEY=VALUE")]
+        kwargs: Vec<String>,
+
+        #[arg(long, value_name = "FILE")]
+        kwargs_file: Option<String>,
+
         #[arg(long, value_name = "FILE", conflicts_with = "args")]
         args_file: Option<String>,
 
@@ -35,7 +71,13 @@ pub enum Commands {
         #[arg(long)]
         export: bool,
     },
-    Worker,
+    Worker {
+        #[arg(long, value_name = "FILE")]
+        env_file: Vec<String>,
+
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+    },
     Exec {
         file: String,
 
@@ -45,9 +87,36 @@ pub enum Commands {
         #[arg(long)]
         verbose: bool,
 
-        #[arg(long, value_name = "HOST[::GUEST][:ro|:rw]")]
+        #[arg(long, value_name = "HOST[::GUEST][:ro|:rw|:cow]")]
         mount: Vec<String>,
 
+        #[arg(long, value_name = "DIR")]
+        outputs_dir: Option<String>,
+
+        #[arg(long, value_name = "FILE")]
+        env_file: Vec<String>,
+
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+
+        #[arg(long)]
+        inherit_stdio: bool,
+
+        #[arg(long, value_name = "FILE")]
+        stdin_file: Option<String>,
+
+        #[arg(long)]
+        deterministic: bool,
+
+        #[arg(long, value_name = "SEED")]
+        seed: Option<u64>,
+
+        #[arg(long = "kwarg", value_name = "KEY=VALUE")]
+        kwargs: Vec<String>,
+
+        #[arg(long, value_name = "FILE")]
+        kwargs_file: Option<String>,
+
         #[arg(long, value_name = "FILE", conflicts_with = "args")]
         args_file: Option<String>,
 
@@ -119,6 +119,7 @@ pub async fn execute(file_path: Option<&Path>, export: bool) -> Result<PathBuf,
     let runtime_config = RuntimeConfig {
         cache_dir: compile_result.cache_dir,
         log_level: LogLevel::Verbose,
+        ..Default::default()
     };
     let runtime = Runtime::new(runtime_config, manifest.capsule_toml)?;
     let cwasm_path = runtime.precompile(&compile_result.wasm_path)?;
@@ -1,16 +1,20 @@
 use std::fmt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 
 use capsule_core::config::manifest::{Manifest, ManifestError};
 use capsule_core::wasm::commands::create::CreateInstance;
 use capsule_core::wasm::commands::run::RunInstance;
-use capsule_core::wasm::execution_policy::{Compute, ExecutionPolicy};
+use capsule_core::wasm::execution_policy::{Compute, ExecutionPolicy, OutputsPolicy};
 use capsule_core::wasm::runtime::{Runtime, RuntimeConfig, WasmRuntimeError};
+use capsule_core::wasm::utilities::stdio_capture::OutputEvent;
+use capsule_core::wasm::utilities::task_config::TaskInvocation;
+use capsule_core::wasm::utilities::task_progress::ProgressEvent;
 use capsule_core::wasm::utilities::task_reporter::{LogLevel, TaskReporter};
+use tokio::sync::mpsc::UnboundedSender;
 
-use crate::commands::shared::load_env_variables;
+use crate::commands::shared::{EnvSelection, load_env_variables, route_output};
 
 pub enum ExecError {
     IoError(String),
@@ -44,14 +48,44 @@ impl From<ManifestError> for ExecError {
     }
 }
 
+#[derive(Default)]
+pub struct ExecOptions {
+    pub args: Vec<String>,
+    pub kwargs: serde_json::Map<String, serde_json::Value>,
+    pub mounts: Vec<String>,
+    pub json: bool,
+    pub verbose: bool,
+    pub outputs_dir: Option<PathBuf>,
+    pub env: EnvSelection,
+    pub inherit_stdio: bool,
+    pub deterministic: bool,
+    pub seed: Option<u64>,
+    pub output_events: Option<UnboundedSender<OutputEvent>>,
+    pub progress_events: Option<UnboundedSender<ProgressEvent>>,
+    pub stdin: Option<Vec<u8>>,
+}
+
 pub async fn execute(
     wasm_path: &Path,
-    args: Vec<String>,
-    mounts: Vec<String>,
-    json: bool,
-    verbose: bool,
+    options: ExecOptions,
     shared_runtime: Option<Arc<Runtime>>,
 ) -> Result<String, ExecError> {
+    let ExecOptions {
+        args,
+        kwargs,
+        mounts,
+        json,
+        verbose,
+        outputs_dir,
+        env,
+        inherit_stdio,
+        deterministic,
+        seed,
+        output_events,
+        progress_events,
+        stdin,
+    } = options;
+
     let ext = wasm_path.extension().and_then(|e| e.to_str()).unwrap_or("");
 
     if ext != "wasm" && ext != "cwasm" {
@@ -90,12 +124,6 @@ pub async fn execute(
         std::fs::create_dir_all(&wasm_dir)?;
     }
 
-    load_env_variables(&project_root).map_err(ExecError::IoError)?;
-
-    let mut execution_policy = ExecutionPolicy::default().compute(Some(Compute::Custom(u64::MAX)));
-
-    execution_policy.mounts.extend(mounts);
-
     let runtime = match shared_runtime {
         Some(r) => r,
         None => {
@@ -105,6 +133,7 @@ pub async fn execute(
             let runtime_config = RuntimeConfig {
                 cache_dir,
                 log_level,
+                ..Default::default()
             };
             let runtime = Runtime::new(runtime_config, capsule_toml)?;
 
@@ -113,6 +142,30 @@ pub async fn execute(
         }
     };
 
+    let host_env = load_env_variables(&project_root, runtime.capsule_toml.env.as_ref(), &env)
+        .map_err(ExecError::IoError)?;
+
+    let mut execution_policy = ExecutionPolicy::default()
+        .compute(Some(Compute::Custom(u64::MAX)))
+        .host_env(host_env);
+
+    execution_policy.mounts.extend(mounts);
+
+    if outputs_dir.is_some() {
+        execution_policy = execution_policy
+            .outputs(Some(OutputsPolicy::default()))
+            .outputs_dest(outputs_dir);
+    }
+
+    let (output_events, printer) = route_output(json, inherit_stdio, output_events);
+    execution_policy = execution_policy
+        .inherit_stdio(Some(inherit_stdio))
+        .deterministic((deterministic || seed.is_some()).then_some(true))
+        .seed(seed)
+        .output_events(output_events)
+        .progress_events(progress_events)
+        .stdin(stdin);
+
     let create_instance_command = CreateInstance::new(execution_policy.clone(), args.clone())
         .wasm_path(wasm_path_abs)
         .project_root(project_root);
@@ -121,16 +174,23 @@ pub async fn execute(
 
     let start_time = Instant::now();
 
-    let args_json = serde_json::json!({
-        "task_name": "main",
-        "args": args,
-        "kwargs": {}
-    })
-    .to_string();
+    let invocation = TaskInvocation {
+        task_name: "main".to_string(),
+        args: args
+            .iter()
+            .cloned()
+            .map(serde_json::Value::String)
+            .collect(),
+        kwargs,
+    };
 
-    let run_command = RunInstance::new(task_id, execution_policy, store, instance, args_json);
+    let run_command = RunInstance::new(task_id, execution_policy, store, instance, invocation);
     let result = runtime.execute(run_command).await?;
 
+    if let Some(printer) = printer {
+        let _ = printer.await;
+    }
+
     let elapsed = start_time.elapsed();
     let time_str = reporter.format_duration(elapsed);
     reporter.success(&format!("✓ Complete ({})", time_str));
@@ -7,14 +7,18 @@ use std::time::Instant;
 use capsule_core::config::manifest::{CapsuleToml, Manifest, ManifestError};
 use capsule_core::wasm::commands::create::CreateInstance;
 use capsule_core::wasm::commands::run::RunInstance;
-use capsule_core::wasm::execution_policy::{Compute, ExecutionPolicy};
+use capsule_core::wasm::execution_policy::{Compute, ExecutionPolicy, OutputsPolicy};
 use capsule_core::wasm::runtime::{Runtime, RuntimeConfig, WasmRuntimeError};
-use capsule_core::wasm::utilities::task_config::TaskConfig;
+use capsule_core::wasm::utilities::cassette::CassetteConfig;
+use capsule_core::wasm::utilities::stdio_capture::OutputEvent;
+use capsule_core::wasm::utilities::task_config::{TaskConfig, TaskInvocation};
+use capsule_core::wasm::utilities::task_progress::ProgressEvent;
 use capsule_core::wasm::utilities::task_reporter::{LogLevel, TaskReporter};
 use tokio::sync::Mutex;
+use tokio::sync::mpsc::UnboundedSender;
 
 use crate::build::{BuildError, TaskRegistry, compile_to_wasm};
-use crate::commands::shared::load_env_variables;
+use crate::commands::shared::{EnvSelection, load_env_variables, route_output};
 
 pub enum RunError {
     IoError(String),
@@ -119,15 +123,49 @@ async fn resolve_wasm(
     }
 }
 
+#[derive(Default)]
+pub struct RunOptions {
+    pub args: Vec<String>,
+    pub kwargs: serde_json::Map<String, serde_json::Value>,
+    pub mounts: Vec<String>,
+    pub json: bool,
+    pub verbose: bool,
+    pub cassette: Option<CassetteConfig>,
+    pub export_changes: Option<PathBuf>,
+    pub outputs_dir: Option<PathBuf>,
+    pub env: EnvSelection,
+    pub inherit_stdio: bool,
+    pub deterministic: bool,
+    pub seed: Option<u64>,
+    pub output_events: Option<UnboundedSender<OutputEvent>>,
+    pub progress_events: Option<UnboundedSender<ProgressEvent>>,
+    pub stdin: Option<Vec<u8>>,
+}
+
 pub async fn execute(
     file_path: Option<&Path>,
-    args: Vec<String>,
-    mounts: Vec<String>,
-    json: bool,
-    verbose: bool,
+    options: RunOptions,
     shared_runtime: Option<Arc<Runtime>>,
     wasm_cache: Option<Arc<Mutex<HashMap<String, PathBuf>>>>,
 ) -> Result<String, RunError> {
+    let RunOptions {
+        args,
+        kwargs,
+        mounts,
+        json,
+        verbose,
+        cassette,
+        export_changes,
+        outputs_dir,
+        env,
+        inherit_stdio,
+        deterministic,
+        seed,
+        output_events,
+        progress_events,
+        stdin,
+    } = options;
+
     let manifest = Manifest::new()?;
 
     let log_level = if json {
@@ -156,12 +194,30 @@ pub async fn execute(
             .unwrap_or_default()
     });
 
-    load_env_variables(&project_root).map_err(RunError::IoError)?;
+    let host_env = load_env_variables(&project_root, manifest.capsule_toml.env.as_ref(), &env)
+        .map_err(RunError::IoError)?;
 
     let mut execution_policy = extract_main_execution_policy(task_registry, &manifest.capsule_toml)
         .unwrap_or_else(|| ExecutionPolicy::default().compute(Some(Compute::Custom(u64::MAX))));
 
     execution_policy.mounts.extend(mounts);
+    execution_policy = execution_policy
+        .export_changes(export_changes)
+        .host_env(host_env);
+
+    if outputs_dir.is_some() && execution_policy.outputs.is_none() {
+        execution_policy = execution_policy.outputs(Some(OutputsPolicy::default()));
+    }
+    execution_policy = execution_policy.outputs_dest(outputs_dir);
+
+    let (output_events, printer) = route_output(json, inherit_stdio, output_events);
+    execution_policy = execution_policy
+        .inherit_stdio(Some(inherit_stdio))
+        .deterministic((deterministic || seed.is_some()).then_some(true))
+        .seed(seed)
+        .output_events(output_events)
+        .progress_events(progress_events)
+        .stdin(stdin);
 
     let runtime = match shared_runtime {
         Some(r) => r,
@@ -171,6 +227,8 @@ pub async fn execute(
             let runtime_config = RuntimeConfig {
                 cache_dir,
                 log_level,
+                cassette,
+                ..Default::default()
             };
             let runtime = Runtime::new(runtime_config, manifest.capsule_toml)?;
 
@@ -187,16 +245,29 @@ pub async fn execute(
 
     let start_time = Instant::now();
 
-    let args_json = serde_json::json!({
-        "task_name": "main",
-        "args": args,
-        "kwargs": {}
-    })
-    .to_string();
+    let invocation = TaskInvocation {
+        task_name: "main".to_string(),
+        args: args
+            .iter()
+            .cloned()
+            .map(serde_json::Value::String)
+            .collect(),
+        kwargs,
+    };
 
-    let run_command = RunInstance::new(task_id, execution_policy, store, instance, args_json);
+    let run_command = RunInstance::new(task_id, execution_policy, store, instance, invocation);
     let result = runtime.execute(run_command).await?;
 
+    if let Some(cassette) = &runtime.cassette {
+        cassette
+            .flush()
+            .map_err(|e| RunError::IoError(e.to_string()))?;
+    }
+
+    if let Some(printer) = printer {
+        let _ = printer.await;
+    }
+
     let elapsed = start_time.elapsed();
     let time_str = reporter.format_duration(elapsed);
     reporter.success(&format!("✓ Complete ({})", time_str));
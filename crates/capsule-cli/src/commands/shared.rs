@@ -1,4 +1,12 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use capsule_core::config::manifest::EnvConfig;
+use capsule_core::wasm::utilities::stdin_input::{DEFAULT_STDIN_LIMIT, read_stdin};
+use capsule_core::wasm::utilities::stdio_capture::{OutputEvent, OutputKind};
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use tokio::task::JoinHandle;
 
 pub fn load_args_file(path: &str) -> Result<Vec<String>, String> {
     let content = std::fs::read_to_string(path)
@@ -8,17 +16,396 @@ pub fn load_args_file(path: &str) -> Result<Vec<String>, String> {
     Ok(args)
 }
 
-pub fn load_env_variables(project_root: &Path) -> Result<(), String> {
-    let env_files = [".env", ".env.local", ".env.development", ".env.production"];
+pub fn load_kwargs(
+    kwargs: &[String],
+    kwargs_file: Option<&str>,
+) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    let mut loaded = match kwargs_file {
+        Some(path) => match read_json_file(path, "--kwargs-file")? {
+            serde_json::Value::Object(map) => map,
+            _ => {
+                return Err(format!(
+                    "Invalid --kwargs-file '{}': expected a JSON object",
+                    path
+                ));
+            }
+        },
+        None => serde_json::Map::new(),
+    };
+
+    for kwarg in kwargs {
+        let (key, value) = kwarg
+            .split_once('=')
+            .filter(|(key, _)| !key.is_empty())
+            .ok_or_else(|| format!("Invalid --kwarg '{}': expected KEY=VALUE", kwarg))?;
+
+        let value = match value.strip_prefix('@') {
+            Some(path) => read_json_file(path, "--kwarg")?,
+            None => serde_json::from_str(value)
+                .unwrap_or_else(|_| serde_json::Value::String(value.to_string())),
+        };
+
+        loaded.insert(key.to_string(), value);
+    }
+
+    Ok(loaded)
+}
+
+fn read_json_file(path: &str, flag: &str) -> Result<serde_json::Value, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {} '{}': {}", flag, path, e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {} '{}': {}", flag, path, e))
+}
+
+pub fn load_stdin(stdin_file: Option<&str>) -> Result<Option<Vec<u8>>, String> {
+    let data = match stdin_file {
+        Some("-") => read_stdin(std::io::stdin().lock(), DEFAULT_STDIN_LIMIT),
+        Some(path) => {
+            let file = std::fs::File::open(path)
+                .map_err(|e| format!("Failed to read --stdin-file '{}': {}", path, e))?;
+            read_stdin(file, DEFAULT_STDIN_LIMIT)
+        }
+        None => return Ok(None),
+    }
+    .map_err(|e| e.to_string())?;
+
+    Ok((!data.is_empty()).then_some(data))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EnvSelection {
+    pub files: Vec<String>,
+    pub profile: Option<String>,
+}
+
+fn env_files(
+    project_root: &Path,
+    config: Option<&EnvConfig>,
+    selection: &EnvSelection,
+) -> Vec<(PathBuf, bool)> {
+    let explicit = if !selection.files.is_empty() {
+        Some(selection.files.clone())
+    } else {
+        config.and_then(|c| c.files.clone())
+    };
+
+    if let Some(files) = explicit {
+        return files
+            .iter()
+            .map(|file| (project_root.join(file), true))
+            .collect();
+    }
+
+    let profile = selection
+        .profile
+        .clone()
+        .or_else(|| config.and_then(|c| c.profile.clone()));
+
+    let mut names = vec![".env".to_string(), ".env.local".to_string()];
+
+    if let Some(profile) = profile {
+        names.push(format!(".env.{}", profile));
+        names.push(format!(".env.{}.local", profile));
+    }
+
+    names
+        .iter()
+        .map(|name| (project_root.join(name), false))
+        .collect()
+}
+
+pub fn load_env_variables(
+    project_root: &Path,
+    config: Option<&EnvConfig>,
+    selection: &EnvSelection,
+) -> Result<HashMap<String, String>, String> {
+    let mut variables = HashMap::new();
+
+    for (file_path, required) in env_files(project_root, config, selection) {
+        if !file_path.exists() {
+            if required {
+                return Err(format!("Env file not found: {}", file_path.display()));
+            }
+            continue;
+        }
+
+        let entries = dotenvy::from_path_iter(&file_path)
+            .map_err(|e| format!("Failed to load {}: {}", file_path.display(), e))?;
+
+        for entry in entries {
+            let (key, value) =
+                entry.map_err(|e| format!("Failed to load {}: {}", file_path.display(), e))?;
+            variables.insert(key, value);
+        }
+    }
+
+    Ok(variables)
+}
+
+pub fn spawn_output_printer() -> (UnboundedSender<OutputEvent>, JoinHandle<()>) {
+    let (tx, mut rx) = unbounded_channel::<OutputEvent>();
+
+    let handle = tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            let _ = match event.stream {
+                OutputKind::Stdout => {
+                    let mut stdout = std::io::stdout();
+                    stdout
+                        .write_all(event.data.as_bytes())
+                        .and_then(|_| stdout.flush())
+                }
+                OutputKind::Stderr => {
+                    let mut stderr = std::io::stderr();
+                    stderr
+                        .write_all(event.data.as_bytes())
+                        .and_then(|_| stderr.flush())
+                }
+            };
+        }
+    });
+
+    (tx, handle)
+}
+
+pub fn route_output(
+    json: bool,
+    inherit_stdio: bool,
+    output_events: Option<UnboundedSender<OutputEvent>>,
+) -> (Option<UnboundedSender<OutputEvent>>, Option<JoinHandle<()>>) {
+    match output_events {
+        Some(tx) => (Some(tx), None),
+        None if !json && !inherit_stdio => {
+            let (tx, handle) = spawn_output_printer();
+            (Some(tx), Some(handle))
+        }
+        None => (None, None),
+    }
+}
 
-    for file_name in env_files {
-        let file_path = project_root.join(file_name);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if file_path.exists() {
-            dotenvy::from_path(&file_path)
-                .map_err(|e| format!("Failed to load {}: {}", file_path.display(), e))?;
+    fn config(files: Option<&[&str]>, profile: Option<&str>) -> EnvConfig {
+        EnvConfig {
+            files: files.map(|files| files.iter().map(|f| f.to_string()).collect()),
+            profile: profile.map(str::to_string),
         }
     }
 
-    Ok(())
+    fn names(files: Vec<(PathBuf, bool)>, root: &Path) -> Vec<(String, bool)> {
+        files
+            .into_iter()
+            .map(|(path, required)| {
+                let name = path
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string();
+                (name, required)
+            })
+            .collect()
+    }
+
+    fn write(dir: &Path, name: &str, content: &str) -> String {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_load_kwargs_inline_values() {
+        let kwargs = load_kwargs(
+            &[
+                "count=3".to_string(),
+                "strict=true".to_string(),
+                "tags=[\"a\",\"b\"]".to_string(),
+                "name=capsule".to_string(),
+                "quoted=\"42\"".to_string(),
+                "expr=a=b".to_string(),
+                "empty=".to_string(),
+            ],
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_json::Value::Object(kwargs),
+            serde_json::json!({
+                "count": 3,
+                "strict": true,
+                "tags": ["a", "b"],
+                "name": "capsule",
+                "quoted": "42",
+                "expr": "a=b",
+                "empty": "",
+            })
+        );
+        assert!(load_kwargs(&["novalue".to_string()], None).is_err());
+        assert!(load_kwargs(&["=value".to_string()], None).is_err());
+    }
+
+    #[test]
+    fn test_load_kwargs_from_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = write(
+            dir.path(),
+            "options.json",
+            r#"{"depth": 2, "mode": "fast"}"#,
+        );
+        let filters = write(dir.path(), "filters.json", r#"{"include": ["*.rs"]}"#);
+
+        let kwargs = load_kwargs(
+            &["mode=slow".to_string(), format!("filters=@{}", filters)],
+            Some(&options),
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_json::Value::Object(kwargs),
+            serde_json::json!({
+                "depth": 2,
+                "mode": "slow",
+                "filters": {"include": ["*.rs"]},
+            })
+        );
+    }
+
+    #[test]
+    fn test_load_kwargs_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let invalid = write(dir.path(), "invalid.json", "{not json");
+        let list = write(dir.path(), "list.json", "[1, 2]");
+
+        let err = load_kwargs(&[], Some(&invalid)).unwrap_err();
+        assert!(err.starts_with("Failed to parse --kwargs-file"), "{}", err);
+
+        let err = load_kwargs(&[], Some(&list)).unwrap_err();
+        assert!(err.ends_with("expected a JSON object"), "{}", err);
+
+        let err = load_kwargs(&[format!("data=@{}", invalid)], None).unwrap_err();
+        assert!(err.starts_with("Failed to parse --kwarg"), "{}", err);
+
+        let missing = dir.path().join("missing.json");
+        let err = load_kwargs(&[format!("data=@{}", missing.display())], None).unwrap_err();
+        assert!(err.starts_with("Failed to read --kwarg"), "{}", err);
+    }
+
+    #[test]
+    fn test_load_stdin_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input.bin");
+        std::fs::write(&input, b"rows\n\x00\xff").unwrap();
+
+        assert_eq!(
+            load_stdin(input.to_str()).unwrap(),
+            Some(b"rows\n\x00\xff".to_vec())
+        );
+
+        let empty = write(dir.path(), "empty.txt", "");
+        assert_eq!(load_stdin(Some(&empty)).unwrap(), None);
+
+        let missing = dir.path().join("missing.txt");
+        let err = load_stdin(missing.to_str()).unwrap_err();
+        assert!(err.starts_with("Failed to read --stdin-file"), "{}", err);
+
+        assert_eq!(load_stdin(None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_env_files_selection() {
+        let root = Path::new("/project");
+        let cli = EnvSelection {
+            files: vec!["ci.env".to_string()],
+            profile: Some("staging".to_string()),
+        };
+        let profile_only = EnvSelection {
+            files: Vec::new(),
+            profile: Some("staging".to_string()),
+        };
+
+        assert_eq!(
+            names(env_files(root, None, &EnvSelection::default()), root),
+            vec![
+                (".env".to_string(), false),
+                (".env.local".to_string(), false)
+            ]
+        );
+        assert_eq!(
+            names(
+                env_files(root, Some(&config(None, Some("production"))), &profile_only),
+                root
+            ),
+            vec![
+                (".env".to_string(), false),
+                (".env.local".to_string(), false),
+                (".env.staging".to_string(), false),
+                (".env.staging.local".to_string(), false),
+            ]
+        );
+        assert_eq!(
+            names(
+                env_files(
+                    root,
+                    Some(&config(None, Some("production"))),
+                    &EnvSelection::default()
+                ),
+                root
+            )[2..],
+            [
+                (".env.production".to_string(), false),
+                (".env.production.local".to_string(), false),
+            ]
+        );
+        assert_eq!(
+            names(
+                env_files(
+                    root,
+                    Some(&config(Some(&["shared.env"]), None)),
+                    &EnvSelection::default()
+                ),
+                root
+            ),
+            vec![("shared.env".to_string(), true)]
+        );
+        assert_eq!(
+            names(
+                env_files(root, Some(&config(Some(&["shared.env"]), None)), &cli),
+                root
+            ),
+            vec![("ci.env".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn test_load_env_variables_precedence() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join(".env"), "A=env\nB=env\n").unwrap();
+        std::fs::write(root.path().join(".env.local"), "B=local\n").unwrap();
+        std::fs::write(root.path().join(".env.production"), "C=production\n").unwrap();
+
+        let variables = load_env_variables(root.path(), None, &EnvSelection::default()).unwrap();
+
+        assert_eq!(variables.get("A").map(String::as_str), Some("env"));
+        assert_eq!(variables.get("B").map(String::as_str), Some("local"));
+        assert!(!variables.contains_key("C"));
+    }
+
+    #[test]
+    fn test_missing_explicit_env_file() {
+        let root = tempfile::tempdir().unwrap();
+        let selection = EnvSelection {
+            files: vec!["missing.env".to_string()],
+            profile: None,
+        };
+
+        let err = load_env_variables(root.path(), None, &selection).unwrap_err();
+        assert!(err.starts_with("Env file not found:"));
+        assert!(
+            load_env_variables(root.path(), None, &EnvSelection::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
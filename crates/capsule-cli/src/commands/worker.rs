@@ -5,13 +5,18 @@ use std::sync::Arc;
 
 use capsule_core::config::manifest::Manifest;
 use capsule_core::wasm::runtime::{Runtime, RuntimeConfig};
+use capsule_core::wasm::utilities::stdin_input::{DEFAULT_STDIN_LIMIT, decode_stdin};
+use capsule_core::wasm::utilities::stdio_capture::OutputEvent;
+use capsule_core::wasm::utilities::task_progress::ProgressEvent;
 use capsule_core::wasm::utilities::task_reporter::LogLevel;
 use serde::{Deserialize, Serialize};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc::unbounded_channel;
 use tokio::sync::{Mutex, mpsc};
 
-use crate::commands::exec::ExecError;
-use crate::commands::run::RunError;
+use crate::commands::exec::{ExecError, ExecOptions};
+use crate::commands::run::{RunError, RunOptions};
+use crate::commands::shared::EnvSelection;
 
 const RESPONSE_CHANNEL_SIZE: usize = 64;
 
@@ -43,7 +48,39 @@ pub struct WorkerRequest {
     #[serde(default)]
     pub args: Vec<String>,
     #[serde(default)]
+    pub kwargs: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
     pub mounts: Vec<String>,
+    #[serde(default)]
+    pub outputs_dir: Option<String>,
+    #[serde(default)]
+    pub stream_output: bool,
+    #[serde(default)]
+    pub stream_progress: bool,
+    #[serde(default)]
+    pub deterministic: bool,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub stdin: Option<String>,
+    #[serde(default)]
+    pub stdin_base64: Option<String>,
+}
+
+impl WorkerRequest {
+    fn run_options(&self, env: EnvSelection) -> RunOptions {
+        RunOptions {
+            args: self.args.clone(),
+            kwargs: self.kwargs.clone(),
+            mounts: self.mounts.clone(),
+            json: true,
+            outputs_dir: self.outputs_dir.as_ref().map(PathBuf::from),
+            env,
+            deterministic: self.deterministic,
+            seed: self.seed,
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -58,9 +95,11 @@ pub struct WorkerResponse {
 pub enum WorkerResult {
     Ok { output: serde_json::Value },
     Err { error: String },
+    Event { event: OutputEvent },
+    Progress { progress: ProgressEvent },
 }
 
-pub async fn execute() -> Result<(), WorkerError> {
+pub async fn execute(env: EnvSelection) -> Result<(), WorkerError> {
     let project_root = std::env::current_dir().unwrap_or_default();
     let cache_dir = project_root.join(".capsule");
     std::fs::create_dir_all(&cache_dir)?;
@@ -69,6 +108,7 @@ pub async fn execute() -> Result<(), WorkerError> {
     let runtime_config = RuntimeConfig {
         cache_dir,
         log_level: LogLevel::Silent,
+        ..Default::default()
     };
 
     let runtime = Runtime::new(runtime_config, capsule_toml)
@@ -100,7 +140,7 @@ pub async fn execute() -> Result<(), WorkerError> {
 
         let tx = tx.clone();
 
-        let request: WorkerRequest = match serde_json::from_str(&line) {
+        let mut request: WorkerRequest = match serde_json::from_str(&line) {
             Ok(r) => r,
             Err(e) => {
                 let _ = tx
@@ -117,16 +157,85 @@ pub async fn execute() -> Result<(), WorkerError> {
 
         let runtime = Arc::clone(&runtime);
         let wasm_cache = Arc::clone(&wasm_cache);
+        let env = env.clone();
 
         tokio::spawn(async move {
-            let result = dispatch(
-                request.file,
-                request.args,
-                request.mounts,
-                runtime,
-                wasm_cache,
-            )
-            .await;
+            let stdin = match decode_stdin(
+                request.stdin.take(),
+                request.stdin_base64.take(),
+                DEFAULT_STDIN_LIMIT,
+            ) {
+                Ok(stdin) => stdin,
+                Err(e) => {
+                    let _ = tx
+                        .send(WorkerResponse {
+                            id: request.id,
+                            result: WorkerResult::Err {
+                                error: e.to_string(),
+                            },
+                        })
+                        .await;
+                    return;
+                }
+            };
+
+            let (output_events, forwarder) = if request.stream_output {
+                let (events_tx, mut events_rx) = unbounded_channel::<OutputEvent>();
+                let tx = tx.clone();
+                let id = request.id.clone();
+
+                let forwarder = tokio::spawn(async move {
+                    while let Some(event) = events_rx.recv().await {
+                        let _ = tx
+                            .send(WorkerResponse {
+                                id: id.clone(),
+                                result: WorkerResult::Event { event },
+                            })
+                            .await;
+                    }
+                });
+
+                (Some(events_tx), Some(forwarder))
+            } else {
+                (None, None)
+            };
+
+            let (progress_events, progress_forwarder) = if request.stream_progress {
+                let (events_tx, mut events_rx) = unbounded_channel::<ProgressEvent>();
+                let tx = tx.clone();
+                let id = request.id.clone();
+
+                let forwarder = tokio::spawn(async move {
+                    while let Some(progress) = events_rx.recv().await {
+                        let _ = tx
+                            .send(WorkerResponse {
+                                id: id.clone(),
+                                result: WorkerResult::Progress { progress },
+                            })
+                            .await;
+                    }
+                });
+
+                (Some(events_tx), Some(forwarder))
+            } else {
+                (None, None)
+            };
+
+            let options = RunOptions {
+                output_events,
+                progress_events,
+                stdin,
+                ..request.run_options(env)
+            };
+
+            let result = dispatch(request.file, options, runtime, wasm_cache).await;
+
+            if let Some(forwarder) = forwarder {
+                let _ = forwarder.await;
+            }
+            if let Some(forwarder) = progress_forwarder {
+                let _ = forwarder.await;
+            }
 
             let response = WorkerResponse {
                 id: request.id,
@@ -150,8 +259,7 @@ pub async fn execute() -> Result<(), WorkerError> {
 
 async fn dispatch(
     file: String,
-    args: Vec<String>,
-    mounts: Vec<String>,
+    options: RunOptions,
     runtime: Arc<Runtime>,
     wasm_cache: Arc<Mutex<HashMap<String, PathBuf>>>,
 ) -> Result<String, String> {
@@ -164,17 +272,29 @@ async fn dispatch(
         .to_lowercase();
 
     match ext.as_str() {
-        "wasm" | "cwasm" => {
-            super::exec::execute(Path::new(&file), args, mounts, true, false, Some(runtime))
-                .await
-                .map_err(|e: ExecError| e.to_string())
-        }
+        "wasm" | "cwasm" => super::exec::execute(
+            Path::new(&file),
+            ExecOptions {
+                args: options.args,
+                kwargs: options.kwargs,
+                mounts: options.mounts,
+                json: true,
+                outputs_dir: options.outputs_dir,
+                env: options.env,
+                deterministic: options.deterministic,
+                seed: options.seed,
+                output_events: options.output_events,
+                progress_events: options.progress_events,
+                stdin: options.stdin,
+                ..Default::default()
+            },
+            Some(runtime),
+        )
+        .await
+        .map_err(|e: ExecError| e.to_string()),
         _ => super::run::execute(
             Some(Path::new(&file)),
-            args,
-            mounts,
-            true,
-            false,
+            options,
             Some(runtime),
             Some(wasm_cache),
         )
@@ -182,3 +302,43 @@ async fn dispatch(
         .map_err(|e: RunError| e.to_string()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_kwargs_reach_run_options() {
+        let request: WorkerRequest = serde_json::from_str(
+            r#"{
+                "id": "1",
+                "file": "main.py",
+                "args": ["input"],
+                "kwargs": {"count": 3, "name": "capsule", "options": {"tags": ["a"], "limit": null}}
+            }"#,
+        )
+        .unwrap();
+
+        let options = request.run_options(EnvSelection::default());
+
+        assert_eq!(options.args, vec!["input".to_string()]);
+        assert_eq!(
+            serde_json::Value::Object(options.kwargs),
+            serde_json::json!({
+                "count": 3,
+                "name": "capsule",
+                "options": {"tags": ["a"], "limit": null},
+            })
+        );
+        assert!(options.json);
+
+        let request: WorkerRequest =
+            serde_json::from_str(r#"{"id": "2", "file": "main.py"}"#).unwrap();
+        assert!(
+            request
+                .run_options(EnvSelection::default())
+                .kwargs
+                .is_empty()
+        );
+    }
+}
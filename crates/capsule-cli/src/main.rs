@@ -1,12 +1,15 @@
 pub mod cli;
 pub mod commands;
 
+use capsule_core::wasm::utilities::cassette::CassetteConfig;
 use clap::Parser;
 use std::fmt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use cli::{Cli, Commands};
-use commands::shared::load_args_file;
+use commands::exec::ExecOptions;
+use commands::run::RunOptions;
+use commands::shared::{EnvSelection, load_args_file, load_kwargs, load_stdin};
 use commands::{BuildError, ExecError, RunError, build, exec, run, worker};
 
 #[derive(Debug)]
@@ -54,6 +57,18 @@ async fn main() -> Result<(), CliError> {
             json,
             verbose,
             mount,
+            export_changes,
+            outputs_dir,
+            env_file,
+            profile,
+            inherit_stdio,
+            stdin_file,
+            deterministic,
+            seed,
+            record,
+            replay,
+            kwargs,
+            kwargs_file,
             args_file,
             args,
         } => {
@@ -61,8 +76,35 @@ async fn main() -> Result<(), CliError> {
                 Some(ref path) => load_args_file(path).map_err(CliError::RunError)?,
                 None => args,
             };
+            let kwargs =
+                load_kwargs(&kwargs, kwargs_file.as_deref()).map_err(CliError::RunError)?;
+            let stdin = load_stdin(stdin_file.as_deref()).map_err(CliError::RunError)?;
+            let cassette = match (record, replay) {
+                (Some(path), _) => Some(CassetteConfig::record(path)),
+                (None, Some(path)) => Some(CassetteConfig::replay(path)),
+                (None, None) => None,
+            };
             let file_path = file.as_deref().map(Path::new);
-            let result = run::execute(file_path, args, mount, json, verbose, None, None).await?;
+            let options = RunOptions {
+                args,
+                kwargs,
+                mounts: mount,
+                json,
+                verbose,
+                cassette,
+                export_changes: export_changes.map(PathBuf::from),
+                outputs_dir: outputs_dir.map(PathBuf::from),
+                env: EnvSelection {
+                    files: env_file,
+                    profile,
+                },
+                inherit_stdio,
+                deterministic,
+                seed,
+                stdin,
+                ..Default::default()
+            };
+            let result = run::execute(file_path, options, None, None).await?;
 
             if json {
                 println!("{}", result);
@@ -72,10 +114,13 @@ async fn main() -> Result<(), CliError> {
                 println!("{}", result);
             }
         }
-        Commands::Worker => {
-            worker::execute()
-                .await
-                .map_err(|e| CliError::RunError(e.to_string()))?;
+        Commands::Worker { env_file, profile } => {
+            worker::execute(EnvSelection {
+                files: env_file,
+                profile,
+            })
+            .await
+            .map_err(|e| CliError::RunError(e.to_string()))?;
         }
         Commands::Build { file, export } => {
             let file_path = file.as_deref().map(Path::new);
@@ -86,6 +131,15 @@ async fn main() -> Result<(), CliError> {
             json,
             verbose,
             mount,
+            outputs_dir,
+            env_file,
+            profile,
+            inherit_stdio,
+            stdin_file,
+            deterministic,
+            seed,
+            kwargs,
+            kwargs_file,
             args_file,
             args,
         } => {
@@ -93,7 +147,27 @@ async fn main() -> Result<(), CliError> {
                 Some(ref path) => load_args_file(path).map_err(CliError::ExecError)?,
                 None => args,
             };
-            let result = exec::execute(Path::new(&file), args, mount, json, verbose, None).await?;
+            let kwargs =
+                load_kwargs(&kwargs, kwargs_file.as_deref()).map_err(CliError::ExecError)?;
+            let stdin = load_stdin(stdin_file.as_deref()).map_err(CliError::ExecError)?;
+            let options = ExecOptions {
+                args,
+                kwargs,
+                mounts: mount,
+                json,
+                verbose,
+                outputs_dir: outputs_dir.map(PathBuf::from),
+                env: EnvSelection {
+                    files: env_file,
+                    profile,
+                },
+                inherit_stdio,
+                deterministic,
+                seed,
+                stdin,
+                ..Default::default()
+            };
+            let result = exec::execute(Path::new(&file), options, None).await?;
 
             if json {
                 println!("{}", result);
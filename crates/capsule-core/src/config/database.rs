@@ -167,6 +167,18 @@ impl Database {
         Ok(count > 0)
     }
 
+    pub fn column_exists(&self, table: &str, column: &str) -> Result<bool, DatabaseError> {
+        let conn = self.conn.lock()?;
+
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info(?) WHERE name=?",
+            [table, column],
+            |row| row.get(0),
+        )?;
+
+        Ok(count > 0)
+    }
+
     pub fn validate_table_name(&self, table: &str) -> Result<(), DatabaseError> {
         if table.is_empty() || table.len() > 64 {
             return Err(DatabaseError::InvalidQuery(
@@ -379,6 +391,18 @@ mod tests {
             assert!(table_exists, "test table was not created");
         }
 
+        #[test]
+        fn test_column_exists() {
+            let db = Database::new(None, "trace.db-wal").expect("Failed to create database");
+            db.create_table("test_table", &["name TEXT NOT NULL"], &[])
+                .expect("Failed to create test table");
+
+            assert!(db.column_exists("test_table", "name").unwrap());
+            assert!(db.column_exists("test_table", "created_at").unwrap());
+            assert!(!db.column_exists("test_table", "missing").unwrap());
+            assert!(!db.column_exists("missing_table", "name").unwrap());
+        }
+
         #[test]
         fn test_validate_table_name() {
             let db = Database::new(None, "trace.db-wal").expect("Failed to create database");
@@ -0,0 +1,330 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use nanoid::nanoid;
+use rusqlite::{OptionalExtension, params};
+
+use crate::config::database::{Database, DatabaseError};
+
+pub const MAX_KEY_BYTES: usize = 512;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum KvError {
+    AccessDenied(String),
+    QuotaExceeded(u64),
+    InvalidKey(String),
+    DatabaseError(String),
+}
+
+impl fmt::Display for KvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KvError::AccessDenied(namespace) => {
+                write!(f, "KV error > Namespace '{}' is not granted", namespace)
+            }
+            KvError::QuotaExceeded(limit) => {
+                write!(f, "KV error > Namespace exceeds the {} byte quota", limit)
+            }
+            KvError::InvalidKey(msg) => write!(f, "KV error > Invalid key > {}", msg),
+            KvError::DatabaseError(msg) => write!(f, "KV error > {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for KvError {}
+
+impl From<DatabaseError> for KvError {
+    fn from(err: DatabaseError) -> Self {
+        KvError::DatabaseError(err.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for KvError {
+    fn from(err: rusqlite::Error) -> Self {
+        KvError::DatabaseError(err.to_string())
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+fn expires_at(ttl_ms: Option<u64>) -> Option<i64> {
+    ttl_ms.map(|ttl| now_ms().saturating_add(ttl.min(i64::MAX as u64) as i64))
+}
+
+fn validate_key(key: &str) -> Result<(), KvError> {
+    if key.is_empty() {
+        return Err(KvError::InvalidKey("key is empty".to_string()));
+    }
+    if key.len() > MAX_KEY_BYTES {
+        return Err(KvError::InvalidKey(format!(
+            "key is longer than {} bytes",
+            MAX_KEY_BYTES
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct KvStore {
+    db: Database,
+}
+
+impl KvStore {
+    pub fn new(db: Database) -> Result<Self, KvError> {
+        if !db.table_exists("kv_store")? {
+            db.create_table(
+                "kv_store",
+                &[
+                    "namespace TEXT NOT NULL",
+                    "key TEXT NOT NULL",
+                    "value BLOB NOT NULL",
+                    "expires_at INTEGER",
+                ],
+                &["UNIQUE(namespace, key)"],
+            )?;
+        }
+
+        Ok(Self { db })
+    }
+
+    fn purge_expired(&self, namespace: &str) -> Result<(), KvError> {
+        self.db.execute(
+            "DELETE FROM kv_store WHERE namespace = ?1 AND expires_at IS NOT NULL AND expires_at <= ?2",
+            params![namespace, now_ms()],
+        )?;
+        Ok(())
+    }
+
+    pub fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>, KvError> {
+        self.purge_expired(namespace)?;
+
+        let rows = self.db.query(
+            "SELECT value FROM kv_store WHERE namespace = ?1 AND key = ?2",
+            params![namespace, key],
+            |row| Ok(row.get::<_, Vec<u8>>(0)?),
+        )?;
+
+        Ok(rows.into_iter().next())
+    }
+
+    pub fn delete(&self, namespace: &str, key: &str) -> Result<bool, KvError> {
+        self.purge_expired(namespace)?;
+
+        let deleted = self.db.execute(
+            "DELETE FROM kv_store WHERE namespace = ?1 AND key = ?2",
+            params![namespace, key],
+        )?;
+
+        Ok(deleted > 0)
+    }
+
+    pub fn list(&self, namespace: &str, prefix: &str) -> Result<Vec<String>, KvError> {
+        self.purge_expired(namespace)?;
+
+        let keys = self.db.query(
+            "SELECT key FROM kv_store WHERE namespace = ?1 AND substr(key, 1, length(?2)) = ?2 ORDER BY key",
+            params![namespace, prefix],
+            |row| Ok(row.get::<_, String>(0)?),
+        )?;
+
+        Ok(keys)
+    }
+
+    pub fn usage(&self, namespace: &str) -> Result<u64, KvError> {
+        self.purge_expired(namespace)?;
+
+        let usage = self.db.query(
+            "SELECT COALESCE(SUM(length(key) + length(value)), 0) FROM kv_store WHERE namespace = ?1",
+            params![namespace],
+            |row| Ok(row.get::<_, i64>(0)?),
+        )?;
+
+        Ok(usage.into_iter().next().unwrap_or_default() as u64)
+    }
+
+    pub fn set(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &[u8],
+        ttl_ms: Option<u64>,
+        max_bytes: Option<u64>,
+    ) -> Result<(), KvError> {
+        self.write(namespace, key, None, value, ttl_ms, max_bytes)
+            .map(|_| ())
+    }
+
+    pub fn compare_and_swap(
+        &self,
+        namespace: &str,
+        key: &str,
+        expected: Option<&[u8]>,
+        value: &[u8],
+        ttl_ms: Option<u64>,
+        max_bytes: Option<u64>,
+    ) -> Result<bool, KvError> {
+        self.write(namespace, key, Some(expected), value, ttl_ms, max_bytes)
+    }
+
+    fn write(
+        &self,
+        namespace: &str,
+        key: &str,
+        expected: Option<Option<&[u8]>>,
+        value: &[u8],
+        ttl_ms: Option<u64>,
+        max_bytes: Option<u64>,
+    ) -> Result<bool, KvError> {
+        validate_key(key)?;
+
+        let now = now_ms();
+        let mut conn = self.db.conn.lock().map_err(DatabaseError::from)?;
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "DELETE FROM kv_store WHERE namespace = ?1 AND expires_at IS NOT NULL AND expires_at <= ?2",
+            params![namespace, now],
+        )?;
+
+        let current: Option<Vec<u8>> = tx
+            .query_row(
+                "SELECT value FROM kv_store WHERE namespace = ?1 AND key = ?2",
+                params![namespace, key],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        if let Some(expected) = expected
+            && current.as_deref() != expected
+        {
+            return Ok(false);
+        }
+
+        if let Some(limit) = max_bytes {
+            let used: i64 = tx.query_row(
+                "SELECT COALESCE(SUM(length(key) + length(value)), 0) FROM kv_store WHERE namespace = ?1 AND key != ?2",
+                params![namespace, key],
+                |row| row.get(0),
+            )?;
+
+            if used as u64 + (key.len() + value.len()) as u64 > limit {
+                return Err(KvError::QuotaExceeded(limit));
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO kv_store (id, namespace, key, value, expires_at) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(namespace, key) DO UPDATE SET
+                value = excluded.value,
+                expires_at = excluded.expires_at,
+                updated_at = CURRENT_TIMESTAMP",
+            params![nanoid!(10), namespace, key, value, expires_at(ttl_ms)],
+        )?;
+
+        tx.commit()?;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> KvStore {
+        KvStore::new(Database::new(None, "trace.db-wal").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_set_get_delete_list() {
+        let kv = store();
+
+        kv.set("jobs", "run/1", b"done", None, None).unwrap();
+        kv.set("jobs", "run/2", b"pending", None, None).unwrap();
+        kv.set("jobs", "other", b"x", None, None).unwrap();
+        kv.set("cache", "run/1", b"cached", None, None).unwrap();
+
+        assert_eq!(kv.get("jobs", "run/1").unwrap(), Some(b"done".to_vec()));
+        assert_eq!(kv.get("cache", "run/1").unwrap(), Some(b"cached".to_vec()));
+        assert_eq!(kv.get("jobs", "missing").unwrap(), None);
+        assert_eq!(kv.list("jobs", "run/").unwrap(), vec!["run/1", "run/2"]);
+        assert_eq!(kv.list("jobs", "").unwrap().len(), 3);
+
+        kv.set("jobs", "run/2", b"done", None, None).unwrap();
+        assert_eq!(kv.get("jobs", "run/2").unwrap(), Some(b"done".to_vec()));
+
+        assert!(kv.delete("jobs", "run/1").unwrap());
+        assert!(!kv.delete("jobs", "run/1").unwrap());
+        assert_eq!(kv.list("jobs", "run/").unwrap(), vec!["run/2"]);
+
+        assert!(matches!(
+            kv.set("jobs", "", b"x", None, None),
+            Err(KvError::InvalidKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_compare_and_swap() {
+        let kv = store();
+
+        assert!(
+            kv.compare_and_swap("locks", "leader", None, b"a", None, None)
+                .unwrap()
+        );
+        assert!(
+            !kv.compare_and_swap("locks", "leader", None, b"b", None, None)
+                .unwrap()
+        );
+        assert!(
+            !kv.compare_and_swap("locks", "leader", Some(b"b"), b"c", None, None)
+                .unwrap()
+        );
+        assert!(
+            kv.compare_and_swap("locks", "leader", Some(b"a"), b"c", None, None)
+                .unwrap()
+        );
+        assert_eq!(kv.get("locks", "leader").unwrap(), Some(b"c".to_vec()));
+    }
+
+    #[test]
+    fn test_ttl_expiry() {
+        let kv = store();
+
+        kv.set("cache", "short", b"gone", Some(0), None).unwrap();
+        kv.set("cache", "long", b"kept", Some(60_000), None)
+            .unwrap();
+
+        assert_eq!(kv.get("cache", "short").unwrap(), None);
+        assert_eq!(kv.get("cache", "long").unwrap(), Some(b"kept".to_vec()));
+        assert_eq!(kv.list("cache", "").unwrap(), vec!["long"]);
+
+        kv.set("cache", "lease", b"a", Some(0), None).unwrap();
+        assert!(
+            kv.compare_and_swap("cache", "lease", None, b"b", None, None)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_quota() {
+        let kv = store();
+
+        kv.set("small", "a", b"1234", None, Some(10)).unwrap();
+        kv.set("small", "a", b"123456789", None, Some(10)).unwrap();
+        assert_eq!(kv.usage("small").unwrap(), 10);
+
+        assert_eq!(
+            kv.set("small", "b", b"1", None, Some(10)),
+            Err(KvError::QuotaExceeded(10))
+        );
+        assert_eq!(kv.get("small", "b").unwrap(), None);
+
+        kv.set("small", "b", b"1", None, None).unwrap();
+        assert_eq!(kv.usage("small").unwrap(), 12);
+    }
+}
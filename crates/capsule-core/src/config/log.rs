@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fmt;
 use std::sync::mpsc;
 use std::thread::{Builder, JoinHandle};
@@ -7,6 +8,8 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::oneshot;
 
 use crate::config::database::{Database, DatabaseError};
+use crate::wasm::filesystem::audit::FileAccess;
+use crate::wasm::utilities::guest_log::GuestLogEntry;
 use crate::wasm::utilities::task_config::HostRequest;
 
 #[derive(Debug)]
@@ -80,6 +83,8 @@ pub struct InstanceLog {
     pub fuel_consumed: u64,
     pub ram_used: u64,
     pub host_requests: Vec<HostRequest>,
+    pub metrics: BTreeMap<String, f64>,
+    pub seed: Option<u64>,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -95,6 +100,7 @@ pub struct CreateInstanceLog {
     pub fuel_consumed: u64,
     pub ram_used: u64,
     pub host_requests: Vec<HostRequest>,
+    pub seed: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,6 +110,7 @@ pub struct UpdateInstanceLog {
     pub fuel_consumed: u64,
     pub ram_used: u64,
     pub host_requests: Vec<HostRequest>,
+    pub metrics: BTreeMap<String, f64>,
 }
 
 enum LogCommand {
@@ -116,6 +123,24 @@ enum LogCommand {
         log: UpdateInstanceLog,
         response: tokio::sync::oneshot::Sender<Result<(), LogError>>,
     },
+
+    State {
+        task_id: String,
+        state: InstanceState,
+        response: tokio::sync::oneshot::Sender<Result<(), LogError>>,
+    },
+
+    FileAccess {
+        task_id: String,
+        accesses: Vec<FileAccess>,
+        response: tokio::sync::oneshot::Sender<Result<(), LogError>>,
+    },
+
+    GuestLogs {
+        task_id: String,
+        entries: Vec<GuestLogEntry>,
+        response: tokio::sync::oneshot::Sender<Result<(), LogError>>,
+    },
 }
 
 #[derive()]
@@ -156,6 +181,8 @@ impl Log {
                     "fuel_consumed INTEGER NOT NULL",
                     "ram_used INTEGER NOT NULL",
                     "host_requests TEXT NOT NULL",
+                    "metrics TEXT NOT NULL DEFAULT '{}'",
+                    "seed TEXT",
                 ],
                 &[],
             )?;
@@ -171,6 +198,43 @@ impl Log {
             )?;
         }
 
+        if !db.column_exists("instance_log", "metrics")? {
+            db.execute(
+                "ALTER TABLE instance_log ADD COLUMN metrics TEXT NOT NULL DEFAULT '{}'",
+                [],
+            )?;
+        }
+
+        if !db.column_exists("instance_log", "seed")? {
+            db.execute("ALTER TABLE instance_log ADD COLUMN seed TEXT", [])?;
+        }
+
+        if !db.table_exists("file_access_log")? {
+            db.create_table(
+                "file_access_log",
+                &["task_id TEXT NOT NULL", "accesses TEXT NOT NULL"],
+                &[],
+            )?;
+
+            db.execute(
+                "CREATE INDEX IF NOT EXISTS idx_file_access_log_task_id ON file_access_log(task_id)",
+                [],
+            )?;
+        }
+
+        if !db.table_exists("guest_log")? {
+            db.create_table(
+                "guest_log",
+                &["task_id TEXT NOT NULL", "entries TEXT NOT NULL"],
+                &[],
+            )?;
+
+            db.execute(
+                "CREATE INDEX IF NOT EXISTS idx_guest_log_task_id ON guest_log(task_id)",
+                [],
+            )?;
+        }
+
         Ok(())
     }
 
@@ -198,24 +262,49 @@ impl Log {
                     let result = Self::execute_update(&db, log);
                     let _ = response.send(result);
                 }
+                LogCommand::State {
+                    task_id,
+                    state,
+                    response,
+                } => {
+                    let result = Self::execute_update_state(&db, &task_id, state);
+                    let _ = response.send(result);
+                }
+                LogCommand::FileAccess {
+                    task_id,
+                    accesses,
+                    response,
+                } => {
+                    let result = Self::execute_file_access(&db, &task_id, &accesses);
+                    let _ = response.send(result);
+                }
+                LogCommand::GuestLogs {
+                    task_id,
+                    entries,
+                    response,
+                } => {
+                    let result = Self::execute_guest_logs(&db, &task_id, &entries);
+                    let _ = response.send(result);
+                }
             }
         }
     }
 
     fn execute_create(db: &Database, log: CreateInstanceLog) -> Result<(), LogError> {
         db.execute(
-            "INSERT INTO instance_log (id, agent_name, agent_version, task_id, task_name, state, fuel_limit, fuel_consumed, ram_used, host_requests) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-            [
-                &nanoid!(10),
-                &log.agent_name,
-                &log.agent_version,
-                &log.task_id,
-                &log.task_name,
-                &log.state.to_string(),
-                &log.fuel_limit.to_string(),
-                &log.fuel_consumed.to_string(),
-                &log.ram_used.to_string(),
-                &serde_json::to_string(&log.host_requests).unwrap_or_default(),
+            "INSERT INTO instance_log (id, agent_name, agent_version, task_id, task_name, state, fuel_limit, fuel_consumed, ram_used, host_requests, seed) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            rusqlite::params![
+                nanoid!(10),
+                log.agent_name,
+                log.agent_version,
+                log.task_id,
+                log.task_name,
+                log.state.to_string(),
+                log.fuel_limit.to_string(),
+                log.fuel_consumed.to_string(),
+                log.ram_used.to_string(),
+                serde_json::to_string(&log.host_requests).unwrap_or_default(),
+                log.seed.map(|seed| seed.to_string()),
             ],
         )?;
 
@@ -224,12 +313,13 @@ impl Log {
 
     fn execute_update(db: &Database, log: UpdateInstanceLog) -> Result<(), LogError> {
         db.execute(
-            "UPDATE instance_log SET state = ?, fuel_consumed = ?, ram_used = ?, host_requests = ? WHERE task_id = ?",
+            "UPDATE instance_log SET state = ?, fuel_consumed = ?, ram_used = ?, host_requests = ?, metrics = ? WHERE task_id = ?",
             [
                 &log.state.to_string(),
                 &log.fuel_consumed.to_string(),
                 &log.ram_used.to_string(),
                 &serde_json::to_string(&log.host_requests).unwrap_or_default(),
+                &serde_json::to_string(&log.metrics).unwrap_or_default(),
                 &log.task_id,
             ],
         )?;
@@ -237,6 +327,53 @@ impl Log {
         Ok(())
     }
 
+    fn execute_update_state(
+        db: &Database,
+        task_id: &str,
+        state: InstanceState,
+    ) -> Result<(), LogError> {
+        db.execute(
+            "UPDATE instance_log SET state = ? WHERE task_id = ?",
+            [state.to_string().as_str(), task_id],
+        )?;
+
+        Ok(())
+    }
+
+    fn execute_file_access(
+        db: &Database,
+        task_id: &str,
+        accesses: &[FileAccess],
+    ) -> Result<(), LogError> {
+        db.execute(
+            "INSERT INTO file_access_log (id, task_id, accesses) VALUES (?, ?, ?)",
+            [
+                nanoid!(10).as_str(),
+                task_id,
+                serde_json::to_string(accesses).unwrap_or_default().as_str(),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn execute_guest_logs(
+        db: &Database,
+        task_id: &str,
+        entries: &[GuestLogEntry],
+    ) -> Result<(), LogError> {
+        db.execute(
+            "INSERT INTO guest_log (id, task_id, entries) VALUES (?, ?, ?)",
+            [
+                nanoid!(10).as_str(),
+                task_id,
+                serde_json::to_string(entries).unwrap_or_default().as_str(),
+            ],
+        )?;
+
+        Ok(())
+    }
+
     pub async fn commit_log(&self, log: CreateInstanceLog) -> Result<(), LogError> {
         let (tx, rx) = oneshot::channel();
 
@@ -253,9 +390,81 @@ impl Log {
         rx.await?
     }
 
+    pub async fn update_state(&self, task_id: &str, state: InstanceState) -> Result<(), LogError> {
+        let (tx, rx) = oneshot::channel();
+
+        self.log_tx.send(LogCommand::State {
+            task_id: task_id.to_string(),
+            state,
+            response: tx,
+        })?;
+
+        rx.await?
+    }
+
+    pub async fn record_file_access(
+        &self,
+        task_id: &str,
+        accesses: Vec<FileAccess>,
+    ) -> Result<(), LogError> {
+        let (tx, rx) = oneshot::channel();
+
+        self.log_tx.send(LogCommand::FileAccess {
+            task_id: task_id.to_string(),
+            accesses,
+            response: tx,
+        })?;
+
+        rx.await?
+    }
+
+    pub fn get_file_access(&self, task_id: &str) -> Result<Vec<FileAccess>, LogError> {
+        let batches = self.db.query(
+            "SELECT accesses FROM file_access_log WHERE task_id = ? ORDER BY created_at",
+            [task_id],
+            |row| {
+                Ok(serde_json::from_str::<Vec<FileAccess>>(
+                    &row.get::<_, String>(0)?,
+                )?)
+            },
+        )?;
+
+        Ok(batches.into_iter().flatten().collect())
+    }
+
+    pub async fn record_guest_logs(
+        &self,
+        task_id: &str,
+        entries: Vec<GuestLogEntry>,
+    ) -> Result<(), LogError> {
+        let (tx, rx) = oneshot::channel();
+
+        self.log_tx.send(LogCommand::GuestLogs {
+            task_id: task_id.to_string(),
+            entries,
+            response: tx,
+        })?;
+
+        rx.await?
+    }
+
+    pub fn get_guest_logs(&self, task_id: &str) -> Result<Vec<GuestLogEntry>, LogError> {
+        let batches = self.db.query(
+            "SELECT entries FROM guest_log WHERE task_id = ? ORDER BY created_at",
+            [task_id],
+            |row| {
+                Ok(serde_json::from_str::<Vec<GuestLogEntry>>(
+                    &row.get::<_, String>(0)?,
+                )?)
+            },
+        )?;
+
+        Ok(batches.into_iter().flatten().collect())
+    }
+
     pub fn get_logs(&self) -> Result<Vec<InstanceLog>, LogError> {
         let logs = self.db.query(
-            "SELECT id, agent_name, agent_version, task_id, task_name, state, fuel_limit, fuel_consumed, ram_used, host_requests, created_at, updated_at FROM instance_log ORDER BY created_at DESC",
+            "SELECT id, agent_name, agent_version, task_id, task_name, state, fuel_limit, fuel_consumed, ram_used, host_requests, created_at, updated_at, metrics, seed FROM instance_log ORDER BY created_at DESC",
             [],
             |row| {
                 let id_str: String = row.get(0)?;
@@ -278,6 +487,8 @@ impl Log {
                     fuel_consumed: row.get::<_, i64>(7)? as u64,
                     ram_used: row.get::<_, i64>(8)? as u64,
                     host_requests: serde_json::from_str::<Vec<HostRequest>>(&row.get::<_, String>(9)?)? as Vec<HostRequest>,
+                    metrics: serde_json::from_str(&row.get::<_, String>(12)?)?,
+                    seed: row.get::<_, Option<String>>(13)?.and_then(|seed| seed.parse().ok()),
                     created_at: row.get(10)?,
                     updated_at: row.get(11)?,
                 })
@@ -302,6 +513,10 @@ impl Log {
     pub fn delete_log(&self, task_id: &str) -> Result<(), LogError> {
         self.db
             .execute("DELETE FROM instance_log WHERE task_id = ?", [task_id])?;
+        self.db
+            .execute("DELETE FROM file_access_log WHERE task_id = ?", [task_id])?;
+        self.db
+            .execute("DELETE FROM guest_log WHERE task_id = ?", [task_id])?;
 
         Ok(())
     }
@@ -368,6 +583,7 @@ mod tests {
                     fuel_consumed: 0,
                     ram_used: 0,
                     host_requests: Vec::new(),
+                    seed: Some(u64::MAX),
                 })
                 .await
                 .expect("Failed to commit log");
@@ -381,7 +597,16 @@ mod tests {
 
             let exists = stmt.exists([]).expect("Failed to check if instance exists");
 
+            let seed: String = conn
+                .query_row(
+                    "SELECT seed FROM instance_log WHERE task_id = 'task_id'",
+                    [],
+                    |row| row.get(0),
+                )
+                .expect("Failed to query seed");
+
             assert!(exists, "instance does not exist");
+            assert_eq!(seed, u64::MAX.to_string(), "Seed should be stored");
         }
     }
 
@@ -416,6 +641,7 @@ mod tests {
                     fuel_consumed: 10,
                     ram_used: 1500,
                     host_requests: Vec::new(),
+                    metrics: BTreeMap::from([("tokens".to_string(), 512.0)]),
                 })
                 .await
                 .expect("Failed to update log");
@@ -455,10 +681,94 @@ mod tests {
                 )
                 .expect("Failed to query host_requests");
 
+            let metrics: String = conn
+                .query_row(
+                    "SELECT metrics FROM instance_log WHERE task_id = 'test_task_123'",
+                    [],
+                    |row| row.get(0),
+                )
+                .expect("Failed to query metrics");
+
             assert_eq!(state, "running", "State should be updated to running");
             assert_eq!(fuel_consumed, 10, "Fuel consumed should be updated to 10");
             assert_eq!(ram_used, 1500, "Ram used should be updated to 1500");
             assert_eq!(host_requests, "[]", "Host requests should be updated to []");
+            assert_eq!(metrics, r#"{"tokens":512.0}"#, "Metrics should be updated");
+        }
+
+        #[test]
+        fn test_update_state_keeps_usage() {
+            let log = Log::new(None, "trace.db-wal").unwrap();
+
+            {
+                let conn = log.db.conn.lock().unwrap();
+
+                conn.execute("INSERT INTO instance_log (id, agent_name, agent_version, task_id, task_name, state, fuel_limit, fuel_consumed, ram_used, host_requests, metrics, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)", [
+                    &nanoid!(10),
+                    "test_agent",
+                    "1.0.0",
+                    "test_task_123",
+                    "Test Task",
+                    "running",
+                    "15000000",
+                    "10",
+                    "1500",
+                    r#"[{"method":"GET","url":"https://example.com"}]"#,
+                    r#"{"tokens":512.0}"#,
+                    "1000",
+                    "1000",
+                ]).expect("Failed to insert test data");
+            }
+
+            run_async(async {
+                log.update_state("test_task_123", InstanceState::Interrupted)
+                    .await
+                    .expect("Failed to update state");
+            });
+
+            let logs = log.get_logs().unwrap();
+
+            assert_eq!(logs.len(), 1);
+            assert_eq!(logs[0].state, InstanceState::Interrupted);
+            assert_eq!(logs[0].fuel_consumed, 10);
+            assert_eq!(logs[0].ram_used, 1500);
+            assert_eq!(logs[0].host_requests.len(), 1);
+            assert_eq!(logs[0].metrics.get("tokens"), Some(&512.0));
+        }
+
+        #[test]
+        fn test_column_migrations() {
+            let db = Database::new(None, "trace.db-wal").unwrap();
+
+            db.create_table(
+                "instance_log",
+                &[
+                    "agent_name TEXT NOT NULL",
+                    "agent_version TEXT NOT NULL",
+                    "task_id TEXT NOT NULL",
+                    "task_name TEXT NOT NULL",
+                    "state TEXT NOT NULL",
+                    "fuel_limit INTEGER NOT NULL",
+                    "fuel_consumed INTEGER NOT NULL",
+                    "ram_used INTEGER NOT NULL",
+                    "host_requests TEXT NOT NULL",
+                ],
+                &[],
+            )
+            .unwrap();
+
+            assert!(!db.column_exists("instance_log", "metrics").unwrap());
+
+            Log::ensure_schema(&db).expect("Failed to migrate schema");
+
+            assert!(
+                db.column_exists("instance_log", "metrics").unwrap(),
+                "Metrics column should be added to existing logs"
+            );
+            assert!(
+                db.column_exists("instance_log", "seed").unwrap(),
+                "Seed column should be added to existing logs"
+            );
         }
     }
 
@@ -835,7 +1145,9 @@ mod tests {
                     method: "GET".to_string(),
                     url: "http://example.com".to_string(),
                     headers: None,
-                    body: None
+                    body: None,
+                    status: Some(200),
+                    ..Default::default()
                 }],
                 "First log host_requests should be []"
             );
@@ -938,5 +1250,80 @@ mod tests {
                 "other_task_456 log should have agent_name other_agent"
             );
         }
+
+        #[test]
+        fn test_file_access_log() {
+            use crate::wasm::filesystem::audit::FileOperation;
+
+            let log = Log::new(None, "trace.db-wal").unwrap();
+
+            let accesses = vec![
+                FileAccess {
+                    operation: FileOperation::Open,
+                    path: "/data/input.csv".to_string(),
+                    bytes: None,
+                    target: None,
+                },
+                FileAccess {
+                    operation: FileOperation::Read,
+                    path: "/data/input.csv".to_string(),
+                    bytes: Some(128),
+                    target: None,
+                },
+            ];
+
+            run_async(async {
+                log.record_file_access("task_id", accesses.clone())
+                    .await
+                    .expect("Failed to record file access");
+            });
+
+            assert_eq!(
+                log.get_file_access("task_id").unwrap(),
+                accesses,
+                "File accesses should round-trip"
+            );
+            assert!(
+                log.get_file_access("other_task").unwrap().is_empty(),
+                "Other tasks should have no file accesses"
+            );
+
+            log.delete_log("task_id").unwrap();
+            assert!(
+                log.get_file_access("task_id").unwrap().is_empty(),
+                "Deleting a log should remove its file accesses"
+            );
+        }
+
+        #[test]
+        fn test_guest_log() {
+            use crate::wasm::utilities::guest_log::GuestLogLevel;
+
+            let log = Log::new(None, "trace.db-wal").unwrap();
+
+            let entries = vec![GuestLogEntry::new(
+                GuestLogLevel::Info,
+                "fetched page".to_string(),
+                vec![("status".to_string(), "200".to_string())],
+            )];
+
+            run_async(async {
+                log.record_guest_logs("task_id", entries.clone())
+                    .await
+                    .expect("Failed to record guest logs");
+            });
+
+            assert_eq!(
+                log.get_guest_logs("task_id").unwrap(),
+                entries,
+                "Guest logs should round-trip"
+            );
+
+            log.delete_log("task_id").unwrap();
+            assert!(
+                log.get_guest_logs("task_id").unwrap().is_empty(),
+                "Deleting a log should remove its guest logs"
+            );
+        }
     }
 }
@@ -1,9 +1,11 @@
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::wasm::execution_policy::Compute;
+use crate::wasm::utilities::env_resolver::EnvVariables;
 
 #[derive(Debug)]
 pub enum ManifestError {
@@ -37,13 +39,50 @@ pub struct DefaultPolicy {
     pub default_max_retries: Option<u64>,
     pub default_allowed_files: Option<Vec<String>>,
     pub default_allowed_hosts: Option<Vec<String>>,
-    pub default_env_variables: Option<Vec<String>>,
+    pub default_env_variables: Option<EnvVariables>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpMock {
+    pub method: Option<String>,
+    pub url: String,
+    pub status: Option<u16>,
+    pub headers: Option<HashMap<String, String>>,
+    pub body: Option<String>,
+    pub body_file: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NetworkConfig {
+    pub proxy: Option<String>,
+    pub no_proxy: Option<Vec<String>>,
+    pub ca_bundle: Option<String>,
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    pub body_capture_limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FilesystemConfig {
+    pub allowed_roots: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EnvConfig {
+    pub files: Option<Vec<String>>,
+    pub profile: Option<String>,
 }
 
 #[derive(Debug, Default, Deserialize)]
 pub struct CapsuleToml {
     pub workflow: Option<Workflow>,
     pub tasks: Option<DefaultPolicy>,
+    pub network: Option<NetworkConfig>,
+    pub filesystem: Option<FilesystemConfig>,
+    pub env: Option<EnvConfig>,
+
+    #[serde(default)]
+    pub mocks: Vec<HttpMock>,
 }
 
 pub struct Manifest {
@@ -69,10 +108,7 @@ impl Manifest {
                     ManifestError::ParseError(format!("Failed to parse capsule.toml: {}", e))
                 })?
             }
-            None => CapsuleToml {
-                workflow: None,
-                tasks: None,
-            },
+            None => CapsuleToml::default(),
         };
 
         Ok(Self {
@@ -1,4 +1,5 @@
 pub mod database;
 pub mod fingerprint;
+pub mod kv;
 pub mod log;
 pub mod manifest;
@@ -1,19 +1,37 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use nanoid::nanoid;
+use tokio::sync::Semaphore;
 
 use wasmtime::component::{Linker, ResourceTable};
 use wasmtime::{Store, StoreLimitsBuilder};
 use wasmtime_wasi::add_to_linker_async;
+use wasmtime_wasi::pipe::MemoryInputPipe;
 use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};
 use wasmtime_wasi_http::WasiHttpCtx;
 
 use crate::config::log::{CreateInstanceLog, InstanceState, UpdateInstanceLog};
 use crate::wasm::execution_policy::ExecutionPolicy;
+use crate::wasm::filesystem;
+use crate::wasm::filesystem::archive::ArchiveMount;
+use crate::wasm::filesystem::outputs::OutputsDir;
+use crate::wasm::filesystem::overlay::OverlayMount;
+use crate::wasm::filesystem::sandbox::SandboxFs;
+use crate::wasm::filesystem::scratch::ScratchDir;
 use crate::wasm::runtime::{Runtime, RuntimeCommand, WasmRuntimeError};
-use crate::wasm::state::{CapsuleAgent, State, capsule};
+use crate::wasm::state::{State, capsule};
+use crate::wasm::utilities::deterministic::{configure_wasi, generate_seed};
+use crate::wasm::utilities::env_resolver::resolve_env;
+use crate::wasm::utilities::guest_log::GuestLogs;
+use crate::wasm::utilities::host_sql::SqlDatabases;
+use crate::wasm::utilities::http_trace::HttpTrace;
 use crate::wasm::utilities::path_validator::{FileAccessMode, validate_path};
+use crate::wasm::utilities::stdio_capture::{DEFAULT_CAPTURE_LIMIT, StdioCapture};
+use crate::wasm::utilities::task_progress::TaskMetrics;
+use crate::wasm::utilities::task_runner::AgentInstance;
 
 use crate::wasm::utilities::cache::load_or_compile_component;
 
@@ -69,12 +87,24 @@ impl CreateInstance {
 }
 
 impl RuntimeCommand for CreateInstance {
-    type Output = (Store<State>, CapsuleAgent, String);
+    type Output = (Store<State>, AgentInstance, String);
 
     async fn execute(
         self,
         runtime: Arc<Runtime>,
-    ) -> Result<(Store<State>, CapsuleAgent, String), WasmRuntimeError> {
+    ) -> Result<(Store<State>, AgentInstance, String), WasmRuntimeError> {
+        let envs = resolve_env(
+            &self.policy.env_variables,
+            &self.policy.env_values,
+            &self.policy.host_env,
+        )
+        .map_err(|e| WasmRuntimeError::ConfigError(e.to_string()))?;
+
+        let seed = self
+            .policy
+            .deterministic
+            .then(|| self.policy.seed.unwrap_or_else(generate_seed));
+
         runtime
             .log
             .commit_log(CreateInstanceLog {
@@ -87,6 +117,7 @@ impl RuntimeCommand for CreateInstance {
                 fuel_consumed: 0,
                 ram_used: 0,
                 host_requests: Vec::new(),
+                seed,
             })
             .await?;
 
@@ -95,20 +126,44 @@ impl RuntimeCommand for CreateInstance {
         add_to_linker_async(&mut linker)?;
         wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)?;
 
-        capsule::host::api::add_to_linker(&mut linker, |state: &mut State| state)?;
+        linker.allow_shadowing(true);
+        filesystem::host::add_to_linker(&mut linker)?;
+        linker.allow_shadowing(false);
 
-        let envs = std::env::vars()
-            .collect::<Vec<_>>()
-            .into_iter()
-            .filter(|(key, _)| self.policy.env_variables.contains(key))
-            .collect::<Vec<_>>();
+        capsule::host::api::add_to_linker(&mut linker, |state: &mut State| state)?;
+        capsule::host::kv::add_to_linker(&mut linker, |state: &mut State| state)?;
+        capsule::host::log::add_to_linker(&mut linker, |state: &mut State| state)?;
+        capsule::host::progress::add_to_linker(&mut linker, |state: &mut State| state)?;
+        capsule::host::scheduler::add_to_linker(&mut linker, |state: &mut State| state)?;
+        capsule::host::sql::add_to_linker(&mut linker, |state: &mut State| state)?;
+        capsule::http::client::add_to_linker(&mut linker, |state: &mut State| state)?;
 
         let mut wasi_builder = WasiCtxBuilder::new();
-        wasi_builder
-            .inherit_stdout()
-            .inherit_stderr()
-            .envs(&envs)
-            .args(&self.args);
+        wasi_builder.envs(&envs).args(&self.args);
+
+        let clock = seed.map(|seed| configure_wasi(&mut wasi_builder, seed));
+
+        if let Some(stdin) = self.policy.stdin.clone() {
+            wasi_builder.stdin(MemoryInputPipe::new(stdin));
+        }
+
+        let stdio = if self.policy.inherit_stdio {
+            wasi_builder.inherit_stdout().inherit_stderr();
+            None
+        } else {
+            let capture = StdioCapture::new(
+                self.policy.capture_limit.unwrap_or(DEFAULT_CAPTURE_LIMIT),
+                self.policy.output_events.clone(),
+                &self.task_id,
+                &self.policy.name,
+            );
+            wasi_builder
+                .stdout(capture.stdout())
+                .stderr(capture.stderr());
+            Some(capture)
+        };
+
+        let mut fs = SandboxFs::for_policy(&self.policy);
 
         let all_paths = self
             .policy
@@ -116,16 +171,70 @@ impl RuntimeCommand for CreateInstance {
             .iter()
             .chain(self.policy.mounts.iter());
 
+        let allowed_roots = runtime
+            .capsule_toml
+            .filesystem
+            .as_ref()
+            .and_then(|filesystem| filesystem.allowed_roots.as_ref())
+            .map(|roots| {
+                roots
+                    .iter()
+                    .map(|root| self.project_root.join(root))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
         for path_spec in all_paths {
-            match validate_path(path_spec, &self.project_root) {
+            match validate_path(path_spec, &self.project_root, &allowed_roots) {
                 Ok(parsed) => {
+                    if let Some(format) = parsed.archive {
+                        let archive = ArchiveMount::open(&parsed.path, format, &parsed.guest_path)
+                            .map_err(|e| {
+                                WasmRuntimeError::FilesystemError(format!(
+                                    "Failed to open archive '{}': {}",
+                                    path_spec, e
+                                ))
+                            })?;
+
+                        fs.add_archive(archive);
+                        continue;
+                    }
+
+                    fs.add_mount(&parsed.guest_path, parsed.mode);
+
                     let (dir_perms, file_perms) = match parsed.mode {
                         FileAccessMode::ReadOnly => (DirPerms::READ, FilePerms::READ),
-                        FileAccessMode::ReadWrite => (DirPerms::all(), FilePerms::all()),
+                        FileAccessMode::ReadWrite | FileAccessMode::CopyOnWrite => {
+                            (DirPerms::all(), FilePerms::all())
+                        }
+                    };
+
+                    let host_path = if parsed.mode == FileAccessMode::CopyOnWrite {
+                        let overlay = OverlayMount::new(
+                            &parsed.path,
+                            &parsed.guest_path,
+                            &runtime.cache_dir.join("overlays"),
+                        )
+                        .map_err(|e| {
+                            WasmRuntimeError::FilesystemError(format!(
+                                "Failed to create overlay for '{}': {}",
+                                path_spec, e
+                            ))
+                        })?;
+
+                        let host_path = overlay.host_path().to_path_buf();
+                        fs.add_overlay(overlay);
+                        host_path
+                    } else {
+                        parsed.path
                     };
 
+                    if let Some(files) = parsed.files {
+                        fs.add_filter(&parsed.guest_path, &host_path, files);
+                    }
+
                     if let Err(e) = wasi_builder.preopened_dir(
-                        &parsed.path,
+                        &host_path,
                         &parsed.guest_path,
                         dir_perms,
                         file_perms,
@@ -142,6 +251,85 @@ impl RuntimeCommand for CreateInstance {
             }
         }
 
+        let sql_policy = self.policy.sql.clone().unwrap_or_default();
+        let mut sql = SqlDatabases::new(
+            sql_policy.max_rows,
+            sql_policy.timeout_ms.map(Duration::from_millis),
+        );
+
+        for path_spec in &sql_policy.databases {
+            let parsed = validate_path(path_spec, &self.project_root, &allowed_roots)
+                .map_err(|e| WasmRuntimeError::FilesystemError(e.to_string()))?;
+
+            if parsed.files.is_some() || !parsed.path.is_file() {
+                return Err(WasmRuntimeError::FilesystemError(format!(
+                    "SQL database must be a single file: {}",
+                    path_spec
+                )));
+            }
+
+            if parsed.mode == FileAccessMode::CopyOnWrite {
+                return Err(WasmRuntimeError::FilesystemError(format!(
+                    "SQL databases can only be granted :ro or :rw: {}",
+                    path_spec
+                )));
+            }
+
+            sql.grant(
+                &parsed.guest_path,
+                parsed.path,
+                parsed.mode == FileAccessMode::ReadOnly,
+            );
+        }
+
+        if let Some(scratch_policy) = &self.policy.scratch {
+            let scratch = ScratchDir::new(scratch_policy).map_err(|e| {
+                WasmRuntimeError::FilesystemError(format!(
+                    "Failed to create scratch directory: {}",
+                    e
+                ))
+            })?;
+
+            if let Err(e) = wasi_builder.preopened_dir(
+                scratch.host_path(),
+                scratch.guest_path(),
+                DirPerms::all(),
+                FilePerms::all(),
+            ) {
+                return Err(WasmRuntimeError::FilesystemError(format!(
+                    "Failed to preopen scratch directory: {}",
+                    e
+                )));
+            }
+
+            wasi_builder.env("TMPDIR", scratch.guest_path());
+            fs = fs.with_scratch(scratch);
+        }
+
+        if let Some(outputs_policy) = &self.policy.outputs {
+            let outputs = OutputsDir::new(outputs_policy).map_err(|e| {
+                WasmRuntimeError::FilesystemError(format!(
+                    "Failed to create outputs directory: {}",
+                    e
+                ))
+            })?;
+
+            if let Err(e) = wasi_builder.preopened_dir(
+                outputs.host_path(),
+                outputs.guest_path(),
+                DirPerms::all(),
+                FilePerms::all(),
+            ) {
+                return Err(WasmRuntimeError::FilesystemError(format!(
+                    "Failed to preopen outputs directory: {}",
+                    e
+                )));
+            }
+
+            wasi_builder.env("CAPSULE_OUTPUTS_DIR", outputs.guest_path());
+            fs = fs.with_outputs(outputs);
+        }
+
         let wasi = wasi_builder.build();
 
         let mut limits = StoreLimitsBuilder::new();
@@ -158,16 +346,29 @@ impl RuntimeCommand for CreateInstance {
             table: ResourceTable::new(),
             limits,
             runtime: Some(Arc::clone(&runtime)),
+            task_id: self.task_id.clone(),
             policy: self.policy.clone(),
             wasm_path: self.wasm_path.clone(),
             peak_memory_bytes: 0,
-            host_requests: Vec::new(),
+            host_requests: HttpTrace::new(),
+            logs: GuestLogs::new(),
+            metrics: TaskMetrics::new(),
+            sql,
+            seed,
+            subtasks_started: 0,
+            fs,
+            stdio,
+            subtask_slots: Arc::new(Semaphore::new(self.policy.concurrency_limit())),
         };
 
         let mut store = Store::new(&runtime.engine, state);
 
         store.set_fuel(self.policy.compute.as_fuel())?;
 
+        if let Some(clock) = clock {
+            clock.attach(&mut store, self.policy.compute.as_fuel());
+        }
+
         store.limiter(|state| state);
 
         let component = match runtime.get_component(&self.wasm_path).await {
@@ -189,8 +390,7 @@ impl RuntimeCommand for CreateInstance {
             }
         };
 
-        let instance = match CapsuleAgent::instantiate_async(&mut store, &component, &linker).await
-        {
+        let instance = match AgentInstance::instantiate(&mut store, &component, &linker).await {
             Ok(instance) => instance,
             Err(e) => {
                 runtime
@@ -201,6 +401,7 @@ impl RuntimeCommand for CreateInstance {
                         fuel_consumed: 0,
                         ram_used: 0,
                         host_requests: Vec::new(),
+                        metrics: BTreeMap::new(),
                     })
                     .await?;
                 return Err(WasmRuntimeError::WasmtimeError(e));
@@ -1,19 +1,23 @@
 use std::sync::Arc;
+use std::time::Instant;
 
 use wasmtime::Store;
 
 use crate::config::log::{InstanceState, UpdateInstanceLog};
 use crate::wasm::execution_policy::ExecutionPolicy;
+use crate::wasm::filesystem::outputs::OutputsError;
 use crate::wasm::runtime::{Runtime, RuntimeCommand, WasmRuntimeError};
-use crate::wasm::state::{CapsuleAgent, State};
-use crate::wasm::utilities::task_config::{TaskError, TaskExecution, TaskResult};
+use crate::wasm::state::State;
+use crate::wasm::state::host_types::ErrorCode;
+use crate::wasm::utilities::task_config::{TaskError, TaskExecution, TaskInvocation, TaskResult};
+use crate::wasm::utilities::task_runner::{AgentInstance, failure};
 
 pub struct RunInstance {
     task_id: String,
     policy: ExecutionPolicy,
     store: Store<State>,
-    instance: CapsuleAgent,
-    args_json: String,
+    instance: AgentInstance,
+    invocation: TaskInvocation,
 }
 
 impl RunInstance {
@@ -21,15 +25,37 @@ impl RunInstance {
         task_id: String,
         policy: ExecutionPolicy,
         store: Store<State>,
-        instance: CapsuleAgent,
-        args_json: String,
+        instance: AgentInstance,
+        invocation: TaskInvocation,
     ) -> Self {
         Self {
             task_id,
             policy,
             store,
             instance,
-            args_json,
+            invocation,
+        }
+    }
+
+    fn execution(&self, start_time: Instant) -> TaskExecution {
+        let stdio = self.store.data().stdio.as_ref();
+
+        TaskExecution {
+            task_name: self.policy.name.clone(),
+            duration_ms: start_time.elapsed().as_millis() as u64,
+            retries: self.policy.max_retries,
+            fuel_consumed: self.policy.compute.as_fuel() - self.store.get_fuel().unwrap_or(0),
+            ram_used: self.store.data().peak_memory_bytes,
+            host_requests: self.store.data().host_requests.snapshot(),
+            overlay_changes: Vec::new(),
+            file_access: self.store.data().fs.audit().map(|audit| audit.summary()),
+            artifacts: Vec::new(),
+            stdout: stdio.map(|s| s.stdout().text()).unwrap_or_default(),
+            stderr: stdio.map(|s| s.stderr().text()).unwrap_or_default(),
+            output_truncated: stdio.is_some_and(|s| s.truncated()),
+            logs: self.store.data().logs.snapshot(),
+            metrics: self.store.data().metrics.snapshot(),
+            seed: self.store.data().seed,
         }
     }
 }
@@ -38,7 +64,7 @@ impl RuntimeCommand for RunInstance {
     type Output = String;
 
     async fn execute(mut self, runtime: Arc<Runtime>) -> Result<Self::Output, WasmRuntimeError> {
-        let start_time = std::time::Instant::now();
+        let start_time = Instant::now();
 
         runtime
             .log
@@ -47,217 +73,111 @@ impl RuntimeCommand for RunInstance {
                 state: InstanceState::Running,
                 fuel_consumed: self.policy.compute.as_fuel() - self.store.get_fuel().unwrap_or(0),
                 ram_used: self.store.data().peak_memory_bytes,
-                host_requests: self.store.data().host_requests.clone(),
+                host_requests: self.store.data().host_requests.snapshot(),
+                metrics: self.store.data().metrics.snapshot(),
             })
             .await?;
 
-        let wasm_future = self
-            .instance
-            .capsule_host_task_runner()
-            .call_run(&mut self.store, &self.args_json);
+        let run = self.instance.run(&mut self.store, &self.invocation);
 
-        let response = match self.policy.timeout_duration() {
-            Some(duration) => match tokio::time::timeout(duration, wasm_future).await {
-                Ok(wasm_result) => match wasm_result {
-                    Ok(inner_result) => match inner_result {
-                        Ok(json_string) => {
-                            let result_object = serde_json::from_str(&json_string)
-                                .unwrap_or(serde_json::Value::String(json_string));
-
-                            let result = result_object
-                                .get("result")
-                                .cloned()
-                                .unwrap_or(result_object);
+        let output = match self.policy.timeout_duration() {
+            Some(duration) => match tokio::time::timeout(duration, run).await {
+                Ok(output) => output,
+                Err(_elapsed) => Ok(Err(failure(
+                    ErrorCode::Timeout,
+                    format!("timeout after {}ms", duration.as_millis()),
+                )
+                .into())),
+            },
+            None => run.await,
+        };
 
-                            if result.get("error_type").is_some() && result.get("message").is_some()
-                            {
-                                TaskResult {
-                                    success: false,
-                                    result: None,
-                                    error: Some(TaskError {
-                                        error_type: result
-                                            .get("error_type")
-                                            .and_then(|v| v.as_str())
-                                            .unwrap_or_default()
-                                            .to_string(),
-                                        message: result
-                                            .get("message")
-                                            .and_then(|v| v.as_str())
-                                            .unwrap_or_default()
-                                            .to_string(),
-                                    }),
-                                    execution: TaskExecution {
-                                        task_name: self.policy.name.clone(),
-                                        duration_ms: start_time.elapsed().as_millis() as u64,
-                                        retries: self.policy.max_retries,
-                                        fuel_consumed: self.policy.compute.as_fuel()
-                                            - self.store.get_fuel().unwrap_or(0),
-                                        ram_used: self.store.data().peak_memory_bytes,
-                                        host_requests: self.store.data().host_requests.clone(),
-                                    },
-                                }
-                            } else {
-                                TaskResult {
-                                    success: true,
-                                    result: Some(result),
-                                    error: None,
-                                    execution: TaskExecution {
-                                        task_name: self.policy.name.clone(),
-                                        duration_ms: start_time.elapsed().as_millis() as u64,
-                                        retries: self.policy.max_retries,
-                                        fuel_consumed: self.policy.compute.as_fuel()
-                                            - self.store.get_fuel().unwrap_or(0),
-                                        ram_used: self.store.data().peak_memory_bytes,
-                                        host_requests: self.store.data().host_requests.clone(),
-                                    },
-                                }
-                            }
-                        }
-                        Err(error_string) => TaskResult {
-                            success: false,
-                            result: None,
-                            error: Some(TaskError {
-                                error_type: "task_error".to_string(),
-                                message: error_string,
-                            }),
-                            execution: TaskExecution {
-                                task_name: self.policy.name.clone(),
-                                duration_ms: start_time.elapsed().as_millis() as u64,
-                                retries: self.policy.max_retries,
-                                fuel_consumed: self.policy.compute.as_fuel()
-                                    - self.store.get_fuel().unwrap_or(0),
-                                ram_used: self.store.data().peak_memory_bytes,
-                                host_requests: self.store.data().host_requests.clone(),
-                            },
-                        },
-                    },
-                    Err(e) => TaskResult {
-                        success: false,
-                        result: None,
-                        error: Some(TaskError {
-                            error_type: "Wasm_error".to_string(),
-                            message: e.to_string(),
-                        }),
-                        execution: TaskExecution {
-                            task_name: self.policy.name.clone(),
-                            duration_ms: start_time.elapsed().as_millis() as u64,
-                            retries: self.policy.max_retries,
-                            fuel_consumed: self.policy.compute.as_fuel()
-                                - self.store.get_fuel().unwrap_or(0),
-                            ram_used: self.store.data().peak_memory_bytes,
-                            host_requests: self.store.data().host_requests.clone(),
-                        },
-                    },
-                },
-                Err(_elapsed) => TaskResult {
-                    success: false,
-                    result: None,
-                    error: Some(TaskError {
-                        error_type: "timeout".to_string(),
-                        message: format!("timeout after {}ms", duration.as_millis()),
-                    }),
-                    execution: TaskExecution {
-                        task_name: self.policy.name.clone(),
-                        duration_ms: start_time.elapsed().as_millis() as u64,
-                        retries: self.policy.max_retries,
-                        fuel_consumed: self.policy.compute.as_fuel()
-                            - self.store.get_fuel().unwrap_or(0),
-                        ram_used: self.store.data().peak_memory_bytes,
-                        host_requests: self.store.data().host_requests.clone(),
-                    },
-                },
+        let mut response = match output {
+            Ok(Ok(result)) => TaskResult {
+                success: true,
+                result: Some(result),
+                error: None,
+                execution: self.execution(start_time),
+            },
+            Ok(Err(error)) => TaskResult {
+                success: false,
+                result: None,
+                error: Some(error),
+                execution: self.execution(start_time),
+            },
+            Err(e) => TaskResult {
+                success: false,
+                result: None,
+                error: Some(TaskError {
+                    error_type: "wasm_error".to_string(),
+                    message: e.to_string(),
+                    traceback: None,
+                }),
+                execution: self.execution(start_time),
             },
-            None => match wasm_future.await {
-                Ok(inner_result) => match inner_result {
-                    Ok(json_string) => {
-                        let result_object = serde_json::from_str(&json_string)
-                            .unwrap_or(serde_json::Value::String(json_string));
+        };
 
-                        let result = result_object
-                            .get("result")
-                            .cloned()
-                            .unwrap_or(result_object);
+        match self
+            .store
+            .data()
+            .fs
+            .overlay_changes(self.policy.export_changes.as_deref())
+        {
+            Ok(changes) => response.execution.overlay_changes = changes,
+            Err(e) => {
+                response.success = false;
+                response.result = None;
+                response.error = Some(TaskError {
+                    error_type: "overlay_error".to_string(),
+                    message: format!("Failed to collect overlay changes: {}", e),
+                    traceback: None,
+                });
+            }
+        }
 
-                        if result.get("error_type").is_some() && result.get("message").is_some() {
-                            TaskResult {
-                                success: false,
-                                result: None,
-                                error: Some(TaskError {
-                                    error_type: result
-                                        .get("error_type")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                    message: result
-                                        .get("message")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                }),
-                                execution: TaskExecution {
-                                    task_name: self.policy.name.clone(),
-                                    duration_ms: start_time.elapsed().as_millis() as u64,
-                                    retries: self.policy.max_retries,
-                                    fuel_consumed: self.policy.compute.as_fuel()
-                                        - self.store.get_fuel().unwrap_or(0),
-                                    ram_used: self.store.data().peak_memory_bytes,
-                                    host_requests: self.store.data().host_requests.clone(),
-                                },
-                            }
-                        } else {
-                            TaskResult {
-                                success: true,
-                                result: Some(result),
-                                error: None,
-                                execution: TaskExecution {
-                                    task_name: self.policy.name.clone(),
-                                    duration_ms: start_time.elapsed().as_millis() as u64,
-                                    retries: self.policy.max_retries,
-                                    fuel_consumed: self.policy.compute.as_fuel()
-                                        - self.store.get_fuel().unwrap_or(0),
-                                    ram_used: self.store.data().peak_memory_bytes,
-                                    host_requests: self.store.data().host_requests.clone(),
-                                },
-                            }
+        if let Some(outputs) = self.store.data().fs.outputs() {
+            match outputs.collect(self.policy.outputs_dest.as_deref()) {
+                Ok(artifacts) => response.execution.artifacts = artifacts,
+                Err(e) => {
+                    let (error_type, message) = match e {
+                        OutputsError::Io(e) => {
+                            ("outputs_error", format!("Failed to collect outputs: {}", e))
                         }
-                    }
-                    Err(error_string) => TaskResult {
-                        success: false,
-                        result: None,
-                        error: Some(TaskError {
-                            error_type: "task_error".to_string(),
-                            message: error_string,
-                        }),
-                        execution: TaskExecution {
-                            task_name: self.policy.name.clone(),
-                            duration_ms: start_time.elapsed().as_millis() as u64,
-                            retries: self.policy.max_retries,
-                            fuel_consumed: self.policy.compute.as_fuel()
-                                - self.store.get_fuel().unwrap_or(0),
-                            ram_used: self.store.data().peak_memory_bytes,
-                            host_requests: self.store.data().host_requests.clone(),
-                        },
-                    },
-                },
-                Err(e) => TaskResult {
-                    success: false,
-                    result: None,
-                    error: Some(TaskError {
-                        error_type: "wasm_error".to_string(),
-                        message: e.to_string(),
-                    }),
-                    execution: TaskExecution {
-                        task_name: self.policy.name.clone(),
-                        duration_ms: start_time.elapsed().as_millis() as u64,
-                        retries: self.policy.max_retries,
-                        fuel_consumed: self.policy.compute.as_fuel()
-                            - self.store.get_fuel().unwrap_or(0),
-                        ram_used: self.store.data().peak_memory_bytes,
-                        host_requests: self.store.data().host_requests.clone(),
-                    },
-                },
-            },
-        };
+                        e => ("outputs_limit_exceeded", e.to_string()),
+                    };
+
+                    response.success = false;
+                    response.result = None;
+                    response.error = Some(TaskError {
+                        error_type: error_type.to_string(),
+                        message,
+                        traceback: None,
+                    });
+                }
+            }
+        }
+
+        if let Some(violation) = self.store.data().fs.limits().and_then(|l| l.violation()) {
+            response.success = false;
+            response.result = None;
+            response.error = Some(TaskError {
+                error_type: "disk_quota_exceeded".to_string(),
+                message: violation.to_string(),
+                traceback: None,
+            });
+        }
+
+        if let Some(cassette) = &runtime.cassette
+            && let Err(e) = cassette.flush()
+        {
+            response.success = false;
+            response.result = None;
+            response.error = Some(TaskError {
+                error_type: "cassette_error".to_string(),
+                message: e.to_string(),
+                traceback: None,
+            });
+        }
 
         let state = if response.success {
             InstanceState::Completed
@@ -273,9 +193,24 @@ impl RuntimeCommand for RunInstance {
                 fuel_consumed: response.execution.fuel_consumed,
                 ram_used: response.execution.ram_used,
                 host_requests: response.execution.host_requests.clone(),
+                metrics: response.execution.metrics.clone(),
             })
             .await?;
 
+        if let Some(audit) = self.store.data().fs.audit() {
+            runtime
+                .log
+                .record_file_access(&self.task_id, audit.entries())
+                .await?;
+        }
+
+        if !response.execution.logs.is_empty() {
+            runtime
+                .log
+                .record_guest_logs(&self.task_id, response.execution.logs.clone())
+                .await?;
+        }
+
         if !response.success {
             let error_message = response
                 .error
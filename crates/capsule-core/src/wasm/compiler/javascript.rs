@@ -158,20 +158,30 @@ impl JavascriptWasmCompiler {
         let wrapper_content = format!(
             r#"// Auto-generated bootloader for Capsule
 import * as hostApi from 'capsule:host/api';
+import * as kv from 'capsule:host/kv';
+import * as log from 'capsule:host/log';
+import * as progress from 'capsule:host/progress';
+import * as scheduler from 'capsule:host/scheduler';
+import * as sql from 'capsule:host/sql';
 import * as fsTypes from 'wasi:filesystem/types@0.2.0';
 import * as fsPreopens from 'wasi:filesystem/preopens@0.2.0';
 import * as environment from 'wasi:cli/environment@0.2.0';
 import * as stdinApi from 'wasi:cli/stdin@0.2.0';
 import * as stdoutApi from 'wasi:cli/stdout@0.2.0';
 globalThis['capsule:host/api'] = hostApi;
+globalThis['capsule:host/kv'] = kv;
+globalThis['capsule:host/log'] = log;
+globalThis['capsule:host/progress'] = progress;
+globalThis['capsule:host/scheduler'] = scheduler;
+globalThis['capsule:host/sql'] = sql;
 globalThis['wasi:filesystem/types'] = fsTypes;
 globalThis['wasi:filesystem/preopens'] = fsPreopens;
 globalThis['wasi:cli/environment'] = environment;
 globalThis['wasi:cli/stdin'] = stdinApi;
 globalThis['wasi:cli/stdout'] = stdoutApi;
 import '{}';
-import {{ exports, incomingHandler }} from '{}/dist/app.js';
-export const taskRunner = exports;
+import {{ exportsV2, incomingHandler }} from '{}/dist/app.js';
+export const taskRunnerV2 = exportsV2;
 export {{ incomingHandler }};
             "#,
             import_path, sdk_path_str
@@ -196,7 +206,7 @@ export {{ incomingHandler }};
             .arg("--format=esm")
             .arg("--platform=neutral")
             .arg("--main-fields=main,module")
-            .arg("--external:capsule:host/api")
+            .arg("--external:capsule:host/*")
             .arg("--external:wasi:filesystem/*")
             .arg("--external:wasi:cli/*")
             .arg(format!("--inject:{}", process_polyfill_path.display()))
@@ -279,7 +289,7 @@ export {{ incomingHandler }};
             .arg("--wit")
             .arg(&wit_path_normalized)
             .arg("--world-name")
-            .arg("capsule-agent")
+            .arg("capsule-agent-v2")
             .arg("--enable")
             .arg("http")
             .arg("-o")
@@ -315,7 +325,7 @@ export {{ incomingHandler }};
     fn get_wit_path(&self) -> Result<PathBuf, JavascriptWasmCompilerError> {
         let wit_dir = self.cache_dir.join("wit");
 
-        if !wit_dir.join("capsule.wit").exists() {
+        if !WitManager::is_current(&wit_dir) {
             WitManager::import_wit_deps(&wit_dir)?;
         }
 
@@ -151,7 +151,7 @@ capsule.socket._install()
 import {module_name}
 import capsule.app
 capsule.app._main_module = {module_name}
-from capsule.app import TaskRunner, exports
+from capsule.app import TaskRunnerV2
 "#,
             module_name = module_name
         );
@@ -168,7 +168,7 @@ from capsule.app import TaskRunner, exports
         cmd.arg("-d")
             .arg(&wit_path_normalized)
             .arg("-w")
-            .arg("capsule-agent")
+            .arg("capsule-agent-v2")
             .arg("componentize")
             .arg("_capsule_boot")
             .arg("-p")
@@ -210,7 +210,7 @@ from capsule.app import TaskRunner, exports
     fn get_wit_path(&self) -> Result<PathBuf, PythonWasmCompilerError> {
         let wit_dir = self.cache_dir.join("wit");
 
-        if !wit_dir.join("capsule.wit").exists() {
+        if !WitManager::is_current(&wit_dir) {
             WitManager::import_wit_deps(&wit_dir)?;
         }
 
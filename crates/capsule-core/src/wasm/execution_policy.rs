@@ -1,6 +1,16 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use std::time::Duration;
 
+use bytes::Bytes;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::wasm::utilities::stdio_capture::OutputEvent;
+use crate::wasm::utilities::task_progress::ProgressEvent;
+
+pub const DEFAULT_MAX_CONCURRENCY: usize = 8;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Compute {
@@ -25,6 +35,36 @@ impl Compute {
     }
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScratchPolicy {
+    pub max_bytes: Option<u64>,
+    pub max_inodes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputsPolicy {
+    pub max_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KvPolicy {
+    pub namespaces: Vec<String>,
+    pub max_bytes: Option<u64>,
+}
+
+impl KvPolicy {
+    pub fn allows(&self, namespace: &str) -> bool {
+        self.namespaces.iter().any(|n| n == namespace || n == "*")
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SqlPolicy {
+    pub databases: Vec<String>,
+    pub max_rows: Option<u64>,
+    pub timeout_ms: Option<u64>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExecutionPolicy {
     pub name: String,
@@ -44,6 +84,63 @@ pub struct ExecutionPolicy {
 
     #[serde(default)]
     pub env_variables: Vec<String>,
+
+    #[serde(default)]
+    pub env_values: BTreeMap<String, String>,
+
+    #[serde(skip)]
+    pub host_env: HashMap<String, String>,
+
+    #[serde(default)]
+    pub scratch: Option<ScratchPolicy>,
+
+    #[serde(skip)]
+    pub export_changes: Option<PathBuf>,
+
+    #[serde(default)]
+    pub max_write_bytes: Option<u64>,
+
+    #[serde(default)]
+    pub max_files_created: Option<u64>,
+
+    #[serde(default)]
+    pub audit_files: bool,
+
+    #[serde(default)]
+    pub outputs: Option<OutputsPolicy>,
+
+    #[serde(skip)]
+    pub outputs_dest: Option<PathBuf>,
+
+    #[serde(skip)]
+    pub inherit_stdio: bool,
+
+    #[serde(default)]
+    pub capture_limit: Option<u64>,
+
+    #[serde(default)]
+    pub max_concurrency: Option<usize>,
+
+    #[serde(default)]
+    pub kv: Option<KvPolicy>,
+
+    #[serde(default)]
+    pub sql: Option<SqlPolicy>,
+
+    #[serde(default)]
+    pub deterministic: bool,
+
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    #[serde(skip)]
+    pub output_events: Option<UnboundedSender<OutputEvent>>,
+
+    #[serde(skip)]
+    pub progress_events: Option<UnboundedSender<ProgressEvent>>,
+
+    #[serde(skip)]
+    pub stdin: Option<Bytes>,
 }
 
 impl Default for ExecutionPolicy {
@@ -58,6 +155,25 @@ impl Default for ExecutionPolicy {
             mounts: Vec::new(),
             allowed_hosts: Vec::new(),
             env_variables: Vec::new(),
+            env_values: BTreeMap::new(),
+            host_env: HashMap::new(),
+            scratch: None,
+            export_changes: None,
+            max_write_bytes: None,
+            max_files_created: None,
+            audit_files: false,
+            outputs: None,
+            outputs_dest: None,
+            inherit_stdio: false,
+            capture_limit: None,
+            max_concurrency: None,
+            kv: None,
+            sql: None,
+            deterministic: false,
+            seed: None,
+            output_events: None,
+            progress_events: None,
+            stdin: None,
         }
     }
 }
@@ -118,6 +234,118 @@ impl ExecutionPolicy {
         self.env_variables = env_variables;
         self
     }
+
+    pub fn env_values(mut self, env_values: BTreeMap<String, String>) -> Self {
+        self.env_values = env_values;
+        self
+    }
+
+    pub fn host_env(mut self, host_env: HashMap<String, String>) -> Self {
+        self.host_env = host_env;
+        self
+    }
+
+    pub fn scratch(mut self, scratch: Option<ScratchPolicy>) -> Self {
+        self.scratch = scratch;
+        self
+    }
+
+    pub fn export_changes(mut self, export_changes: Option<PathBuf>) -> Self {
+        self.export_changes = export_changes;
+        self
+    }
+
+    pub fn max_write_bytes(mut self, max_write_bytes: Option<u64>) -> Self {
+        self.max_write_bytes = max_write_bytes;
+        self
+    }
+
+    pub fn max_files_created(mut self, max_files_created: Option<u64>) -> Self {
+        self.max_files_created = max_files_created;
+        self
+    }
+
+    pub fn audit_files(mut self, audit_files: Option<bool>) -> Self {
+        if let Some(a) = audit_files {
+            self.audit_files = a;
+        }
+        self
+    }
+
+    pub fn outputs(mut self, outputs: Option<OutputsPolicy>) -> Self {
+        self.outputs = outputs;
+        self
+    }
+
+    pub fn outputs_dest(mut self, outputs_dest: Option<PathBuf>) -> Self {
+        self.outputs_dest = outputs_dest;
+        self
+    }
+
+    pub fn inherit_stdio(mut self, inherit_stdio: Option<bool>) -> Self {
+        if let Some(i) = inherit_stdio {
+            self.inherit_stdio = i;
+        }
+        self
+    }
+
+    pub fn capture_limit(mut self, capture_limit: Option<u64>) -> Self {
+        self.capture_limit = capture_limit;
+        self
+    }
+
+    pub fn max_concurrency(mut self, max_concurrency: Option<usize>) -> Self {
+        self.max_concurrency = max_concurrency;
+        self
+    }
+
+    pub fn kv(mut self, kv: Option<KvPolicy>) -> Self {
+        self.kv = kv;
+        self
+    }
+
+    pub fn sql(mut self, sql: Option<SqlPolicy>) -> Self {
+        self.sql = sql;
+        self
+    }
+
+    pub fn deterministic(mut self, deterministic: Option<bool>) -> Self {
+        if let Some(d) = deterministic {
+            self.deterministic = d;
+        }
+        self
+    }
+
+    pub fn seed(mut self, seed: Option<u64>) -> Self {
+        if seed.is_some() {
+            self.seed = seed;
+        }
+        self
+    }
+
+    pub fn concurrency_limit(&self) -> usize {
+        self.max_concurrency
+            .unwrap_or(DEFAULT_MAX_CONCURRENCY)
+            .max(1)
+    }
+
+    pub fn output_events(mut self, output_events: Option<UnboundedSender<OutputEvent>>) -> Self {
+        self.output_events = output_events;
+        self
+    }
+
+    pub fn progress_events(
+        mut self,
+        progress_events: Option<UnboundedSender<ProgressEvent>>,
+    ) -> Self {
+        self.progress_events = progress_events;
+        self
+    }
+
+    pub fn stdin(mut self, stdin: Option<Vec<u8>>) -> Self {
+        self.stdin = stdin.map(Bytes::from);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -143,5 +371,63 @@ mod tests {
         assert_eq!(policy.allowed_files, vec!["/etc/passwd".to_string()]);
         assert_eq!(policy.env_variables, vec!["API_KEY".to_string()]);
         assert_eq!(policy.allowed_hosts, Vec::<String>::new());
+        assert_eq!(policy.scratch, None);
+        assert!(!policy.inherit_stdio);
+    }
+
+    #[test]
+    fn test_execution_policy_env_values() {
+        let policy = ExecutionPolicy::new()
+            .env_values(BTreeMap::from([(
+                "API_KEY".to_string(),
+                "$OPENAI_API_KEY".to_string(),
+            )]))
+            .host_env(HashMap::from([(
+                "OPENAI_API_KEY".to_string(),
+                "sk-test".to_string(),
+            )]));
+
+        let json = serde_json::to_value(&policy).unwrap();
+        assert_eq!(json["env_values"]["API_KEY"], "$OPENAI_API_KEY");
+        assert!(json.get("host_env").is_none());
+        assert_eq!(policy.host_env.len(), 1);
+    }
+
+    #[test]
+    fn test_execution_policy_concurrency_limit() {
+        assert_eq!(
+            ExecutionPolicy::new().concurrency_limit(),
+            DEFAULT_MAX_CONCURRENCY
+        );
+        assert_eq!(
+            ExecutionPolicy::new()
+                .max_concurrency(Some(0))
+                .concurrency_limit(),
+            1
+        );
+        assert_eq!(
+            ExecutionPolicy::new()
+                .max_concurrency(Some(32))
+                .concurrency_limit(),
+            32
+        );
+    }
+
+    #[test]
+    fn test_kv_policy_allows() {
+        let kv = KvPolicy {
+            namespaces: vec!["jobs".to_string()],
+            max_bytes: None,
+        };
+
+        assert!(kv.allows("jobs"));
+        assert!(!kv.allows("jobs2"));
+        assert!(!KvPolicy::default().allows("jobs"));
+
+        let any = KvPolicy {
+            namespaces: vec!["*".to_string()],
+            max_bytes: None,
+        };
+        assert!(any.allows("anything"));
     }
 }
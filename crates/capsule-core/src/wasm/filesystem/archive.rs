@@ -0,0 +1,544 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use tempfile::NamedTempFile;
+
+const MAX_ENTRY_SIZE: u64 = 1 << 30;
+const MAX_TOTAL_SIZE: u64 = 4 << 30;
+const MAX_PREALLOC: u64 = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Directory,
+    File,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryLocation {
+    Implicit,
+    Offset(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    pub kind: EntryKind,
+    pub size: u64,
+    pub mtime: Option<u64>,
+    location: EntryLocation,
+}
+
+impl ArchiveEntry {
+    fn directory() -> Self {
+        Self {
+            kind: EntryKind::Directory,
+            size: 0,
+            mtime: None,
+            location: EntryLocation::Implicit,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ArchiveMount {
+    source: PathBuf,
+    format: ArchiveFormat,
+    guest_path: String,
+    entries: BTreeMap<PathBuf, ArchiveEntry>,
+    unpacked: Option<NamedTempFile>,
+}
+
+impl ArchiveMount {
+    pub fn open(source: &Path, format: ArchiveFormat, guest_path: &str) -> io::Result<Self> {
+        Self::open_with_limit(source, format, guest_path, MAX_TOTAL_SIZE)
+    }
+
+    fn open_with_limit(
+        source: &Path,
+        format: ArchiveFormat,
+        guest_path: &str,
+        limit: u64,
+    ) -> io::Result<Self> {
+        let mut mount = Self {
+            source: source.to_path_buf(),
+            format,
+            guest_path: guest_path.to_string(),
+            entries: BTreeMap::new(),
+            unpacked: None,
+        };
+
+        mount
+            .entries
+            .insert(PathBuf::new(), ArchiveEntry::directory());
+
+        match format {
+            ArchiveFormat::Tar => mount.index_tar(File::open(source)?)?,
+            ArchiveFormat::TarGz => {
+                let mut unpacked = NamedTempFile::new()?;
+                let decoder = GzDecoder::new(File::open(source)?);
+                let size = io::copy(&mut decoder.take(limit + 1), &mut unpacked)?;
+                check_total(source, size, limit)?;
+
+                mount.index_tar(unpacked.reopen()?)?;
+                mount.unpacked = Some(unpacked);
+            }
+            ArchiveFormat::Zip => mount.index_zip(limit)?,
+        }
+
+        Ok(mount)
+    }
+
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    pub fn format(&self) -> ArchiveFormat {
+        self.format
+    }
+
+    pub fn guest_path(&self) -> &str {
+        &self.guest_path
+    }
+
+    pub fn entry(&self, path: &Path) -> Option<&ArchiveEntry> {
+        self.entries.get(path)
+    }
+
+    pub fn children(&self, dir: &Path) -> Vec<(String, EntryKind)> {
+        self.entries
+            .iter()
+            .filter(|(path, _)| !path.as_os_str().is_empty() && path.parent() == Some(dir))
+            .filter_map(|(path, entry)| {
+                let name = path.file_name()?.to_string_lossy().to_string();
+                Some((name, entry.kind))
+            })
+            .collect()
+    }
+
+    pub fn resolve(&self, base: &Path, path: &str) -> Option<PathBuf> {
+        if path.starts_with('/') {
+            return None;
+        }
+
+        normalize(&base.join(path), false)
+    }
+
+    pub fn read_at(&self, path: &Path, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+        let entry = self
+            .entry(path)
+            .filter(|e| e.kind == EntryKind::File)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.display().to_string()))?;
+
+        let EntryLocation::Offset(start) = entry.location else {
+            return Ok(Vec::new());
+        };
+
+        let offset = offset.min(entry.size);
+        let len = len.min(entry.size - offset);
+
+        let mut file = File::open(self.data_path())?;
+        file.seek(SeekFrom::Start(start + offset))?;
+
+        let mut contents = Vec::with_capacity(len.min(MAX_PREALLOC) as usize);
+        file.take(len).read_to_end(&mut contents)?;
+        Ok(contents)
+    }
+
+    fn data_path(&self) -> &Path {
+        match &self.unpacked {
+            Some(unpacked) => unpacked.path(),
+            None => &self.source,
+        }
+    }
+
+    fn index_tar(&mut self, file: File) -> io::Result<()> {
+        let mut archive = tar::Archive::new(BufReader::new(file));
+
+        for item in archive.entries()? {
+            let item = item?;
+            let header = item.header();
+
+            let kind = match header.entry_type() {
+                tar::EntryType::Directory => EntryKind::Directory,
+                tar::EntryType::Regular | tar::EntryType::Continuous => EntryKind::File,
+                _ => continue,
+            };
+
+            let Some(path) = normalize(&item.path()?, true) else {
+                continue;
+            };
+
+            let size = check_size(&path, header.size()?)?;
+
+            self.insert(
+                path,
+                ArchiveEntry {
+                    kind,
+                    size,
+                    mtime: header.mtime().ok(),
+                    location: EntryLocation::Offset(item.raw_file_position()),
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    fn index_zip(&mut self, limit: u64) -> io::Result<()> {
+        let mut archive =
+            zip::ZipArchive::new(File::open(&self.source)?).map_err(io::Error::other)?;
+        let mut unpacked = NamedTempFile::new()?;
+        let mut offset = 0;
+
+        for index in 0..archive.len() {
+            let item = archive.by_index(index).map_err(io::Error::other)?;
+
+            let Some(path) = item.enclosed_name().and_then(|name| normalize(&name, true)) else {
+                continue;
+            };
+
+            if item.is_dir() {
+                self.insert(path, ArchiveEntry::directory());
+                continue;
+            }
+
+            check_size(&path, item.size())?;
+            check_total(&self.source, offset + item.size(), limit)?;
+
+            let remaining = MAX_ENTRY_SIZE.min(limit - offset);
+            let size = io::copy(&mut item.take(remaining + 1), &mut unpacked)?;
+            check_size(&path, size)?;
+            check_total(&self.source, offset + size, limit)?;
+
+            self.insert(
+                path,
+                ArchiveEntry {
+                    kind: EntryKind::File,
+                    size,
+                    mtime: None,
+                    location: EntryLocation::Offset(offset),
+                },
+            );
+
+            offset += size;
+        }
+
+        self.unpacked = Some(unpacked);
+        Ok(())
+    }
+
+    fn insert(&mut self, path: PathBuf, entry: ArchiveEntry) {
+        if path.as_os_str().is_empty() {
+            return;
+        }
+
+        for ancestor in path.ancestors().skip(1) {
+            self.entries
+                .entry(ancestor.to_path_buf())
+                .or_insert_with(ArchiveEntry::directory);
+        }
+
+        self.entries.insert(path, entry);
+    }
+}
+
+fn check_size(path: &Path, size: u64) -> io::Result<u64> {
+    if size > MAX_ENTRY_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Archive entry '{}' exceeds the {} byte limit",
+                path.display(),
+                MAX_ENTRY_SIZE
+            ),
+        ));
+    }
+
+    Ok(size)
+}
+
+fn check_total(source: &Path, size: u64, limit: u64) -> io::Result<()> {
+    if size > limit {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Archive '{}' exceeds the {} byte decompressed size limit",
+                source.display(),
+                limit
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+fn normalize(path: &Path, allow_root: bool) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                if !allow_root {
+                    return None;
+                }
+            }
+        }
+    }
+
+    Some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_tar<W: Write>(writer: W) -> W {
+        let mut builder = tar::Builder::new(writer);
+
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        header.set_mtime(1_700_000_000);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "data/train.csv", &b"a,b,c"[..])
+            .unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_size(2);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "./README", &b"hi"[..])
+            .unwrap();
+
+        builder.into_inner().unwrap()
+    }
+
+    fn fixture(name: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        let file = File::create(&path).unwrap();
+
+        match ArchiveFormat::detect(&path).unwrap() {
+            ArchiveFormat::Tar => {
+                write_tar(file);
+            }
+            ArchiveFormat::TarGz => {
+                write_tar(flate2::write::GzEncoder::new(file, Default::default()))
+                    .finish()
+                    .unwrap();
+            }
+            ArchiveFormat::Zip => {
+                let mut zip = zip::ZipWriter::new(file);
+                let options = zip::write::SimpleFileOptions::default();
+                zip.start_file("data/train.csv", options).unwrap();
+                zip.write_all(b"a,b,c").unwrap();
+                zip.start_file("README", options).unwrap();
+                zip.write_all(b"hi").unwrap();
+                zip.finish().unwrap();
+            }
+        }
+
+        (dir, path)
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            ArchiveFormat::detect(Path::new("fixtures.tar")),
+            Some(ArchiveFormat::Tar)
+        );
+        assert_eq!(
+            ArchiveFormat::detect(Path::new("fixtures.TAR.GZ")),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ArchiveFormat::detect(Path::new("fixtures.tgz")),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ArchiveFormat::detect(Path::new("fixtures.zip")),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(ArchiveFormat::detect(Path::new("fixtures")), None);
+    }
+
+    #[test]
+    fn test_read_archives() {
+        for name in ["fixtures.tar", "fixtures.tar.gz", "fixtures.zip"] {
+            let (_dir, path) = fixture(name);
+            let format = ArchiveFormat::detect(&path).unwrap();
+            let mount = ArchiveMount::open(&path, format, "/fixtures").unwrap();
+
+            assert_eq!(
+                mount.entry(Path::new("data")).unwrap().kind,
+                EntryKind::Directory,
+                "{}",
+                name
+            );
+            assert_eq!(mount.entry(Path::new("data/train.csv")).unwrap().size, 5);
+            assert_eq!(
+                mount.read_at(Path::new("data/train.csv"), 0, 10).unwrap(),
+                b"a,b,c",
+                "{}",
+                name
+            );
+            assert_eq!(
+                mount.read_at(Path::new("data/train.csv"), 2, 2).unwrap(),
+                b"b,",
+                "{}",
+                name
+            );
+            assert_eq!(
+                mount.read_at(Path::new("README"), 0, 10).unwrap(),
+                b"hi",
+                "{}",
+                name
+            );
+            assert!(
+                mount
+                    .read_at(Path::new("README"), 10, 10)
+                    .unwrap()
+                    .is_empty()
+            );
+            assert!(mount.read_at(Path::new("data"), 0, 10).is_err());
+            assert!(mount.read_at(Path::new("missing"), 0, 10).is_err());
+        }
+    }
+
+    #[test]
+    fn test_tar_mtime() {
+        let (_dir, path) = fixture("fixtures.tar");
+        let mount = ArchiveMount::open(&path, ArchiveFormat::Tar, "/fixtures").unwrap();
+
+        assert_eq!(
+            mount.entry(Path::new("data/train.csv")).unwrap().mtime,
+            Some(1_700_000_000)
+        );
+    }
+
+    #[test]
+    fn test_children() {
+        let (_dir, path) = fixture("fixtures.zip");
+        let mount = ArchiveMount::open(&path, ArchiveFormat::Zip, "/fixtures").unwrap();
+
+        assert_eq!(
+            mount.children(Path::new("")),
+            vec![
+                ("README".to_string(), EntryKind::File),
+                ("data".to_string(), EntryKind::Directory),
+            ]
+        );
+        assert_eq!(
+            mount.children(Path::new("data")),
+            vec![("train.csv".to_string(), EntryKind::File)]
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let (_dir, path) = fixture("fixtures.tar");
+        let mount = ArchiveMount::open(&path, ArchiveFormat::Tar, "/fixtures").unwrap();
+
+        assert_eq!(
+            mount.resolve(Path::new("data"), "./train.csv"),
+            Some(PathBuf::from("data/train.csv"))
+        );
+        assert_eq!(
+            mount.resolve(Path::new("data"), "../README"),
+            Some(PathBuf::from("README"))
+        );
+        assert_eq!(mount.resolve(Path::new(""), "../escape"), None);
+        assert_eq!(mount.resolve(Path::new(""), "/etc/passwd"), None);
+    }
+
+    #[test]
+    fn test_oversized_entries_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("huge.tar");
+        let mut builder = tar::Builder::new(File::create(&path).unwrap());
+
+        let mut header = tar::Header::new_gnu();
+        header.set_size(MAX_ENTRY_SIZE + 1);
+        header.set_mode(0o644);
+        header.set_path("huge.bin").unwrap();
+        header.set_cksum();
+        builder.append(&header, io::empty()).unwrap();
+        drop(builder);
+
+        let err = ArchiveMount::open(&path, ArchiveFormat::Tar, "/fixtures").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_oversized_archives_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let payload = vec![0u8; 64 * 1024];
+
+        let tar_gz = dir.path().join("bomb.tar.gz");
+        let encoder =
+            flate2::write::GzEncoder::new(File::create(&tar_gz).unwrap(), Default::default());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(payload.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "zeros.bin", &payload[..])
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let zip_path = dir.path().join("bomb.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for name in ["a.bin", "b.bin"] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(&payload[..payload.len() / 2]).unwrap();
+        }
+        zip.finish().unwrap();
+
+        for (path, format) in [
+            (&tar_gz, ArchiveFormat::TarGz),
+            (&zip_path, ArchiveFormat::Zip),
+        ] {
+            let err =
+                ArchiveMount::open_with_limit(path, format, "/fixtures", 16 * 1024).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", format);
+            assert!(ArchiveMount::open_with_limit(path, format, "/fixtures", 1 << 20).is_ok());
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use wasmtime_wasi::bindings::clocks::wall_clock::Datetime;
+use wasmtime_wasi::bindings::filesystem::types::{
+    DescriptorFlags, DescriptorStat, DescriptorType, DirectoryEntry, ErrorCode, MetadataHashValue,
+    OpenFlags,
+};
+use wasmtime_wasi::{HostInputStream, StreamError, StreamResult, Subscribe, async_trait};
+
+use crate::wasm::filesystem::archive::{ArchiveEntry, ArchiveMount, EntryKind};
+
+pub struct ArchiveDescriptor {
+    mount: Arc<ArchiveMount>,
+    path: PathBuf,
+}
+
+impl ArchiveDescriptor {
+    pub fn root(mount: Arc<ArchiveMount>) -> Self {
+        Self {
+            mount,
+            path: PathBuf::new(),
+        }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    fn entry(&self) -> Result<&ArchiveEntry, ErrorCode> {
+        self.mount.entry(&self.path).ok_or(ErrorCode::NoEntry)
+    }
+
+    fn entry_at(&self, path: &str) -> Result<(PathBuf, &ArchiveEntry), ErrorCode> {
+        let resolved = self
+            .mount
+            .resolve(&self.path, path)
+            .ok_or(ErrorCode::NotPermitted)?;
+        let entry = self.mount.entry(&resolved).ok_or(ErrorCode::NoEntry)?;
+
+        Ok((resolved, entry))
+    }
+
+    fn require_dir(&self) -> Result<(), ErrorCode> {
+        match self.entry()?.kind {
+            EntryKind::Directory => Ok(()),
+            EntryKind::File => Err(ErrorCode::NotDirectory),
+        }
+    }
+
+    pub fn descriptor_type(&self) -> Result<DescriptorType, ErrorCode> {
+        Ok(descriptor_type(self.entry()?.kind))
+    }
+
+    pub fn flags(&self) -> DescriptorFlags {
+        DescriptorFlags::READ
+    }
+
+    pub fn stat(&self) -> Result<DescriptorStat, ErrorCode> {
+        Ok(stat(self.entry()?))
+    }
+
+    pub fn stat_at(&self, path: &str) -> Result<DescriptorStat, ErrorCode> {
+        self.require_dir()?;
+        Ok(stat(self.entry_at(path)?.1))
+    }
+
+    pub fn metadata_hash(&self) -> MetadataHashValue {
+        metadata_hash(&self.mount, &self.path)
+    }
+
+    pub fn metadata_hash_at(&self, path: &str) -> Result<MetadataHashValue, ErrorCode> {
+        self.require_dir()?;
+        let (resolved, _) = self.entry_at(path)?;
+        Ok(metadata_hash(&self.mount, &resolved))
+    }
+
+    pub fn open_at(
+        &self,
+        path: &str,
+        oflags: OpenFlags,
+        flags: DescriptorFlags,
+    ) -> Result<Self, ErrorCode> {
+        self.require_dir()?;
+
+        if flags.intersects(DescriptorFlags::WRITE | DescriptorFlags::MUTATE_DIRECTORY)
+            || oflags.contains(OpenFlags::TRUNCATE)
+        {
+            return Err(ErrorCode::ReadOnly);
+        }
+
+        let (resolved, entry) = match self.entry_at(path) {
+            Ok(found) => found,
+            Err(ErrorCode::NoEntry) if oflags.contains(OpenFlags::CREATE) => {
+                return Err(ErrorCode::ReadOnly);
+            }
+            Err(e) => return Err(e),
+        };
+
+        if oflags.contains(OpenFlags::CREATE | OpenFlags::EXCLUSIVE) {
+            return Err(ErrorCode::Exist);
+        }
+
+        if oflags.contains(OpenFlags::DIRECTORY) && entry.kind != EntryKind::Directory {
+            return Err(ErrorCode::NotDirectory);
+        }
+
+        Ok(Self {
+            mount: Arc::clone(&self.mount),
+            path: resolved,
+        })
+    }
+
+    fn file_size(&self) -> Result<u64, ErrorCode> {
+        let entry = self.entry()?;
+
+        match entry.kind {
+            EntryKind::File => Ok(entry.size),
+            EntryKind::Directory => Err(ErrorCode::IsDirectory),
+        }
+    }
+
+    pub fn read(&self, len: u64, offset: u64) -> Result<(Vec<u8>, bool), ErrorCode> {
+        let size = self.file_size()?;
+        let data = self
+            .mount
+            .read_at(&self.path, offset, len)
+            .map_err(|_| ErrorCode::Io)?;
+        let end = offset.saturating_add(data.len() as u64);
+
+        Ok((data, end >= size))
+    }
+
+    pub fn reader(&self, offset: u64) -> Result<ArchiveReader, ErrorCode> {
+        let size = self.file_size()?;
+
+        Ok(ArchiveReader {
+            mount: Arc::clone(&self.mount),
+            path: self.path.clone(),
+            position: offset,
+            size,
+        })
+    }
+
+    pub fn read_directory(&self) -> Result<ArchiveDirStream, ErrorCode> {
+        self.require_dir()?;
+
+        let entries = self
+            .mount
+            .children(&self.path)
+            .into_iter()
+            .map(|(name, kind)| DirectoryEntry {
+                type_: descriptor_type(kind),
+                name,
+            })
+            .collect::<Vec<_>>();
+
+        Ok(ArchiveDirStream {
+            entries: entries.into_iter(),
+        })
+    }
+}
+
+pub struct ArchiveReader {
+    mount: Arc<ArchiveMount>,
+    path: PathBuf,
+    position: u64,
+    size: u64,
+}
+
+#[async_trait]
+impl Subscribe for ArchiveReader {
+    async fn ready(&mut self) {}
+}
+
+#[async_trait]
+impl HostInputStream for ArchiveReader {
+    fn read(&mut self, size: usize) -> StreamResult<Bytes> {
+        if self.position >= self.size {
+            return Err(StreamError::Closed);
+        }
+
+        let data = self
+            .mount
+            .read_at(&self.path, self.position, size as u64)
+            .map_err(|e| StreamError::LastOperationFailed(e.into()))?;
+
+        self.position += data.len() as u64;
+        Ok(Bytes::from(data))
+    }
+}
+
+pub struct ArchiveDirStream {
+    entries: std::vec::IntoIter<DirectoryEntry>,
+}
+
+impl ArchiveDirStream {
+    pub fn next_entry(&mut self) -> Option<DirectoryEntry> {
+        self.entries.next()
+    }
+}
+
+fn descriptor_type(kind: EntryKind) -> DescriptorType {
+    match kind {
+        EntryKind::Directory => DescriptorType::Directory,
+        EntryKind::File => DescriptorType::RegularFile,
+    }
+}
+
+fn stat(entry: &ArchiveEntry) -> DescriptorStat {
+    let timestamp = entry.mtime.map(|seconds| Datetime {
+        seconds,
+        nanoseconds: 0,
+    });
+
+    DescriptorStat {
+        type_: descriptor_type(entry.kind),
+        link_count: 1,
+        size: entry.size,
+        data_access_timestamp: timestamp,
+        data_modification_timestamp: timestamp,
+        status_change_timestamp: timestamp,
+    }
+}
+
+fn metadata_hash(mount: &ArchiveMount, path: &std::path::Path) -> MetadataHashValue {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(mount.source().as_os_str().as_encoded_bytes());
+    hasher.update(&[0]);
+    hasher.update(path.as_os_str().as_encoded_bytes());
+
+    let hash = hasher.finalize();
+    let bytes = hash.as_bytes();
+
+    MetadataHashValue {
+        lower: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+        upper: u64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm::filesystem::archive::ArchiveFormat;
+    use std::io::Write;
+
+    fn mount() -> (tempfile::TempDir, Arc<ArchiveMount>) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixtures.zip");
+
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("data/train.csv", options).unwrap();
+        zip.write_all(b"a,b,c").unwrap();
+        zip.finish().unwrap();
+
+        let mount = ArchiveMount::open(&path, ArchiveFormat::Zip, "/fixtures").unwrap();
+        (dir, Arc::new(mount))
+    }
+
+    #[test]
+    fn test_open_and_read() {
+        let (_dir, mount) = mount();
+        let root = ArchiveDescriptor::root(mount);
+
+        assert_eq!(root.descriptor_type().unwrap(), DescriptorType::Directory);
+
+        let file = root
+            .open_at("data/train.csv", OpenFlags::empty(), DescriptorFlags::READ)
+            .unwrap();
+
+        assert_eq!(file.stat().unwrap().size, 5);
+        assert_eq!(file.read(3, 0).unwrap(), (b"a,b".to_vec(), false));
+        assert_eq!(file.read(10, 3).unwrap(), (b",c".to_vec(), true));
+        assert_eq!(file.read(10, 100).unwrap(), (Vec::new(), true));
+    }
+
+    #[test]
+    fn test_reader_streams_from_offset() {
+        let (_dir, mount) = mount();
+        let root = ArchiveDescriptor::root(mount);
+        let file = root
+            .open_at("data/train.csv", OpenFlags::empty(), DescriptorFlags::READ)
+            .unwrap();
+
+        let mut reader = file.reader(1).unwrap();
+
+        assert_eq!(reader.read(2).unwrap(), Bytes::from_static(b",b"));
+        assert_eq!(reader.read(10).unwrap(), Bytes::from_static(b",c"));
+        assert!(matches!(reader.read(10), Err(StreamError::Closed)));
+    }
+
+    #[test]
+    fn test_writes_are_rejected() {
+        let (_dir, mount) = mount();
+        let root = ArchiveDescriptor::root(mount);
+
+        assert!(matches!(
+            root.open_at("data/train.csv", OpenFlags::empty(), DescriptorFlags::WRITE),
+            Err(ErrorCode::ReadOnly)
+        ));
+        assert!(matches!(
+            root.open_at("new.txt", OpenFlags::CREATE, DescriptorFlags::READ),
+            Err(ErrorCode::ReadOnly)
+        ));
+        assert!(matches!(
+            root.open_at("data/train.csv", OpenFlags::TRUNCATE, DescriptorFlags::READ),
+            Err(ErrorCode::ReadOnly)
+        ));
+    }
+
+    #[test]
+    fn test_open_errors() {
+        let (_dir, mount) = mount();
+        let root = ArchiveDescriptor::root(mount);
+
+        assert!(matches!(
+            root.open_at("missing", OpenFlags::empty(), DescriptorFlags::READ),
+            Err(ErrorCode::NoEntry)
+        ));
+        assert!(matches!(
+            root.open_at("../outside", OpenFlags::empty(), DescriptorFlags::READ),
+            Err(ErrorCode::NotPermitted)
+        ));
+        assert!(matches!(
+            root.open_at(
+                "data/train.csv",
+                OpenFlags::DIRECTORY,
+                DescriptorFlags::READ
+            ),
+            Err(ErrorCode::NotDirectory)
+        ));
+
+        let data = root
+            .open_at("data", OpenFlags::DIRECTORY, DescriptorFlags::READ)
+            .unwrap();
+        assert!(matches!(data.read(1, 0), Err(ErrorCode::IsDirectory)));
+        assert!(matches!(data.reader(0), Err(ErrorCode::IsDirectory)));
+    }
+
+    #[test]
+    fn test_read_directory() {
+        let (_dir, mount) = mount();
+        let root = ArchiveDescriptor::root(mount);
+
+        let mut stream = root.read_directory().unwrap();
+        let entry = stream.next_entry().unwrap();
+
+        assert_eq!(entry.name, "data");
+        assert_eq!(entry.type_, DescriptorType::Directory);
+        assert!(stream.next_entry().is_none());
+    }
+
+    #[test]
+    fn test_identity() {
+        let (_dir, mount) = mount();
+        let root = ArchiveDescriptor::root(mount);
+
+        let a = root
+            .open_at("data/train.csv", OpenFlags::empty(), DescriptorFlags::READ)
+            .unwrap();
+        let b = root
+            .open_at(
+                "./data/../data/train.csv",
+                OpenFlags::empty(),
+                DescriptorFlags::READ,
+            )
+            .unwrap();
+
+        let hash = |value: MetadataHashValue| (value.lower, value.upper);
+
+        assert_eq!(hash(a.metadata_hash()), hash(b.metadata_hash()));
+        assert_ne!(hash(a.metadata_hash()), hash(root.metadata_hash()));
+        assert_eq!(
+            hash(a.metadata_hash()),
+            hash(root.metadata_hash_at("data/train.csv").unwrap())
+        );
+    }
+}
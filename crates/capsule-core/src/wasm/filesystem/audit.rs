@@ -0,0 +1,198 @@
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileOperation {
+    Open,
+    Read,
+    Write,
+    Create,
+    Delete,
+    Rename,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileAccess {
+    pub operation: FileOperation,
+    pub path: String,
+
+    #[serde(default)]
+    pub bytes: Option<u64>,
+
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileAccessSummary {
+    pub files_opened: u64,
+    pub files_created: u64,
+    pub files_deleted: u64,
+    pub files_renamed: u64,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    pub paths_read: Vec<String>,
+    pub paths_written: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct FileAudit {
+    entries: Mutex<Vec<FileAccess>>,
+}
+
+impl FileAudit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, operation: FileOperation, path: &Path) {
+        self.push(FileAccess {
+            operation,
+            path: clean(path),
+            bytes: None,
+            target: None,
+        });
+    }
+
+    pub fn record_bytes(&self, operation: FileOperation, path: &Path, bytes: u64) {
+        let path = clean(path);
+        let mut entries = self.entries.lock().unwrap();
+
+        if let Some(last) = entries.last_mut()
+            && last.operation == operation
+            && last.path == path
+        {
+            last.bytes = Some(last.bytes.unwrap_or(0) + bytes);
+            return;
+        }
+
+        entries.push(FileAccess {
+            operation,
+            path,
+            bytes: Some(bytes),
+            target: None,
+        });
+    }
+
+    pub fn record_rename(&self, from: &Path, to: &Path) {
+        self.push(FileAccess {
+            operation: FileOperation::Rename,
+            path: clean(from),
+            bytes: None,
+            target: Some(clean(to)),
+        });
+    }
+
+    pub fn entries(&self) -> Vec<FileAccess> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    pub fn summary(&self) -> FileAccessSummary {
+        let mut summary = FileAccessSummary::default();
+        let mut paths_read = BTreeSet::new();
+        let mut paths_written = BTreeSet::new();
+
+        for entry in self.entries.lock().unwrap().iter() {
+            match entry.operation {
+                FileOperation::Open => summary.files_opened += 1,
+                FileOperation::Create => summary.files_created += 1,
+                FileOperation::Delete => summary.files_deleted += 1,
+                FileOperation::Rename => summary.files_renamed += 1,
+                FileOperation::Read => {
+                    summary.bytes_read += entry.bytes.unwrap_or(0);
+                    paths_read.insert(entry.path.clone());
+                }
+                FileOperation::Write => {
+                    summary.bytes_written += entry.bytes.unwrap_or(0);
+                    paths_written.insert(entry.path.clone());
+                }
+            }
+        }
+
+        summary.paths_read = paths_read.into_iter().collect();
+        summary.paths_written = paths_written.into_iter().collect();
+        summary
+    }
+
+    fn push(&self, access: FileAccess) {
+        self.entries.lock().unwrap().push(access);
+    }
+}
+
+fn clean(path: &Path) -> String {
+    let mut cleaned = PathBuf::from("/");
+
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => cleaned.push(part),
+            Component::ParentDir => {
+                cleaned.pop();
+            }
+            _ => {}
+        }
+    }
+
+    cleaned.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_and_writes_are_merged() {
+        let audit = FileAudit::new();
+
+        audit.record(FileOperation::Open, Path::new("/data/input.csv"));
+        audit.record_bytes(FileOperation::Read, Path::new("/data/input.csv"), 10);
+        audit.record_bytes(FileOperation::Read, Path::new("/data/./input.csv"), 5);
+        audit.record_bytes(FileOperation::Write, Path::new("/out/result.json"), 3);
+        audit.record_bytes(FileOperation::Read, Path::new("/data/input.csv"), 1);
+
+        let entries = audit.entries();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[1].bytes, Some(15));
+        assert_eq!(entries[3].bytes, Some(1));
+    }
+
+    #[test]
+    fn test_summary() {
+        let audit = FileAudit::new();
+
+        audit.record(FileOperation::Open, Path::new("/data/input.csv"));
+        audit.record_bytes(FileOperation::Read, Path::new("/data/input.csv"), 10);
+        audit.record(FileOperation::Create, Path::new("/out/tmp.json"));
+        audit.record_bytes(FileOperation::Write, Path::new("/out/tmp.json"), 4);
+        audit.record_rename(Path::new("/out/tmp.json"), Path::new("/out/result.json"));
+        audit.record(FileOperation::Delete, Path::new("/out/old.json"));
+
+        assert_eq!(
+            audit.summary(),
+            FileAccessSummary {
+                files_opened: 1,
+                files_created: 1,
+                files_deleted: 1,
+                files_renamed: 1,
+                bytes_read: 10,
+                bytes_written: 4,
+                paths_read: vec!["/data/input.csv".to_string()],
+                paths_written: vec!["/out/tmp.json".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_paths_are_normalized() {
+        let audit = FileAudit::new();
+
+        audit.record_rename(Path::new("/out/./a.txt"), Path::new("/out/tmp/../b.txt"));
+
+        let entry = &audit.entries()[0];
+        assert_eq!(entry.path, "/out/a.txt");
+        assert_eq!(entry.target.as_deref(), Some("/out/b.txt"));
+    }
+}
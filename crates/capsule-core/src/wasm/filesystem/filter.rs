@@ -0,0 +1,157 @@
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    root: Option<PathBuf>,
+    entries: BTreeSet<PathBuf>,
+    ancestors: BTreeSet<PathBuf>,
+}
+
+impl PathFilter {
+    pub fn new(entries: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut filter = Self::default();
+        filter.extend(entries);
+        filter
+    }
+
+    pub fn set_root(&mut self, root: &Path) {
+        self.root = Some(root.canonicalize().unwrap_or_else(|_| root.to_path_buf()));
+    }
+
+    pub fn extend(&mut self, entries: impl IntoIterator<Item = PathBuf>) {
+        for entry in entries {
+            let Some(entry) = normalize(&entry) else {
+                continue;
+            };
+
+            let mut parent = entry.parent();
+            while let Some(dir) = parent {
+                self.ancestors.insert(dir.to_path_buf());
+                parent = dir.parent();
+            }
+
+            self.entries.insert(entry);
+        }
+    }
+
+    pub fn allows(&self, path: &Path) -> bool {
+        let Some(path) = normalize(path) else {
+            return false;
+        };
+
+        self.matches(&path) && self.allows_resolved(&path)
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        self.ancestors.contains(path) || self.entries.iter().any(|entry| path.starts_with(entry))
+    }
+
+    fn allows_resolved(&self, path: &Path) -> bool {
+        let Some(root) = &self.root else {
+            return true;
+        };
+
+        let full = root.join(path);
+
+        for existing in full.ancestors() {
+            match existing.canonicalize() {
+                Ok(resolved) => {
+                    let Ok(tail) = full.strip_prefix(existing) else {
+                        return false;
+                    };
+
+                    return match resolved.join(tail).strip_prefix(root) {
+                        Ok(relative) => self.matches(relative),
+                        Err(_) => false,
+                    };
+                }
+                Err(_) if existing.symlink_metadata().is_ok() => return false,
+                Err(_) => continue,
+            }
+        }
+
+        false
+    }
+}
+
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+
+    Some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows_matched_entries_and_ancestors() {
+        let filter = PathFilter::new([PathBuf::from("a.csv"), PathBuf::from("nested/b.csv")]);
+
+        assert!(filter.allows(Path::new("")));
+        assert!(filter.allows(Path::new(".")));
+        assert!(filter.allows(Path::new("a.csv")));
+        assert!(filter.allows(Path::new("nested")));
+        assert!(filter.allows(Path::new("nested/./b.csv")));
+        assert!(filter.allows(Path::new("nested/../a.csv")));
+
+        assert!(!filter.allows(Path::new("notes.txt")));
+        assert!(!filter.allows(Path::new("nested/c.csv")));
+        assert!(!filter.allows(Path::new("../a.csv")));
+    }
+
+    #[test]
+    fn test_matched_directory_allows_contents() {
+        let mut filter = PathFilter::default();
+        filter.extend([PathBuf::from("models/small")]);
+
+        assert!(filter.allows(Path::new("models/small/weights.bin")));
+        assert!(!filter.allows(Path::new("models/large/weights.bin")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_are_checked_against_the_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+        std::fs::write(dir.path().join("a.csv"), "a").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink("notes.txt", dir.path().join("b.csv")).unwrap();
+        std::os::unix::fs::symlink("..", dir.path().join("nested/up")).unwrap();
+        std::os::unix::fs::symlink("missing.txt", dir.path().join("c.csv")).unwrap();
+
+        let mut filter = PathFilter::default();
+        filter.set_root(dir.path());
+        filter.extend([
+            PathBuf::from("a.csv"),
+            PathBuf::from("b.csv"),
+            PathBuf::from("c.csv"),
+            PathBuf::from("new.csv"),
+            PathBuf::from("nested"),
+        ]);
+
+        assert!(filter.allows(Path::new("a.csv")));
+        assert!(filter.allows(Path::new("new.csv")));
+        assert!(filter.allows(Path::new("nested")));
+        assert!(filter.allows(Path::new("nested/up")));
+        assert!(filter.allows(Path::new("nested/up/a.csv")));
+
+        assert!(!filter.allows(Path::new("b.csv")));
+        assert!(!filter.allows(Path::new("c.csv")));
+        assert!(!filter.allows(Path::new("nested/up/notes.txt")));
+    }
+}
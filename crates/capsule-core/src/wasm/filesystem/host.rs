@@ -0,0 +1,843 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use wasmtime::component::{Linker, Resource};
+use wasmtime_wasi::bindings::filesystem::preopens;
+use wasmtime_wasi::bindings::filesystem::types::{
+    self, Descriptor, DirectoryEntryStream, ErrorCode, HostDescriptor, HostDirectoryEntryStream,
+};
+use wasmtime_wasi::bindings::io::streams::{InputStream, OutputStream};
+use wasmtime_wasi::pipe::{ClosedOutputStream, MemoryInputPipe};
+use wasmtime_wasi::{FsError, FsResult, WasiImpl};
+
+use crate::wasm::filesystem::archive_node::{ArchiveDescriptor, ArchiveDirStream};
+use crate::wasm::filesystem::audit::{FileAudit, FileOperation};
+use crate::wasm::filesystem::limits::WriteLimits;
+use crate::wasm::filesystem::sandbox::DescriptorInfo;
+use crate::wasm::filesystem::scratch::ScratchDir;
+use crate::wasm::filesystem::stream::{AuditInputStream, AuditOutputStream, QuotaOutputStream};
+use crate::wasm::state::State;
+
+pub struct SandboxFsHost<'a>(pub &'a mut State);
+
+fn sandbox_fs(state: &mut State) -> SandboxFsHost<'_> {
+    SandboxFsHost(state)
+}
+
+pub fn add_to_linker(linker: &mut Linker<State>) -> anyhow::Result<()> {
+    types::add_to_linker_get_host(linker, sandbox_fs)?;
+    preopens::add_to_linker_get_host(linker, sandbox_fs)?;
+    Ok(())
+}
+
+fn borrow(fd: &Resource<Descriptor>) -> Resource<Descriptor> {
+    Resource::new_borrow(fd.rep())
+}
+
+fn record(target: Option<(Arc<FileAudit>, PathBuf)>, operation: FileOperation) {
+    if let Some((audit, path)) = target {
+        audit.record(operation, &path);
+    }
+}
+
+fn record_bytes(target: Option<(Arc<FileAudit>, PathBuf)>, operation: FileOperation, bytes: u64) {
+    if let Some((audit, path)) = target
+        && bytes > 0
+    {
+        audit.record_bytes(operation, &path, bytes);
+    }
+}
+
+impl SandboxFsHost<'_> {
+    fn wasi(&mut self) -> WasiImpl<&mut State> {
+        WasiImpl(&mut *self.0)
+    }
+
+    fn archive(&mut self, fd: &Resource<Descriptor>) -> Option<&mut ArchiveDescriptor> {
+        self.0
+            .table
+            .get_any_mut(fd.rep())
+            .ok()?
+            .downcast_mut::<ArchiveDescriptor>()
+    }
+
+    fn is_archive(&mut self, fd: &Resource<Descriptor>) -> bool {
+        self.archive(fd).is_some()
+    }
+
+    fn push_archive(&mut self, node: ArchiveDescriptor) -> FsResult<Resource<Descriptor>> {
+        let node = self.0.table.push(node)?;
+        Ok(Resource::new_own(node.rep()))
+    }
+
+    fn check_visible(&self, fd: &Resource<Descriptor>, path: &str) -> FsResult<()> {
+        if self.0.fs.is_visible(fd.rep(), Some(path)) {
+            Ok(())
+        } else {
+            Err(ErrorCode::NoEntry.into())
+        }
+    }
+
+    fn scratch_for(&self, fd: &Resource<Descriptor>) -> Option<Arc<ScratchDir>> {
+        self.0.fs.scratch_for(fd.rep())
+    }
+
+    fn limits_for(&self, fd: &Resource<Descriptor>) -> Option<Arc<WriteLimits>> {
+        self.0.fs.limits_for(fd.rep())
+    }
+
+    fn copy_up(&self, fd: &Resource<Descriptor>, path: Option<&str>) -> FsResult<()> {
+        Ok(self.0.fs.copy_up(fd.rep(), path)?)
+    }
+
+    fn audit_path(
+        &self,
+        fd: &Resource<Descriptor>,
+        path: Option<&str>,
+    ) -> Option<(Arc<FileAudit>, PathBuf)> {
+        self.0.fs.audit_path(fd.rep(), path)
+    }
+
+    async fn current_size(&mut self, fd: &Resource<Descriptor>) -> FsResult<u64> {
+        Ok(self.wasi().stat(borrow(fd)).await?.size)
+    }
+
+    async fn current_links(&mut self, fd: &Resource<Descriptor>) -> FsResult<u64> {
+        Ok(self.wasi().stat(borrow(fd)).await?.link_count)
+    }
+
+    async fn scratch_size(&mut self, fd: &Resource<Descriptor>) -> FsResult<u64> {
+        if self.scratch_for(fd).is_none() {
+            return Ok(0);
+        }
+
+        self.current_size(fd).await
+    }
+
+    async fn quota_size(&mut self, fd: &Resource<Descriptor>) -> FsResult<u64> {
+        if self.scratch_for(fd).is_none() && self.limits_for(fd).is_none() {
+            return Ok(0);
+        }
+
+        self.current_size(fd).await
+    }
+
+    async fn entry_size(&mut self, fd: &Resource<Descriptor>, path: &str) -> Option<u64> {
+        let stat = self
+            .wasi()
+            .stat_at(borrow(fd), types::PathFlags::empty(), path.to_string())
+            .await
+            .ok()?;
+
+        match stat.type_ {
+            types::DescriptorType::RegularFile => Some(stat.size),
+            _ => Some(0),
+        }
+    }
+
+    async fn scratch_entry(
+        &mut self,
+        fd: &Resource<Descriptor>,
+        path: &str,
+    ) -> Option<(Arc<ScratchDir>, u64)> {
+        let scratch = self.scratch_for(fd)?;
+        let bytes = self.entry_size(fd, path).await?;
+        Some((scratch, bytes))
+    }
+
+    async fn exists_at(
+        &mut self,
+        fd: &Resource<Descriptor>,
+        path_flags: types::PathFlags,
+        path: &str,
+    ) -> bool {
+        self.wasi()
+            .stat_at(borrow(fd), path_flags, path.to_string())
+            .await
+            .is_ok()
+    }
+
+    async fn check_new_entry(
+        &mut self,
+        fd: &Resource<Descriptor>,
+        path_flags: types::PathFlags,
+        path: &str,
+    ) -> FsResult<()> {
+        let scratch = self.scratch_for(fd);
+        let limits = self.limits_for(fd);
+
+        if (scratch.is_none() && limits.is_none()) || self.exists_at(fd, path_flags, path).await {
+            return Ok(());
+        }
+
+        if let Some(scratch) = &scratch {
+            scratch.reserve(0, 1)?;
+        }
+
+        if let Some(limits) = limits
+            && let Err(code) = limits.reserve_file()
+        {
+            if let Some(scratch) = &scratch {
+                scratch.release(0, 1);
+            }
+            return Err(code.into());
+        }
+
+        Ok(())
+    }
+
+    fn reserve(&self, fd: &Resource<Descriptor>, growth: u64, written: u64) -> FsResult<()> {
+        let scratch = self.scratch_for(fd);
+
+        if let Some(scratch) = &scratch {
+            scratch.reserve(growth, 0)?;
+        }
+
+        if let Some(limits) = self.limits_for(fd)
+            && let Err(code) = limits.reserve_bytes(written)
+        {
+            if let Some(scratch) = &scratch {
+                scratch.release(growth, 0);
+            }
+            return Err(code.into());
+        }
+
+        Ok(())
+    }
+
+    fn wrap_stream(
+        &mut self,
+        fd: &Resource<Descriptor>,
+        stream: Resource<OutputStream>,
+    ) -> FsResult<Resource<OutputStream>> {
+        let scratch = self.scratch_for(fd);
+        let limits = self.limits_for(fd);
+        let audit = self.audit_path(fd, None);
+
+        if scratch.is_none() && limits.is_none() && audit.is_none() {
+            return Ok(stream);
+        }
+
+        let slot = self.0.table.get_mut(&stream)?;
+        let mut inner = std::mem::replace(slot, Box::new(ClosedOutputStream));
+
+        if scratch.is_some() || limits.is_some() {
+            inner = Box::new(QuotaOutputStream::new(inner, scratch, limits));
+        }
+
+        if let Some((audit, path)) = audit {
+            inner = Box::new(AuditOutputStream::new(inner, audit, path));
+        }
+
+        *slot = inner;
+        Ok(stream)
+    }
+
+    fn wrap_input_stream(
+        &mut self,
+        fd: &Resource<Descriptor>,
+        stream: Resource<InputStream>,
+    ) -> FsResult<Resource<InputStream>> {
+        let Some((audit, path)) = self.audit_path(fd, None) else {
+            return Ok(stream);
+        };
+
+        let slot = self.0.table.get_mut(&stream)?;
+        let inner = std::mem::replace(slot, Box::new(MemoryInputPipe::new(Bytes::new())));
+        *slot = Box::new(AuditInputStream::new(inner, audit, path));
+
+        Ok(stream)
+    }
+}
+
+impl preopens::Host for SandboxFsHost<'_> {
+    fn get_directories(&mut self) -> Result<Vec<(Resource<Descriptor>, String)>, anyhow::Error> {
+        let mut directories = preopens::Host::get_directories(&mut self.wasi())?;
+
+        for archive in self.0.fs.archives().to_vec() {
+            let name = archive.guest_path().to_string();
+            let fd = self.push_archive(ArchiveDescriptor::root(archive))?;
+            directories.push((fd, name));
+        }
+
+        for (fd, name) in &directories {
+            self.0.fs.track(
+                fd.rep(),
+                DescriptorInfo {
+                    preopen: name.clone(),
+                    path: Default::default(),
+                },
+            );
+        }
+
+        Ok(directories)
+    }
+}
+
+impl types::Host for SandboxFsHost<'_> {
+    fn convert_error_code(&mut self, err: FsError) -> anyhow::Result<ErrorCode> {
+        types::Host::convert_error_code(&mut self.wasi(), err)
+    }
+
+    fn filesystem_error_code(
+        &mut self,
+        err: Resource<anyhow::Error>,
+    ) -> anyhow::Result<Option<ErrorCode>> {
+        types::Host::filesystem_error_code(&mut self.wasi(), err)
+    }
+}
+
+impl HostDescriptor for SandboxFsHost<'_> {
+    async fn advise(
+        &mut self,
+        fd: Resource<Descriptor>,
+        offset: types::Filesize,
+        len: types::Filesize,
+        advice: types::Advice,
+    ) -> FsResult<()> {
+        if self.is_archive(&fd) {
+            return Ok(());
+        }
+        self.wasi().advise(fd, offset, len, advice).await
+    }
+
+    async fn sync_data(&mut self, fd: Resource<Descriptor>) -> FsResult<()> {
+        if self.is_archive(&fd) {
+            return Ok(());
+        }
+        self.wasi().sync_data(fd).await
+    }
+
+    async fn get_flags(&mut self, fd: Resource<Descriptor>) -> FsResult<types::DescriptorFlags> {
+        if let Some(node) = self.archive(&fd) {
+            return Ok(node.flags());
+        }
+        self.wasi().get_flags(fd).await
+    }
+
+    async fn get_type(&mut self, fd: Resource<Descriptor>) -> FsResult<types::DescriptorType> {
+        if let Some(node) = self.archive(&fd) {
+            return Ok(node.descriptor_type()?);
+        }
+        self.wasi().get_type(fd).await
+    }
+
+    async fn set_size(&mut self, fd: Resource<Descriptor>, size: types::Filesize) -> FsResult<()> {
+        if self.is_archive(&fd) {
+            return Err(ErrorCode::ReadOnly.into());
+        }
+
+        let current = self.quota_size(&fd).await?;
+        let growth = size.saturating_sub(current);
+        self.reserve(&fd, growth, growth)?;
+
+        let scratch = self.scratch_for(&fd);
+        self.wasi().set_size(fd, size).await?;
+
+        if let Some(scratch) = scratch {
+            scratch.release(current.saturating_sub(size), 0);
+        }
+        Ok(())
+    }
+
+    async fn set_times(
+        &mut self,
+        fd: Resource<Descriptor>,
+        atim: types::NewTimestamp,
+        mtim: types::NewTimestamp,
+    ) -> FsResult<()> {
+        if self.is_archive(&fd) {
+            return Err(ErrorCode::ReadOnly.into());
+        }
+
+        if self.0.fs.is_overlay(fd.rep()) && self.current_links(&fd).await? > 1 {
+            return Err(ErrorCode::ReadOnly.into());
+        }
+        self.wasi().set_times(fd, atim, mtim).await
+    }
+
+    async fn read(
+        &mut self,
+        fd: Resource<Descriptor>,
+        len: types::Filesize,
+        offset: types::Filesize,
+    ) -> FsResult<(Vec<u8>, bool)> {
+        let audit = self.audit_path(&fd, None);
+
+        let (data, eof) = if let Some(node) = self.archive(&fd) {
+            node.read(len, offset)?
+        } else {
+            self.wasi().read(fd, len, offset).await?
+        };
+
+        record_bytes(audit, FileOperation::Read, data.len() as u64);
+        Ok((data, eof))
+    }
+
+    async fn write(
+        &mut self,
+        fd: Resource<Descriptor>,
+        buf: Vec<u8>,
+        offset: types::Filesize,
+    ) -> FsResult<types::Filesize> {
+        if self.is_archive(&fd) {
+            return Err(ErrorCode::ReadOnly.into());
+        }
+
+        let len = buf.len() as u64;
+        let growth = offset
+            .saturating_add(len)
+            .saturating_sub(self.scratch_size(&fd).await?);
+        self.reserve(&fd, growth, len)?;
+
+        let audit = self.audit_path(&fd, None);
+        let written = self.wasi().write(fd, buf, offset).await?;
+
+        record_bytes(audit, FileOperation::Write, written);
+        Ok(written)
+    }
+
+    async fn read_directory(
+        &mut self,
+        fd: Resource<Descriptor>,
+    ) -> FsResult<Resource<DirectoryEntryStream>> {
+        if let Some(node) = self.archive(&fd) {
+            let stream = node.read_directory()?;
+            let stream = self.0.table.push(stream)?;
+            return Ok(Resource::new_own(stream.rep()));
+        }
+
+        let info = self
+            .0
+            .fs
+            .is_filtered(fd.rep())
+            .then(|| self.0.fs.info(fd.rep()).cloned())
+            .flatten();
+
+        let stream = self.wasi().read_directory(fd).await?;
+
+        if let Some(info) = info {
+            self.0.fs.track_stream(stream.rep(), info);
+        }
+
+        Ok(stream)
+    }
+
+    async fn sync(&mut self, fd: Resource<Descriptor>) -> FsResult<()> {
+        if self.is_archive(&fd) {
+            return Ok(());
+        }
+        self.wasi().sync(fd).await
+    }
+
+    async fn create_directory_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path: String,
+    ) -> FsResult<()> {
+        if self.is_archive(&fd) {
+            return Err(ErrorCode::ReadOnly.into());
+        }
+
+        self.check_visible(&fd, &path)?;
+        self.check_new_entry(&fd, types::PathFlags::empty(), &path)
+            .await?;
+
+        let audit = self.audit_path(&fd, Some(&path));
+        self.wasi().create_directory_at(fd, path).await?;
+
+        record(audit, FileOperation::Create);
+        Ok(())
+    }
+
+    async fn stat(&mut self, fd: Resource<Descriptor>) -> FsResult<types::DescriptorStat> {
+        if let Some(node) = self.archive(&fd) {
+            return Ok(node.stat()?);
+        }
+        self.wasi().stat(fd).await
+    }
+
+    async fn stat_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: types::PathFlags,
+        path: String,
+    ) -> FsResult<types::DescriptorStat> {
+        if let Some(node) = self.archive(&fd) {
+            return Ok(node.stat_at(&path)?);
+        }
+
+        self.check_visible(&fd, &path)?;
+        self.wasi().stat_at(fd, path_flags, path).await
+    }
+
+    async fn set_times_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: types::PathFlags,
+        path: String,
+        atim: types::NewTimestamp,
+        mtim: types::NewTimestamp,
+    ) -> FsResult<()> {
+        if self.is_archive(&fd) {
+            return Err(ErrorCode::ReadOnly.into());
+        }
+
+        self.check_visible(&fd, &path)?;
+        self.copy_up(&fd, Some(&path))?;
+        self.wasi()
+            .set_times_at(fd, path_flags, path, atim, mtim)
+            .await
+    }
+
+    async fn link_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        old_path_flags: types::PathFlags,
+        old_path: String,
+        new_descriptor: Resource<Descriptor>,
+        new_path: String,
+    ) -> FsResult<()> {
+        if self.is_archive(&fd) || self.is_archive(&new_descriptor) {
+            return Err(ErrorCode::ReadOnly.into());
+        }
+
+        self.check_visible(&fd, &old_path)?;
+        self.check_visible(&new_descriptor, &new_path)?;
+        self.check_new_entry(&new_descriptor, types::PathFlags::empty(), &new_path)
+            .await?;
+
+        let audit = self.audit_path(&new_descriptor, Some(&new_path));
+        self.wasi()
+            .link_at(fd, old_path_flags, old_path, new_descriptor, new_path)
+            .await?;
+
+        record(audit, FileOperation::Create);
+        Ok(())
+    }
+
+    async fn open_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: types::PathFlags,
+        path: String,
+        oflags: types::OpenFlags,
+        flags: types::DescriptorFlags,
+    ) -> FsResult<Resource<Descriptor>> {
+        let info = self.0.fs.info(fd.rep()).map(|info| info.child(&path));
+        let audit = self.audit_path(&fd, Some(&path));
+
+        let (opened, created) = if let Some(node) = self.archive(&fd) {
+            let child = node.open_at(&path, oflags, flags)?;
+            (self.push_archive(child)?, false)
+        } else {
+            self.check_visible(&fd, &path)?;
+
+            let creating = oflags.contains(types::OpenFlags::CREATE);
+
+            if creating
+                || oflags.contains(types::OpenFlags::TRUNCATE)
+                || flags.contains(types::DescriptorFlags::WRITE)
+            {
+                self.copy_up(&fd, Some(&path))?;
+            }
+
+            if creating {
+                self.check_new_entry(&fd, path_flags, &path).await?;
+            }
+
+            let created =
+                creating && audit.is_some() && !self.exists_at(&fd, path_flags, &path).await;
+
+            let truncated = if oflags.contains(types::OpenFlags::TRUNCATE) {
+                self.scratch_entry(&fd, &path).await
+            } else {
+                None
+            };
+
+            let opened = self
+                .wasi()
+                .open_at(fd, path_flags, path, oflags, flags)
+                .await?;
+
+            if let Some((scratch, bytes)) = truncated {
+                scratch.release(bytes, 0);
+            }
+
+            (opened, created)
+        };
+
+        if let Some(info) = info {
+            self.0.fs.track(opened.rep(), info);
+        }
+
+        if created {
+            record(audit.clone(), FileOperation::Create);
+        }
+        record(audit, FileOperation::Open);
+
+        Ok(opened)
+    }
+
+    fn drop(&mut self, fd: Resource<Descriptor>) -> anyhow::Result<()> {
+        self.0.fs.untrack(fd.rep());
+
+        if self.is_archive(&fd) {
+            self.0
+                .table
+                .delete(Resource::<ArchiveDescriptor>::new_own(fd.rep()))?;
+            return Ok(());
+        }
+
+        HostDescriptor::drop(&mut self.wasi(), fd)
+    }
+
+    async fn readlink_at(&mut self, fd: Resource<Descriptor>, path: String) -> FsResult<String> {
+        if self.is_archive(&fd) {
+            return Err(ErrorCode::Invalid.into());
+        }
+
+        self.check_visible(&fd, &path)?;
+        self.wasi().readlink_at(fd, path).await
+    }
+
+    async fn remove_directory_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path: String,
+    ) -> FsResult<()> {
+        if self.is_archive(&fd) {
+            return Err(ErrorCode::ReadOnly.into());
+        }
+
+        self.check_visible(&fd, &path)?;
+
+        let removed = self.scratch_entry(&fd, &path).await;
+        let audit = self.audit_path(&fd, Some(&path));
+        self.wasi().remove_directory_at(fd, path).await?;
+
+        if let Some((scratch, _)) = removed {
+            scratch.release(0, 1);
+        }
+
+        record(audit, FileOperation::Delete);
+        Ok(())
+    }
+
+    async fn rename_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        old_path: String,
+        new_fd: Resource<Descriptor>,
+        new_path: String,
+    ) -> FsResult<()> {
+        if self.is_archive(&fd) || self.is_archive(&new_fd) {
+            return Err(ErrorCode::ReadOnly.into());
+        }
+
+        self.check_visible(&fd, &old_path)?;
+        self.check_visible(&new_fd, &new_path)?;
+
+        let source = self.scratch_for(&fd);
+        let target = self.scratch_for(&new_fd);
+
+        let moved = if source.is_some() != target.is_some() {
+            self.entry_size(&fd, &old_path).await
+        } else {
+            None
+        };
+        let replaced = self.scratch_entry(&new_fd, &new_path).await;
+
+        if let (Some(target), None, Some(bytes)) = (&target, &source, moved) {
+            target.reserve(bytes, 1)?;
+        }
+
+        let from = self.audit_path(&fd, Some(&old_path));
+        let to = self.audit_path(&new_fd, Some(&new_path));
+        let renamed = self.wasi().rename_at(fd, old_path, new_fd, new_path).await;
+
+        match (&source, &target, moved) {
+            (None, Some(target), Some(bytes)) if renamed.is_err() => target.release(bytes, 1),
+            (Some(source), None, Some(bytes)) if renamed.is_ok() => source.release(bytes, 1),
+            _ => {}
+        }
+        renamed?;
+
+        if let Some((scratch, bytes)) = replaced {
+            scratch.release(bytes, 1);
+        }
+
+        if let (Some((audit, from)), Some((_, to))) = (from, to) {
+            audit.record_rename(&from, &to);
+        }
+        Ok(())
+    }
+
+    async fn symlink_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        src_path: String,
+        dest_path: String,
+    ) -> FsResult<()> {
+        if self.is_archive(&fd) {
+            return Err(ErrorCode::ReadOnly.into());
+        }
+
+        self.check_visible(&fd, &dest_path)?;
+        self.check_new_entry(&fd, types::PathFlags::empty(), &dest_path)
+            .await?;
+
+        let audit = self.audit_path(&fd, Some(&dest_path));
+        self.wasi().symlink_at(fd, src_path, dest_path).await?;
+
+        record(audit, FileOperation::Create);
+        Ok(())
+    }
+
+    async fn unlink_file_at(&mut self, fd: Resource<Descriptor>, path: String) -> FsResult<()> {
+        if self.is_archive(&fd) {
+            return Err(ErrorCode::ReadOnly.into());
+        }
+
+        self.check_visible(&fd, &path)?;
+
+        let removed = self.scratch_entry(&fd, &path).await;
+        let audit = self.audit_path(&fd, Some(&path));
+        self.wasi().unlink_file_at(fd, path).await?;
+
+        if let Some((scratch, bytes)) = removed {
+            scratch.release(bytes, 1);
+        }
+
+        record(audit, FileOperation::Delete);
+        Ok(())
+    }
+
+    fn read_via_stream(
+        &mut self,
+        fd: Resource<Descriptor>,
+        offset: types::Filesize,
+    ) -> FsResult<Resource<InputStream>> {
+        let stream = if let Some(node) = self.archive(&fd) {
+            let stream: InputStream = Box::new(node.reader(offset)?);
+            self.0.table.push(stream)?
+        } else {
+            self.wasi().read_via_stream(borrow(&fd), offset)?
+        };
+
+        self.wrap_input_stream(&fd, stream)
+    }
+
+    fn write_via_stream(
+        &mut self,
+        fd: Resource<Descriptor>,
+        offset: types::Filesize,
+    ) -> FsResult<Resource<OutputStream>> {
+        if self.is_archive(&fd) {
+            return Err(ErrorCode::ReadOnly.into());
+        }
+
+        let stream = self.wasi().write_via_stream(borrow(&fd), offset)?;
+        self.wrap_stream(&fd, stream)
+    }
+
+    fn append_via_stream(&mut self, fd: Resource<Descriptor>) -> FsResult<Resource<OutputStream>> {
+        if self.is_archive(&fd) {
+            return Err(ErrorCode::ReadOnly.into());
+        }
+
+        let stream = self.wasi().append_via_stream(borrow(&fd))?;
+        self.wrap_stream(&fd, stream)
+    }
+
+    async fn is_same_object(
+        &mut self,
+        a: Resource<Descriptor>,
+        b: Resource<Descriptor>,
+    ) -> anyhow::Result<bool> {
+        let a_hash = self.archive(&a).map(|node| node.metadata_hash());
+        let b_hash = self.archive(&b).map(|node| node.metadata_hash());
+
+        match (a_hash, b_hash) {
+            (None, None) => self.wasi().is_same_object(a, b).await,
+            (Some(a), Some(b)) => Ok(a.lower == b.lower && a.upper == b.upper),
+            _ => Ok(false),
+        }
+    }
+
+    async fn metadata_hash(
+        &mut self,
+        fd: Resource<Descriptor>,
+    ) -> FsResult<types::MetadataHashValue> {
+        if let Some(node) = self.archive(&fd) {
+            return Ok(node.metadata_hash());
+        }
+        self.wasi().metadata_hash(fd).await
+    }
+
+    async fn metadata_hash_at(
+        &mut self,
+        fd: Resource<Descriptor>,
+        path_flags: types::PathFlags,
+        path: String,
+    ) -> FsResult<types::MetadataHashValue> {
+        if let Some(node) = self.archive(&fd) {
+            return Ok(node.metadata_hash_at(&path)?);
+        }
+
+        self.check_visible(&fd, &path)?;
+        self.wasi().metadata_hash_at(fd, path_flags, path).await
+    }
+}
+
+impl HostDirectoryEntryStream for SandboxFsHost<'_> {
+    async fn read_directory_entry(
+        &mut self,
+        stream: Resource<DirectoryEntryStream>,
+    ) -> FsResult<Option<types::DirectoryEntry>> {
+        if let Some(entries) = self
+            .0
+            .table
+            .get_any_mut(stream.rep())
+            .ok()
+            .and_then(|any| any.downcast_mut::<ArchiveDirStream>())
+        {
+            return Ok(entries.next_entry());
+        }
+
+        let Some(info) = self.0.fs.stream_info(stream.rep()).cloned() else {
+            return self.wasi().read_directory_entry(stream).await;
+        };
+
+        loop {
+            let entry = self
+                .wasi()
+                .read_directory_entry(Resource::new_borrow(stream.rep()))
+                .await?;
+
+            match entry {
+                Some(entry) if !self.0.fs.is_visible_info(&info, Some(&entry.name)) => continue,
+                entry => return Ok(entry),
+            }
+        }
+    }
+
+    fn drop(&mut self, stream: Resource<DirectoryEntryStream>) -> anyhow::Result<()> {
+        let is_archive = self
+            .0
+            .table
+            .get_any_mut(stream.rep())
+            .is_ok_and(|any| any.is::<ArchiveDirStream>());
+
+        if is_archive {
+            self.0
+                .table
+                .delete(Resource::<ArchiveDirStream>::new_own(stream.rep()))?;
+            return Ok(());
+        }
+
+        self.0.fs.untrack_stream(stream.rep());
+        HostDirectoryEntryStream::drop(&mut self.wasi(), stream)
+    }
+}
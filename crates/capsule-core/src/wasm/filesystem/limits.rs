@@ -0,0 +1,150 @@
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use wasmtime_wasi::bindings::filesystem::types::ErrorCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitViolation {
+    WriteBytes(u64),
+    FilesCreated(u64),
+}
+
+impl fmt::Display for LimitViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitViolation::WriteBytes(limit) => {
+                write!(f, "Write quota exceeded: limit is {} bytes", limit)
+            }
+            LimitViolation::FilesCreated(limit) => {
+                write!(f, "File creation quota exceeded: limit is {} files", limit)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct WriteLimits {
+    max_write_bytes: Option<u64>,
+    max_files_created: Option<u64>,
+    bytes_written: AtomicU64,
+    files_created: AtomicU64,
+    violation: Mutex<Option<LimitViolation>>,
+}
+
+impl WriteLimits {
+    pub fn new(max_write_bytes: Option<u64>, max_files_created: Option<u64>) -> Self {
+        Self {
+            max_write_bytes,
+            max_files_created,
+            ..Default::default()
+        }
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written.load(Ordering::SeqCst)
+    }
+
+    pub fn files_created(&self) -> u64 {
+        self.files_created.load(Ordering::SeqCst)
+    }
+
+    pub fn violation(&self) -> Option<LimitViolation> {
+        *self.violation.lock().unwrap()
+    }
+
+    pub fn remaining_bytes(&self) -> Option<u64> {
+        self.max_write_bytes
+            .map(|max| max.saturating_sub(self.bytes_written()))
+    }
+
+    pub fn reserve_bytes(&self, bytes: u64) -> Result<(), ErrorCode> {
+        Self::reserve(&self.bytes_written, bytes, self.max_write_bytes).map_err(|limit| {
+            self.record(LimitViolation::WriteBytes(limit));
+            ErrorCode::InsufficientSpace
+        })
+    }
+
+    pub fn reserve_file(&self) -> Result<(), ErrorCode> {
+        Self::reserve(&self.files_created, 1, self.max_files_created).map_err(|limit| {
+            self.record(LimitViolation::FilesCreated(limit));
+            ErrorCode::Quota
+        })
+    }
+
+    fn reserve(counter: &AtomicU64, amount: u64, max: Option<u64>) -> Result<(), u64> {
+        let Some(max) = max else {
+            counter.fetch_add(amount, Ordering::SeqCst);
+            return Ok(());
+        };
+
+        counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                current.checked_add(amount).filter(|total| *total <= max)
+            })
+            .map(|_| ())
+            .map_err(|_| max)
+    }
+
+    fn record(&self, violation: LimitViolation) {
+        self.violation.lock().unwrap().get_or_insert(violation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_bytes_limit() {
+        let limits = WriteLimits::new(Some(10), None);
+
+        assert!(limits.reserve_bytes(6).is_ok());
+        assert_eq!(limits.remaining_bytes(), Some(4));
+
+        assert!(matches!(
+            limits.reserve_bytes(5),
+            Err(ErrorCode::InsufficientSpace)
+        ));
+        assert_eq!(limits.bytes_written(), 6);
+        assert_eq!(limits.violation(), Some(LimitViolation::WriteBytes(10)));
+
+        assert!(limits.reserve_bytes(4).is_ok());
+        assert_eq!(limits.remaining_bytes(), Some(0));
+    }
+
+    #[test]
+    fn test_files_created_limit() {
+        let limits = WriteLimits::new(None, Some(2));
+
+        assert!(limits.reserve_file().is_ok());
+        assert!(limits.reserve_file().is_ok());
+        assert!(matches!(limits.reserve_file(), Err(ErrorCode::Quota)));
+
+        assert_eq!(limits.files_created(), 2);
+        assert_eq!(limits.violation(), Some(LimitViolation::FilesCreated(2)));
+    }
+
+    #[test]
+    fn test_first_violation_is_kept() {
+        let limits = WriteLimits::new(Some(0), Some(0));
+
+        assert!(limits.reserve_file().is_err());
+        assert!(limits.reserve_bytes(1).is_err());
+
+        assert_eq!(limits.violation(), Some(LimitViolation::FilesCreated(0)));
+    }
+
+    #[test]
+    fn test_unlimited_still_counts() {
+        let limits = WriteLimits::new(None, None);
+
+        assert!(limits.reserve_bytes(1_000).is_ok());
+        assert!(limits.reserve_file().is_ok());
+
+        assert_eq!(limits.bytes_written(), 1_000);
+        assert_eq!(limits.files_created(), 1);
+        assert_eq!(limits.remaining_bytes(), None);
+        assert_eq!(limits.violation(), None);
+    }
+}
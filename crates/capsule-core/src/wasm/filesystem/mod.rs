@@ -0,0 +1,11 @@
+pub mod archive;
+pub mod archive_node;
+pub mod audit;
+pub mod filter;
+pub mod host;
+pub mod limits;
+pub mod outputs;
+pub mod overlay;
+pub mod sandbox;
+pub mod scratch;
+pub mod stream;
@@ -0,0 +1,194 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+use walkdir::WalkDir;
+
+use crate::wasm::execution_policy::OutputsPolicy;
+
+pub const OUTPUTS_GUEST_PATH: &str = "/outputs";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    pub path: String,
+    pub size: u64,
+    pub hash: String,
+
+    #[serde(default)]
+    pub host_path: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum OutputsError {
+    LimitExceeded { total: u64, limit: u64 },
+    Io(io::Error),
+}
+
+impl fmt::Display for OutputsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputsError::LimitExceeded { total, limit } => write!(
+                f,
+                "Outputs exceed size limit: {} bytes written, limit is {} bytes",
+                total, limit
+            ),
+            OutputsError::Io(e) => write!(f, "Failed to collect outputs: {}", e),
+        }
+    }
+}
+
+impl From<io::Error> for OutputsError {
+    fn from(err: io::Error) -> Self {
+        OutputsError::Io(err)
+    }
+}
+
+pub struct OutputsDir {
+    dir: TempDir,
+    max_bytes: Option<u64>,
+}
+
+impl OutputsDir {
+    pub fn new(policy: &OutputsPolicy) -> io::Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("capsule-outputs-")
+            .tempdir()?;
+
+        Ok(Self {
+            dir,
+            max_bytes: policy.max_bytes,
+        })
+    }
+
+    pub fn host_path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn guest_path(&self) -> &str {
+        OUTPUTS_GUEST_PATH
+    }
+
+    pub fn collect(&self, dest: Option<&Path>) -> Result<Vec<Artifact>, OutputsError> {
+        let files = list_files(self.dir.path())?;
+        let total: u64 = files.iter().map(|(_, size)| size).sum();
+
+        if let Some(limit) = self.max_bytes
+            && total > limit
+        {
+            return Err(OutputsError::LimitExceeded { total, limit });
+        }
+
+        let mut artifacts = Vec::with_capacity(files.len());
+
+        for (relative, size) in files {
+            let source = self.dir.path().join(&relative);
+            let hash = blake3::hash(&fs::read(&source)?).to_hex().to_string();
+
+            let host_path = match dest {
+                Some(dest) => {
+                    let target = dest.join(&relative);
+
+                    if let Some(parent) = target.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+
+                    fs::copy(&source, &target)?;
+                    Some(target)
+                }
+                None => None,
+            };
+
+            artifacts.push(Artifact {
+                path: relative.to_string_lossy().replace('\\', "/"),
+                size,
+                hash,
+                host_path,
+            });
+        }
+
+        Ok(artifacts)
+    }
+}
+
+fn list_files(root: &Path) -> io::Result<Vec<(PathBuf, u64)>> {
+    let mut files = Vec::new();
+
+    for entry in WalkDir::new(root).min_depth(1).sort_by_file_name() {
+        let entry = entry.map_err(io::Error::other)?;
+
+        if entry.file_type().is_file() {
+            let relative = entry.path().strip_prefix(root).map_err(io::Error::other)?;
+            let size = entry.metadata().map_err(io::Error::other)?.len();
+            files.push((relative.to_path_buf(), size));
+        }
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outputs(max_bytes: Option<u64>) -> OutputsDir {
+        let outputs = OutputsDir::new(&OutputsPolicy { max_bytes }).unwrap();
+
+        fs::create_dir(outputs.host_path().join("charts")).unwrap();
+        fs::write(outputs.host_path().join("report.md"), "# Report").unwrap();
+        fs::write(outputs.host_path().join("charts/a.svg"), "<svg/>").unwrap();
+
+        outputs
+    }
+
+    #[test]
+    fn test_collect_manifest() {
+        let outputs = outputs(None);
+        let artifacts = outputs.collect(None).unwrap();
+
+        assert_eq!(outputs.guest_path(), "/outputs");
+        assert_eq!(artifacts.len(), 2);
+        assert_eq!(artifacts[0].path, "charts/a.svg");
+        assert_eq!(artifacts[0].size, 6);
+        assert_eq!(
+            artifacts[0].hash,
+            blake3::hash(b"<svg/>").to_hex().to_string()
+        );
+        assert_eq!(artifacts[1].path, "report.md");
+        assert!(artifacts.iter().all(|a| a.host_path.is_none()));
+    }
+
+    #[test]
+    fn test_collect_copies_to_destination() {
+        let outputs = outputs(None);
+        let dest = tempfile::tempdir().unwrap();
+
+        let artifacts = outputs.collect(Some(dest.path())).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dest.path().join("charts/a.svg")).unwrap(),
+            "<svg/>"
+        );
+        assert_eq!(
+            artifacts[1].host_path.as_deref(),
+            Some(dest.path().join("report.md").as_path())
+        );
+    }
+
+    #[test]
+    fn test_collect_size_limit() {
+        let outputs = outputs(Some(10));
+        let dest = tempfile::tempdir().unwrap();
+
+        assert!(matches!(
+            outputs.collect(Some(dest.path())),
+            Err(OutputsError::LimitExceeded {
+                total: 14,
+                limit: 10
+            })
+        ));
+        assert!(!dest.path().join("report.md").exists());
+    }
+}
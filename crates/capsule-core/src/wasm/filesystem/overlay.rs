@@ -0,0 +1,386 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tempfile::{NamedTempFile, TempDir};
+use walkdir::WalkDir;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OverlayChange {
+    pub mount: String,
+    pub path: String,
+    pub kind: ChangeKind,
+}
+
+pub struct OverlayMount {
+    source: PathBuf,
+    guest_path: String,
+    dir: TempDir,
+}
+
+impl OverlayMount {
+    pub fn new(source: &Path, guest_path: &str, work_dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(work_dir)?;
+
+        let dir = tempfile::Builder::new()
+            .prefix("capsule-overlay-")
+            .tempdir_in(work_dir)?;
+
+        link_tree(source, dir.path())?;
+
+        Ok(Self {
+            source: source.to_path_buf(),
+            guest_path: guest_path.to_string(),
+            dir,
+        })
+    }
+
+    pub fn host_path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn guest_path(&self) -> &str {
+        &self.guest_path
+    }
+
+    pub fn copy_up(&self, path: &Path) -> io::Result<()> {
+        let Ok(target) = path.canonicalize() else {
+            return Ok(());
+        };
+
+        if !target.starts_with(self.dir.path().canonicalize()?) {
+            return Ok(());
+        }
+
+        let metadata = fs::metadata(&target)?;
+
+        if !is_shared(&metadata) {
+            return Ok(());
+        }
+
+        let parent = target.parent().unwrap_or(self.dir.path());
+        let mut copy = NamedTempFile::new_in(parent)?;
+
+        io::copy(&mut fs::File::open(&target)?, &mut copy)?;
+        copy.as_file().set_permissions(metadata.permissions())?;
+        copy.persist(&target).map_err(|e| e.error)?;
+
+        Ok(())
+    }
+
+    pub fn changes(&self) -> io::Result<Vec<OverlayChange>> {
+        let mut changes = Vec::new();
+
+        for relative in list_files(self.dir.path())? {
+            let upper = self.dir.path().join(&relative);
+            let lower = self.source.join(&relative);
+
+            let kind = if !lower.is_file() {
+                ChangeKind::Added
+            } else if !same_contents(&lower, &upper)? {
+                ChangeKind::Modified
+            } else {
+                continue;
+            };
+
+            changes.push(self.change(&relative, kind));
+        }
+
+        for relative in list_files(&self.source)? {
+            if !self.dir.path().join(&relative).is_file() {
+                changes.push(self.change(&relative, ChangeKind::Deleted));
+            }
+        }
+
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(changes)
+    }
+
+    pub fn export(&self, changes: &[OverlayChange], dest: &Path) -> io::Result<()> {
+        let root = dest.join(self.guest_path.trim_start_matches('/'));
+
+        for change in changes.iter().filter(|c| c.kind != ChangeKind::Deleted) {
+            let target = root.join(&change.path);
+
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            fs::copy(self.dir.path().join(&change.path), target)?;
+        }
+
+        Ok(())
+    }
+
+    fn change(&self, relative: &Path, kind: ChangeKind) -> OverlayChange {
+        OverlayChange {
+            mount: self.guest_path.clone(),
+            path: relative.to_string_lossy().replace('\\', "/"),
+            kind,
+        }
+    }
+}
+
+fn link_tree(source: &Path, dest: &Path) -> io::Result<()> {
+    for entry in WalkDir::new(source).min_depth(1) {
+        let entry = entry.map_err(io::Error::other)?;
+        let relative = entry
+            .path()
+            .strip_prefix(source)
+            .map_err(io::Error::other)?;
+        let target = dest.join(relative);
+
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if entry.file_type().is_symlink() {
+            copy_symlink(entry.path(), &target)?;
+        } else if entry.file_type().is_file() {
+            link_file(entry.path(), &target)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn link_file(source: &Path, target: &Path) -> io::Result<()> {
+    if fs::hard_link(source, target).is_err() {
+        fs::copy(source, target)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn link_file(source: &Path, target: &Path) -> io::Result<()> {
+    fs::copy(source, target).map(|_| ())
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, target)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, _target: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "Symlinks are not supported in :cow mounts: {}",
+            source.display()
+        ),
+    ))
+}
+
+#[cfg(unix)]
+fn is_shared(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.is_file() && metadata.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn is_shared(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+#[cfg(not(unix))]
+fn same_file(_a: &fs::Metadata, _b: &fs::Metadata) -> bool {
+    false
+}
+
+fn list_files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for entry in WalkDir::new(root).min_depth(1) {
+        let entry = entry.map_err(io::Error::other)?;
+
+        if entry.file_type().is_file() {
+            let relative = entry.path().strip_prefix(root).map_err(io::Error::other)?;
+            files.push(relative.to_path_buf());
+        }
+    }
+
+    Ok(files)
+}
+
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let (a_metadata, b_metadata) = (fs::metadata(a)?, fs::metadata(b)?);
+
+    if same_file(&a_metadata, &b_metadata) {
+        return Ok(true);
+    }
+
+    if a_metadata.len() != b_metadata.len() {
+        return Ok(false);
+    }
+
+    Ok(blake3::hash(&fs::read(a)?) == blake3::hash(&fs::read(b)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_dir() -> TempDir {
+        let source = tempfile::tempdir().unwrap();
+
+        fs::create_dir(source.path().join("src")).unwrap();
+        fs::write(source.path().join("README.md"), "hello").unwrap();
+        fs::write(source.path().join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(source.path().join("src/lib.rs"), "").unwrap();
+
+        source
+    }
+
+    #[test]
+    fn test_overlay_copies_source() {
+        let source = source_dir();
+        let work = tempfile::tempdir().unwrap();
+        let overlay = OverlayMount::new(source.path(), "/repo", work.path()).unwrap();
+
+        assert_eq!(overlay.guest_path(), "/repo");
+        assert_eq!(
+            fs::read_to_string(overlay.host_path().join("src/main.rs")).unwrap(),
+            "fn main() {}"
+        );
+        assert!(overlay.changes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_overlay_writes_do_not_touch_source() {
+        let source = source_dir();
+        let work = tempfile::tempdir().unwrap();
+        let overlay = OverlayMount::new(source.path(), "/repo", work.path()).unwrap();
+
+        let readme = overlay.host_path().join("README.md");
+        overlay.copy_up(&readme).unwrap();
+        fs::write(&readme, "changed").unwrap();
+        fs::write(overlay.host_path().join("src/new.rs"), "mod new;").unwrap();
+        fs::remove_file(overlay.host_path().join("src/lib.rs")).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(source.path().join("README.md")).unwrap(),
+            "hello"
+        );
+        assert!(source.path().join("src/lib.rs").exists());
+        assert!(!source.path().join("src/new.rs").exists());
+
+        let changes = overlay.changes().unwrap();
+        let summary: Vec<_> = changes.iter().map(|c| (c.path.as_str(), c.kind)).collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("README.md", ChangeKind::Modified),
+                ("src/lib.rs", ChangeKind::Deleted),
+                ("src/new.rs", ChangeKind::Added),
+            ]
+        );
+        assert!(changes.iter().all(|c| c.mount == "/repo"));
+    }
+
+    #[test]
+    fn test_overlay_export() {
+        let source = source_dir();
+        let work = tempfile::tempdir().unwrap();
+        let overlay = OverlayMount::new(source.path(), "/repo", work.path()).unwrap();
+        let dest = tempfile::tempdir().unwrap();
+
+        let main = overlay.host_path().join("src/main.rs");
+        overlay.copy_up(&main).unwrap();
+        fs::write(&main, "fn main() { }").unwrap();
+        fs::remove_file(overlay.host_path().join("README.md")).unwrap();
+
+        let changes = overlay.changes().unwrap();
+        overlay.export(&changes, dest.path()).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dest.path().join("repo/src/main.rs")).unwrap(),
+            "fn main() { }"
+        );
+        assert!(!dest.path().join("repo/README.md").exists());
+        assert!(!dest.path().join("repo/src/lib.rs").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_overlay_links_files_until_copy_up() {
+        use std::os::unix::fs::MetadataExt;
+
+        let source = source_dir();
+        let work = tempfile::tempdir().unwrap();
+        let overlay = OverlayMount::new(source.path(), "/repo", work.path()).unwrap();
+        let readme = overlay.host_path().join("README.md");
+
+        assert_eq!(fs::metadata(&readme).unwrap().nlink(), 2);
+
+        overlay.copy_up(&readme).unwrap();
+        overlay
+            .copy_up(&overlay.host_path().join("missing.txt"))
+            .unwrap();
+
+        assert_eq!(fs::metadata(&readme).unwrap().nlink(), 1);
+        assert_eq!(fs::read_to_string(&readme).unwrap(), "hello");
+
+        fs::write(&readme, "changed").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(source.path().join("README.md")).unwrap(),
+            "hello"
+        );
+        assert_eq!(
+            fs::metadata(source.path().join("README.md"))
+                .unwrap()
+                .nlink(),
+            1
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_overlay_preserves_symlinks() {
+        let source = source_dir();
+        let outside = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+
+        fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink("src/main.rs", source.path().join("main.rs")).unwrap();
+        std::os::unix::fs::symlink(
+            outside.path().join("secret.txt"),
+            source.path().join("secret.txt"),
+        )
+        .unwrap();
+
+        let overlay = OverlayMount::new(source.path(), "/repo", work.path()).unwrap();
+        let link = overlay.host_path().join("main.rs");
+
+        assert_eq!(fs::read_link(&link).unwrap(), Path::new("src/main.rs"));
+        assert_eq!(fs::read_to_string(&link).unwrap(), "fn main() {}");
+
+        overlay
+            .copy_up(&overlay.host_path().join("secret.txt"))
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(outside.path().join("secret.txt")).unwrap(),
+            "secret"
+        );
+    }
+}
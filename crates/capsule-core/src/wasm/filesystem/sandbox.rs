@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::wasm::execution_policy::ExecutionPolicy;
+use crate::wasm::filesystem::archive::ArchiveMount;
+use crate::wasm::filesystem::audit::FileAudit;
+use crate::wasm::filesystem::filter::PathFilter;
+use crate::wasm::filesystem::limits::WriteLimits;
+use crate::wasm::filesystem::outputs::OutputsDir;
+use crate::wasm::filesystem::overlay::{OverlayChange, OverlayMount};
+use crate::wasm::filesystem::scratch::ScratchDir;
+use crate::wasm::utilities::path_validator::FileAccessMode;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorInfo {
+    pub preopen: String,
+    pub path: PathBuf,
+}
+
+impl DescriptorInfo {
+    pub fn guest_path(&self) -> PathBuf {
+        Path::new(&self.preopen).join(&self.path)
+    }
+
+    pub fn child(&self, path: &str) -> Self {
+        Self {
+            preopen: self.preopen.clone(),
+            path: self.path.join(path),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SandboxFs {
+    descriptors: HashMap<u32, DescriptorInfo>,
+    mounts: HashMap<String, FileAccessMode>,
+    scratch: Option<Arc<ScratchDir>>,
+    limits: Option<Arc<WriteLimits>>,
+    overlays: Vec<OverlayMount>,
+    archives: Vec<Arc<ArchiveMount>>,
+    audit: Option<Arc<FileAudit>>,
+    outputs: Option<OutputsDir>,
+    filters: HashMap<String, PathFilter>,
+    filtered_streams: HashMap<u32, DescriptorInfo>,
+}
+
+impl SandboxFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn for_policy(policy: &ExecutionPolicy) -> Self {
+        let fs = Self::new().with_limits(WriteLimits::new(
+            policy.max_write_bytes,
+            policy.max_files_created,
+        ));
+
+        if policy.audit_files {
+            fs.with_audit(FileAudit::new())
+        } else {
+            fs
+        }
+    }
+
+    pub fn with_scratch(mut self, scratch: ScratchDir) -> Self {
+        self.scratch = Some(Arc::new(scratch));
+        self
+    }
+
+    pub fn with_limits(mut self, limits: WriteLimits) -> Self {
+        self.limits = Some(Arc::new(limits));
+        self
+    }
+
+    pub fn limits(&self) -> Option<&Arc<WriteLimits>> {
+        self.limits.as_ref()
+    }
+
+    pub fn with_audit(mut self, audit: FileAudit) -> Self {
+        self.audit = Some(Arc::new(audit));
+        self
+    }
+
+    pub fn audit(&self) -> Option<&Arc<FileAudit>> {
+        self.audit.as_ref()
+    }
+
+    pub fn with_outputs(mut self, outputs: OutputsDir) -> Self {
+        self.outputs = Some(outputs);
+        self
+    }
+
+    pub fn outputs(&self) -> Option<&OutputsDir> {
+        self.outputs.as_ref()
+    }
+
+    pub fn add_mount(&mut self, guest_path: &str, mode: FileAccessMode) {
+        self.mounts.insert(guest_path.to_string(), mode);
+    }
+
+    pub fn add_archive(&mut self, archive: ArchiveMount) {
+        self.archives.push(Arc::new(archive));
+    }
+
+    pub fn archives(&self) -> &[Arc<ArchiveMount>] {
+        &self.archives
+    }
+
+    pub fn add_filter(&mut self, guest_path: &str, root: &Path, entries: Vec<PathBuf>) {
+        let filter = self.filters.entry(guest_path.to_string()).or_default();
+
+        filter.set_root(root);
+        filter.extend(entries);
+    }
+
+    pub fn is_visible(&self, rep: u32, path: Option<&str>) -> bool {
+        let Some(info) = self.info(rep) else {
+            return true;
+        };
+
+        self.is_visible_info(info, path)
+    }
+
+    pub fn is_visible_info(&self, info: &DescriptorInfo, path: Option<&str>) -> bool {
+        let Some(filter) = self.filters.get(&info.preopen) else {
+            return true;
+        };
+
+        match path {
+            Some(path) => filter.allows(&info.path.join(path)),
+            None => filter.allows(&info.path),
+        }
+    }
+
+    pub fn is_filtered(&self, rep: u32) -> bool {
+        self.info(rep)
+            .is_some_and(|info| self.filters.contains_key(&info.preopen))
+    }
+
+    pub fn track_stream(&mut self, rep: u32, info: DescriptorInfo) {
+        self.filtered_streams.insert(rep, info);
+    }
+
+    pub fn untrack_stream(&mut self, rep: u32) {
+        self.filtered_streams.remove(&rep);
+    }
+
+    pub fn stream_info(&self, rep: u32) -> Option<&DescriptorInfo> {
+        self.filtered_streams.get(&rep)
+    }
+
+    pub fn add_overlay(&mut self, overlay: OverlayMount) {
+        self.overlays.push(overlay);
+    }
+
+    pub fn is_overlay(&self, rep: u32) -> bool {
+        self.overlay_for(rep).is_some()
+    }
+
+    pub fn copy_up(&self, rep: u32, path: Option<&str>) -> io::Result<()> {
+        let (Some(info), Some(overlay)) = (self.info(rep), self.overlay_for(rep)) else {
+            return Ok(());
+        };
+
+        let host_path = overlay.host_path().join(&info.path);
+
+        match path {
+            Some(path) => overlay.copy_up(&host_path.join(path)),
+            None => overlay.copy_up(&host_path),
+        }
+    }
+
+    fn overlay_for(&self, rep: u32) -> Option<&OverlayMount> {
+        let info = self.info(rep)?;
+        self.overlays
+            .iter()
+            .find(|o| o.guest_path() == info.preopen)
+    }
+
+    pub fn overlay_changes(&self, export_to: Option<&Path>) -> io::Result<Vec<OverlayChange>> {
+        let mut all_changes = Vec::new();
+
+        for overlay in &self.overlays {
+            let changes = overlay.changes()?;
+
+            if let Some(dest) = export_to {
+                overlay.export(&changes, dest)?;
+            }
+
+            all_changes.extend(changes);
+        }
+
+        if let Some(dest) = export_to
+            && !self.overlays.is_empty()
+        {
+            let manifest = serde_json::to_string_pretty(&all_changes).map_err(io::Error::other)?;
+            fs::create_dir_all(dest)?;
+            fs::write(dest.join("changes.json"), manifest)?;
+        }
+
+        Ok(all_changes)
+    }
+
+    pub fn scratch(&self) -> Option<&Arc<ScratchDir>> {
+        self.scratch.as_ref()
+    }
+
+    pub fn track(&mut self, rep: u32, info: DescriptorInfo) {
+        self.descriptors.insert(rep, info);
+    }
+
+    pub fn untrack(&mut self, rep: u32) {
+        self.descriptors.remove(&rep);
+    }
+
+    pub fn info(&self, rep: u32) -> Option<&DescriptorInfo> {
+        self.descriptors.get(&rep)
+    }
+
+    pub fn scratch_for(&self, rep: u32) -> Option<Arc<ScratchDir>> {
+        let info = self.info(rep)?;
+
+        self.scratch
+            .as_ref()
+            .filter(|scratch| scratch.guest_path() == info.preopen)
+            .cloned()
+    }
+
+    pub fn audit_path(&self, rep: u32, path: Option<&str>) -> Option<(Arc<FileAudit>, PathBuf)> {
+        let audit = self.audit.clone()?;
+        let info = self.info(rep)?;
+
+        let guest_path = match path {
+            Some(path) => info.child(path).guest_path(),
+            None => info.guest_path(),
+        };
+
+        Some((audit, guest_path))
+    }
+
+    pub fn limits_for(&self, rep: u32) -> Option<Arc<WriteLimits>> {
+        let info = self.info(rep)?;
+
+        match self.mounts.get(&info.preopen) {
+            Some(FileAccessMode::ReadWrite) => self.limits.clone(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm::execution_policy::ScratchPolicy;
+
+    #[test]
+    fn test_descriptor_info_child() {
+        let root = DescriptorInfo {
+            preopen: "workspace".to_string(),
+            path: PathBuf::new(),
+        };
+
+        let child = root.child("data").child("file.txt");
+
+        assert_eq!(child.preopen, "workspace");
+        assert_eq!(child.path, PathBuf::from("data/file.txt"));
+        assert_eq!(child.guest_path(), PathBuf::from("workspace/data/file.txt"));
+    }
+
+    #[test]
+    fn test_scratch_for_only_matches_scratch_preopen() {
+        let scratch = ScratchDir::new(&ScratchPolicy::default()).unwrap();
+        let guest_path = scratch.guest_path().to_string();
+
+        let mut fs = SandboxFs::new().with_scratch(scratch);
+
+        fs.track(
+            1,
+            DescriptorInfo {
+                preopen: guest_path,
+                path: PathBuf::from("nested"),
+            },
+        );
+        fs.track(
+            2,
+            DescriptorInfo {
+                preopen: "workspace".to_string(),
+                path: PathBuf::new(),
+            },
+        );
+
+        assert!(fs.scratch_for(1).is_some());
+        assert!(fs.scratch_for(2).is_none());
+        assert!(fs.scratch_for(3).is_none());
+
+        fs.untrack(1);
+        assert!(fs.scratch_for(1).is_none());
+    }
+
+    #[test]
+    fn test_limits_for_only_matches_rw_mounts() {
+        let scratch = ScratchDir::new(&ScratchPolicy::default()).unwrap();
+        let scratch_path = scratch.guest_path().to_string();
+
+        let mut fs = SandboxFs::new()
+            .with_scratch(scratch)
+            .with_limits(WriteLimits::new(Some(10), None));
+
+        fs.add_mount("/workspace", FileAccessMode::ReadWrite);
+        fs.add_mount("/repo", FileAccessMode::CopyOnWrite);
+        fs.add_mount("/data", FileAccessMode::ReadOnly);
+
+        for (rep, preopen) in [
+            (1, scratch_path.as_str()),
+            (2, "/workspace"),
+            (3, "/repo"),
+            (4, "/data"),
+            (5, "/outputs"),
+        ] {
+            fs.track(
+                rep,
+                DescriptorInfo {
+                    preopen: preopen.to_string(),
+                    path: PathBuf::new(),
+                },
+            );
+        }
+
+        assert!(fs.limits_for(1).is_none());
+        assert!(fs.limits_for(2).is_some());
+        assert!(fs.limits_for(3).is_none());
+        assert!(fs.limits_for(4).is_none());
+        assert!(fs.limits_for(5).is_none());
+        assert!(fs.limits_for(6).is_none());
+    }
+
+    #[test]
+    fn test_audit_path() {
+        let mut fs = SandboxFs::new();
+        fs.track(
+            1,
+            DescriptorInfo {
+                preopen: "/data".to_string(),
+                path: PathBuf::from("nested"),
+            },
+        );
+        assert!(fs.audit_path(1, None).is_none());
+
+        let fs = SandboxFs {
+            audit: Some(Arc::new(FileAudit::new())),
+            ..fs
+        };
+
+        assert_eq!(
+            fs.audit_path(1, Some("a.txt")).unwrap().1,
+            PathBuf::from("/data/nested/a.txt")
+        );
+        assert_eq!(
+            fs.audit_path(1, None).unwrap().1,
+            PathBuf::from("/data/nested")
+        );
+        assert!(fs.audit_path(2, None).is_none());
+    }
+
+    #[test]
+    fn test_filtered_preopen_visibility() {
+        let root = tempfile::tempdir().unwrap();
+        let mut fs = SandboxFs::new();
+        fs.add_filter("/data", root.path(), vec![PathBuf::from("a.csv")]);
+        fs.add_filter("/data", root.path(), vec![PathBuf::from("nested/b.csv")]);
+
+        fs.track(
+            1,
+            DescriptorInfo {
+                preopen: "/data".to_string(),
+                path: PathBuf::new(),
+            },
+        );
+        fs.track(
+            2,
+            DescriptorInfo {
+                preopen: "/data".to_string(),
+                path: PathBuf::from("nested"),
+            },
+        );
+        fs.track(
+            3,
+            DescriptorInfo {
+                preopen: "/workspace".to_string(),
+                path: PathBuf::new(),
+            },
+        );
+
+        assert!(fs.is_filtered(1));
+        assert!(fs.is_visible(1, Some("a.csv")));
+        assert!(fs.is_visible(1, Some("nested")));
+        assert!(!fs.is_visible(1, Some("secret.env")));
+        assert!(fs.is_visible(2, Some("b.csv")));
+        assert!(!fs.is_visible(2, Some("c.csv")));
+
+        assert!(!fs.is_filtered(3));
+        assert!(fs.is_visible(3, Some("anything")));
+    }
+
+    #[test]
+    fn test_overlay_changes_export_manifest() {
+        let source = tempfile::tempdir().unwrap();
+        std::fs::write(source.path().join("a.txt"), "a").unwrap();
+
+        let work = tempfile::tempdir().unwrap();
+        let overlay = OverlayMount::new(source.path(), "/repo", work.path()).unwrap();
+        std::fs::write(overlay.host_path().join("b.txt"), "b").unwrap();
+
+        let mut fs = SandboxFs::new();
+        fs.add_overlay(overlay);
+
+        let dest = tempfile::tempdir().unwrap();
+        let changes = fs.overlay_changes(Some(dest.path())).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "b.txt");
+        assert!(dest.path().join("repo/b.txt").exists());
+
+        let manifest: Vec<OverlayChange> = serde_json::from_str(
+            &std::fs::read_to_string(dest.path().join("changes.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(manifest, changes);
+    }
+
+    #[test]
+    fn test_copy_up_only_touches_overlay_descriptors() {
+        let source = tempfile::tempdir().unwrap();
+        std::fs::create_dir(source.path().join("src")).unwrap();
+        std::fs::write(source.path().join("src/a.txt"), "a").unwrap();
+
+        let work = tempfile::tempdir().unwrap();
+        let overlay = OverlayMount::new(source.path(), "/repo", work.path()).unwrap();
+        let upper = overlay.host_path().join("src/a.txt");
+
+        let mut fs = SandboxFs::new();
+        fs.add_overlay(overlay);
+        fs.track(
+            1,
+            DescriptorInfo {
+                preopen: "/repo".to_string(),
+                path: PathBuf::from("src"),
+            },
+        );
+        fs.track(
+            2,
+            DescriptorInfo {
+                preopen: "/data".to_string(),
+                path: PathBuf::new(),
+            },
+        );
+
+        assert!(fs.is_overlay(1));
+        assert!(!fs.is_overlay(2));
+
+        fs.copy_up(2, Some("src/a.txt")).unwrap();
+        fs.copy_up(1, Some("a.txt")).unwrap();
+        std::fs::write(&upper, "changed").unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(source.path().join("src/a.txt")).unwrap(),
+            "a"
+        );
+    }
+
+    #[test]
+    fn test_overlay_changes_without_overlays() {
+        let dest = tempfile::tempdir().unwrap();
+        let changes = SandboxFs::new().overlay_changes(Some(dest.path())).unwrap();
+
+        assert!(changes.is_empty());
+        assert!(!dest.path().join("changes.json").exists());
+    }
+}
@@ -0,0 +1,167 @@
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tempfile::TempDir;
+use wasmtime_wasi::bindings::filesystem::types::ErrorCode;
+
+use crate::wasm::execution_policy::ScratchPolicy;
+
+pub const SCRATCH_GUEST_PATH: &str = "/tmp";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ScratchUsage {
+    pub bytes: u64,
+    pub inodes: u64,
+}
+
+pub struct ScratchDir {
+    dir: TempDir,
+    max_bytes: Option<u64>,
+    max_inodes: Option<u64>,
+    bytes: AtomicU64,
+    inodes: AtomicU64,
+}
+
+impl ScratchDir {
+    pub fn new(policy: &ScratchPolicy) -> io::Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("capsule-scratch-")
+            .tempdir()?;
+
+        Ok(Self {
+            dir,
+            max_bytes: policy.max_bytes,
+            max_inodes: policy.max_inodes,
+            bytes: AtomicU64::new(0),
+            inodes: AtomicU64::new(0),
+        })
+    }
+
+    pub fn host_path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn guest_path(&self) -> &str {
+        SCRATCH_GUEST_PATH
+    }
+
+    pub fn usage(&self) -> ScratchUsage {
+        ScratchUsage {
+            bytes: self.bytes.load(Ordering::SeqCst),
+            inodes: self.inodes.load(Ordering::SeqCst),
+        }
+    }
+
+    pub fn remaining_bytes(&self) -> Option<u64> {
+        self.max_bytes
+            .map(|max| max.saturating_sub(self.bytes.load(Ordering::SeqCst)))
+    }
+
+    pub fn reserve(&self, bytes: u64, inodes: u64) -> Result<(), ErrorCode> {
+        if !Self::add(&self.bytes, bytes, self.max_bytes) {
+            return Err(ErrorCode::InsufficientSpace);
+        }
+
+        if !Self::add(&self.inodes, inodes, self.max_inodes) {
+            self.release(bytes, 0);
+            return Err(ErrorCode::Quota);
+        }
+
+        Ok(())
+    }
+
+    pub fn release(&self, bytes: u64, inodes: u64) {
+        Self::sub(&self.bytes, bytes);
+        Self::sub(&self.inodes, inodes);
+    }
+
+    fn add(counter: &AtomicU64, amount: u64, max: Option<u64>) -> bool {
+        counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                current
+                    .checked_add(amount)
+                    .filter(|total| max.is_none_or(|max| *total <= max))
+            })
+            .is_ok()
+    }
+
+    fn sub(counter: &AtomicU64, amount: u64) {
+        let _ = counter.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+            Some(current.saturating_sub(amount))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch_dir_is_removed_on_drop() {
+        let scratch = ScratchDir::new(&ScratchPolicy::default()).unwrap();
+        let path = scratch.host_path().to_path_buf();
+
+        assert!(path.is_dir());
+        assert_eq!(scratch.guest_path(), "/tmp");
+
+        drop(scratch);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_scratch_usage() {
+        let scratch = ScratchDir::new(&ScratchPolicy::default()).unwrap();
+
+        scratch.reserve(0, 1).unwrap();
+        scratch.reserve(5, 1).unwrap();
+        scratch.reserve(3, 1).unwrap();
+        assert_eq!(
+            scratch.usage(),
+            ScratchUsage {
+                bytes: 8,
+                inodes: 3,
+            }
+        );
+
+        scratch.release(5, 1);
+        scratch.release(100, 100);
+        assert_eq!(scratch.usage(), ScratchUsage::default());
+    }
+
+    #[test]
+    fn test_scratch_quota() {
+        let scratch = ScratchDir::new(&ScratchPolicy {
+            max_bytes: Some(10),
+            max_inodes: Some(2),
+        })
+        .unwrap();
+
+        scratch.reserve(5, 1).unwrap();
+
+        assert_eq!(scratch.remaining_bytes(), Some(5));
+        assert!(matches!(
+            scratch.reserve(6, 0),
+            Err(ErrorCode::InsufficientSpace)
+        ));
+        assert!(matches!(scratch.reserve(1, 2), Err(ErrorCode::Quota)));
+        assert_eq!(
+            scratch.usage(),
+            ScratchUsage {
+                bytes: 5,
+                inodes: 1,
+            }
+        );
+
+        assert!(scratch.reserve(5, 1).is_ok());
+        assert_eq!(scratch.remaining_bytes(), Some(0));
+    }
+
+    #[test]
+    fn test_scratch_without_quota() {
+        let scratch = ScratchDir::new(&ScratchPolicy::default()).unwrap();
+
+        assert_eq!(scratch.remaining_bytes(), None);
+        assert!(scratch.reserve(u64::MAX / 2, 1_000_000).is_ok());
+    }
+}
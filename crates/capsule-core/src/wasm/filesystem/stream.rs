@@ -0,0 +1,381 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use wasmtime_wasi::bindings::filesystem::types::ErrorCode;
+use wasmtime_wasi::{
+    HostInputStream, HostOutputStream, InputStream, OutputStream, StreamError, StreamResult,
+    Subscribe, async_trait,
+};
+
+use crate::wasm::filesystem::audit::{FileAudit, FileOperation};
+use crate::wasm::filesystem::limits::WriteLimits;
+use crate::wasm::filesystem::scratch::ScratchDir;
+
+pub struct QuotaOutputStream {
+    inner: OutputStream,
+    scratch: Option<Arc<ScratchDir>>,
+    limits: Option<Arc<WriteLimits>>,
+}
+
+impl QuotaOutputStream {
+    pub fn new(
+        inner: OutputStream,
+        scratch: Option<Arc<ScratchDir>>,
+        limits: Option<Arc<WriteLimits>>,
+    ) -> Self {
+        Self {
+            inner,
+            scratch,
+            limits,
+        }
+    }
+
+    fn remaining(&self) -> Option<u64> {
+        let scratch = self.scratch.as_ref().and_then(|s| s.remaining_bytes());
+        let limits = self.limits.as_ref().and_then(|l| l.remaining_bytes());
+
+        match (scratch, limits) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+#[async_trait]
+impl Subscribe for QuotaOutputStream {
+    async fn ready(&mut self) {
+        self.inner.ready().await
+    }
+}
+
+#[async_trait]
+impl HostOutputStream for QuotaOutputStream {
+    fn write(&mut self, bytes: Bytes) -> StreamResult<()> {
+        let len = bytes.len() as u64;
+
+        if let Some(scratch) = &self.scratch
+            && let Err(code) = scratch.reserve(len, 0)
+        {
+            return Err(quota_exceeded(code));
+        }
+
+        if let Some(limits) = &self.limits
+            && let Err(code) = limits.reserve_bytes(len)
+        {
+            if let Some(scratch) = &self.scratch {
+                scratch.release(len, 0);
+            }
+            return Err(quota_exceeded(code));
+        }
+
+        self.inner.write(bytes)
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        self.inner.flush()
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        let permit = self.inner.check_write()?;
+
+        match self.remaining() {
+            Some(0) if permit > 0 => Err(StreamError::LastOperationFailed(anyhow::anyhow!(
+                "Write quota exceeded"
+            ))),
+            Some(remaining) => Ok(permit.min(remaining as usize)),
+            None => Ok(permit),
+        }
+    }
+
+    async fn cancel(&mut self) {
+        self.inner.cancel().await
+    }
+}
+
+pub struct AuditInputStream {
+    inner: InputStream,
+    audit: Arc<FileAudit>,
+    path: PathBuf,
+}
+
+impl AuditInputStream {
+    pub fn new(inner: InputStream, audit: Arc<FileAudit>, path: PathBuf) -> Self {
+        Self { inner, audit, path }
+    }
+
+    fn record(&self, bytes: &Bytes) {
+        if !bytes.is_empty() {
+            self.audit
+                .record_bytes(FileOperation::Read, &self.path, bytes.len() as u64);
+        }
+    }
+}
+
+#[async_trait]
+impl Subscribe for AuditInputStream {
+    async fn ready(&mut self) {
+        self.inner.ready().await
+    }
+}
+
+#[async_trait]
+impl HostInputStream for AuditInputStream {
+    fn read(&mut self, size: usize) -> StreamResult<Bytes> {
+        let bytes = self.inner.read(size)?;
+        self.record(&bytes);
+        Ok(bytes)
+    }
+
+    async fn blocking_read(&mut self, size: usize) -> StreamResult<Bytes> {
+        let bytes = self.inner.blocking_read(size).await?;
+        self.record(&bytes);
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, nelem: usize) -> StreamResult<usize> {
+        self.inner.skip(nelem)
+    }
+
+    async fn cancel(&mut self) {
+        self.inner.cancel().await
+    }
+}
+
+pub struct AuditOutputStream {
+    inner: OutputStream,
+    audit: Arc<FileAudit>,
+    path: PathBuf,
+}
+
+impl AuditOutputStream {
+    pub fn new(inner: OutputStream, audit: Arc<FileAudit>, path: PathBuf) -> Self {
+        Self { inner, audit, path }
+    }
+}
+
+#[async_trait]
+impl Subscribe for AuditOutputStream {
+    async fn ready(&mut self) {
+        self.inner.ready().await
+    }
+}
+
+#[async_trait]
+impl HostOutputStream for AuditOutputStream {
+    fn write(&mut self, bytes: Bytes) -> StreamResult<()> {
+        let len = bytes.len() as u64;
+        self.inner.write(bytes)?;
+
+        if len > 0 {
+            self.audit
+                .record_bytes(FileOperation::Write, &self.path, len);
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        self.inner.flush()
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        self.inner.check_write()
+    }
+
+    async fn cancel(&mut self) {
+        self.inner.cancel().await
+    }
+}
+
+fn quota_exceeded(code: ErrorCode) -> StreamError {
+    StreamError::LastOperationFailed(anyhow::anyhow!("Write quota exceeded: {:?}", code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm::execution_policy::ScratchPolicy;
+    use wasmtime::component::ResourceTable;
+    use wasmtime_wasi::bindings::filesystem::preopens::Host as _;
+    use wasmtime_wasi::bindings::filesystem::types::{
+        DescriptorFlags, HostDescriptor, OpenFlags, PathFlags,
+    };
+    use wasmtime_wasi::pipe::{MemoryInputPipe, MemoryOutputPipe};
+    use wasmtime_wasi::runtime::in_tokio;
+    use wasmtime_wasi::{DirPerms, FilePerms, WasiCtx, WasiCtxBuilder, WasiImpl, WasiView};
+
+    struct FileView {
+        ctx: WasiCtx,
+        table: ResourceTable,
+    }
+
+    impl WasiView for FileView {
+        fn ctx(&mut self) -> &mut WasiCtx {
+            &mut self.ctx
+        }
+
+        fn table(&mut self) -> &mut ResourceTable {
+            &mut self.table
+        }
+    }
+
+    async fn file_stream(dir: &std::path::Path, name: &str) -> OutputStream {
+        let mut builder = WasiCtxBuilder::new();
+        builder
+            .preopened_dir(dir, "/tmp", DirPerms::all(), FilePerms::all())
+            .unwrap();
+
+        let mut view = FileView {
+            ctx: builder.build(),
+            table: ResourceTable::new(),
+        };
+        let mut wasi = WasiImpl(&mut view);
+
+        let (root, _) = wasi.get_directories().unwrap().remove(0);
+        let file = wasi
+            .open_at(
+                root,
+                PathFlags::empty(),
+                name.to_string(),
+                OpenFlags::CREATE,
+                DescriptorFlags::WRITE,
+            )
+            .await
+            .unwrap();
+        let stream = wasi.write_via_stream(file, 0).unwrap();
+
+        view.table.delete(stream).unwrap()
+    }
+
+    async fn write_all(stream: &mut QuotaOutputStream, bytes: &'static [u8]) -> StreamResult<()> {
+        stream.ready().await;
+        stream.write(Bytes::from_static(bytes))?;
+        stream.flush()?;
+        stream.ready().await;
+        Ok(())
+    }
+
+    fn quota_stream(max_bytes: Option<u64>) -> (QuotaOutputStream, Arc<ScratchDir>) {
+        let scratch = Arc::new(
+            ScratchDir::new(&ScratchPolicy {
+                max_bytes,
+                max_inodes: None,
+            })
+            .unwrap(),
+        );
+        let inner: OutputStream = Box::new(MemoryOutputPipe::new(1024));
+
+        (
+            QuotaOutputStream::new(inner, Some(scratch.clone()), None),
+            scratch,
+        )
+    }
+
+    #[test]
+    fn test_quota_stream_limits_permit() {
+        let (mut stream, scratch) = quota_stream(Some(10));
+
+        scratch.reserve(4, 1).unwrap();
+
+        assert_eq!(stream.check_write().unwrap(), 6);
+        assert!(stream.write(Bytes::from_static(b"123456")).is_ok());
+        assert_eq!(scratch.usage().bytes, 10);
+    }
+
+    #[test]
+    fn test_quota_stream_rejects_overflow() {
+        let (mut stream, scratch) = quota_stream(Some(4));
+
+        scratch.reserve(4, 1).unwrap();
+
+        assert!(stream.check_write().is_err());
+        assert!(matches!(
+            stream.write(Bytes::from_static(b"5")),
+            Err(StreamError::LastOperationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_quota_stream_counts_write_limits() {
+        let inner: OutputStream = Box::new(MemoryOutputPipe::new(1024));
+        let limits = Arc::new(WriteLimits::new(Some(8), None));
+        let mut stream = QuotaOutputStream::new(inner, None, Some(limits.clone()));
+
+        assert_eq!(stream.check_write().unwrap(), 8);
+        assert!(stream.write(Bytes::from_static(b"12345")).is_ok());
+        assert_eq!(stream.check_write().unwrap(), 3);
+        assert!(stream.write(Bytes::from_static(b"6789")).is_err());
+
+        assert_eq!(limits.bytes_written(), 5);
+        assert!(limits.violation().is_some());
+    }
+
+    #[test]
+    fn test_scratch_quota_stops_file_writes() {
+        in_tokio(async {
+            let scratch = Arc::new(
+                ScratchDir::new(&ScratchPolicy {
+                    max_bytes: Some(8),
+                    max_inodes: None,
+                })
+                .unwrap(),
+            );
+            let inner = file_stream(scratch.host_path(), "data.txt").await;
+            let mut stream = QuotaOutputStream::new(inner, Some(scratch.clone()), None);
+
+            assert!(write_all(&mut stream, b"12345").await.is_ok());
+            assert!(write_all(&mut stream, b"6789").await.is_err());
+
+            assert_eq!(
+                std::fs::read(scratch.host_path().join("data.txt")).unwrap(),
+                b"12345"
+            );
+            assert_eq!(scratch.usage().bytes, 5);
+        });
+    }
+
+    #[test]
+    fn test_write_limits_stop_file_writes() {
+        in_tokio(async {
+            let dir = tempfile::tempdir().unwrap();
+            let limits = Arc::new(WriteLimits::new(Some(6), None));
+            let inner = file_stream(dir.path(), "data.txt").await;
+            let mut stream = QuotaOutputStream::new(inner, None, Some(limits.clone()));
+
+            assert!(write_all(&mut stream, b"1234").await.is_ok());
+            assert!(write_all(&mut stream, b"567").await.is_err());
+
+            assert_eq!(std::fs::read(dir.path().join("data.txt")).unwrap(), b"1234");
+            assert_eq!(limits.bytes_written(), 4);
+            assert!(limits.violation().is_some());
+        });
+    }
+
+    #[test]
+    fn test_audit_streams_count_bytes() {
+        let audit = Arc::new(FileAudit::new());
+
+        let inner: InputStream = Box::new(MemoryInputPipe::new(Bytes::from_static(b"hello")));
+        let mut input = AuditInputStream::new(inner, audit.clone(), PathBuf::from("/data/a.txt"));
+        assert_eq!(input.read(3).unwrap().len(), 3);
+        assert_eq!(input.read(10).unwrap().len(), 2);
+
+        let inner: OutputStream = Box::new(MemoryOutputPipe::new(1024));
+        let mut output = AuditOutputStream::new(inner, audit.clone(), PathBuf::from("/out/b.txt"));
+        output.write(Bytes::from_static(b"1234")).unwrap();
+
+        let summary = audit.summary();
+        assert_eq!(summary.bytes_read, 5);
+        assert_eq!(summary.bytes_written, 4);
+        assert_eq!(audit.entries().len(), 2);
+    }
+
+    #[test]
+    fn test_quota_stream_without_limit() {
+        let (mut stream, _scratch) = quota_stream(None);
+
+        assert_eq!(stream.check_write().unwrap(), 1024);
+        assert!(stream.write(Bytes::from_static(b"hello")).is_ok());
+    }
+}
@@ -1,6 +1,7 @@
 pub mod commands;
 pub mod compiler;
 pub mod execution_policy;
+pub mod filesystem;
 pub mod runtime;
 pub mod state;
 pub mod utilities;
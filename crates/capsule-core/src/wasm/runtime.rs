@@ -7,9 +7,12 @@ use tokio::sync::{Mutex, RwLock};
 use wasmtime::component::Component;
 use wasmtime::{Config, Engine};
 
+use crate::config::kv::KvStore;
 use crate::config::log::{Log, LogError};
 use crate::config::manifest::CapsuleToml;
 use crate::wasm::utilities::cache::precompile_component;
+use crate::wasm::utilities::cassette::{Cassette, CassetteConfig};
+use crate::wasm::utilities::http_client::build_http_client;
 use crate::wasm::utilities::task_reporter::{LogLevel, TaskReporter};
 
 pub enum WasmRuntimeError {
@@ -63,6 +66,8 @@ pub trait RuntimeCommand {
 pub struct RuntimeConfig {
     pub cache_dir: PathBuf,
     pub log_level: LogLevel,
+    pub http_capture_limit: Option<usize>,
+    pub cassette: Option<CassetteConfig>,
 }
 
 impl Default for RuntimeConfig {
@@ -70,6 +75,8 @@ impl Default for RuntimeConfig {
         Self {
             cache_dir: PathBuf::from(".capsule"),
             log_level: LogLevel::Normal,
+            http_capture_limit: None,
+            cassette: None,
         }
     }
 }
@@ -77,11 +84,15 @@ impl Default for RuntimeConfig {
 pub struct Runtime {
     pub(crate) engine: Engine,
     pub(crate) log: Log,
+    pub(crate) kv: KvStore,
 
     #[allow(dead_code)]
     pub(crate) cache_dir: PathBuf,
 
     pub log_level: LogLevel,
+    pub http_capture_limit: Option<usize>,
+    pub cassette: Option<Arc<Cassette>>,
+    pub http_client: reqwest::Client,
 
     component: RwLock<HashMap<PathBuf, Component>>,
     pub task_reporter: Arc<Mutex<TaskReporter>>,
@@ -110,17 +121,42 @@ impl Runtime {
                 .expect("failed to get cache dir"),
         )?;
 
+        let kv = KvStore::new(log.db.clone())
+            .map_err(|e| WasmRuntimeError::ConfigError(e.to_string()))?;
+
         engine_config.wasm_component_model(true);
         engine_config.async_support(true);
         engine_config.consume_fuel(true);
 
+        let cassette = match &config.cassette {
+            Some(cassette_config) => Some(Arc::new(
+                Cassette::open(cassette_config)
+                    .map_err(|e| WasmRuntimeError::ConfigError(e.to_string()))?,
+            )),
+            None => None,
+        };
+
+        let http_client = build_http_client(capsule_toml.network.as_ref())
+            .map_err(WasmRuntimeError::ConfigError)?;
+
+        let http_capture_limit = config.http_capture_limit.or_else(|| {
+            capsule_toml
+                .network
+                .as_ref()
+                .and_then(|n| n.body_capture_limit)
+        });
+
         let task_reporter = Arc::new(Mutex::new(TaskReporter::new(config.log_level.clone())));
 
         Ok(Arc::new(Self {
             engine: Engine::new(&engine_config)?,
             log,
+            kv,
             cache_dir: config.cache_dir,
             log_level: config.log_level,
+            http_capture_limit,
+            cassette,
+            http_client,
             component: RwLock::new(HashMap::new()),
             task_reporter,
             capsule_toml,
@@ -149,3 +185,30 @@ impl Runtime {
         precompile_component(&self.engine, wasm_path).map_err(WasmRuntimeError::WasmtimeError)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture_limit(configured: Option<usize>, capsule_toml: &str) -> Option<usize> {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let config = RuntimeConfig {
+            cache_dir: cache_dir.path().to_path_buf(),
+            http_capture_limit: configured,
+            ..Default::default()
+        };
+
+        Runtime::new(config, toml::from_str(capsule_toml).unwrap())
+            .unwrap()
+            .http_capture_limit
+    }
+
+    #[test]
+    fn test_capsule_toml_enables_body_capture() {
+        let network = "[network]\nbody_capture_limit = 4096";
+
+        assert_eq!(capture_limit(None, ""), None);
+        assert_eq!(capture_limit(None, network), Some(4096));
+        assert_eq!(capture_limit(Some(16), network), Some(16));
+    }
+}
@@ -2,33 +2,75 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::Result;
-use hyper::Request;
-use wasmtime::component::{ResourceTable, bindgen};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{Request, Response};
+use rusqlite::types::Value;
+use tokio::sync::Semaphore;
+use wasmtime::component::{Resource, ResourceTable, bindgen};
 use wasmtime::{ResourceLimiter, StoreLimits};
+use wasmtime_wasi::runtime::AbortOnDropJoinHandle;
 use wasmtime_wasi::{WasiCtx, WasiView};
 use wasmtime_wasi_http::bindings::http::types::ErrorCode;
 use wasmtime_wasi_http::body::HyperOutgoingBody;
 use wasmtime_wasi_http::types::{
-    HostFutureIncomingResponse, OutgoingRequestConfig, default_send_request,
+    HostFutureIncomingResponse, IncomingResponse, OutgoingRequestConfig,
 };
 use wasmtime_wasi_http::{HttpResult, WasiHttpCtx, WasiHttpView};
 
+use crate::config::kv::{KvError, KvStore};
+use crate::config::log::InstanceState;
 use crate::wasm::commands::create::CreateInstance;
 use crate::wasm::commands::run::RunInstance;
 use crate::wasm::execution_policy::ExecutionPolicy;
+use crate::wasm::filesystem::sandbox::SandboxFs;
 use crate::wasm::runtime::Runtime;
+use crate::wasm::utilities::cassette::{Cassette, Interaction};
+use crate::wasm::utilities::deterministic::derive_seed;
+use crate::wasm::utilities::guest_log::{GuestLogEntry, GuestLogLevel, GuestLogs};
+use crate::wasm::utilities::host_http::{
+    HostHttpBody, HostHttpError, HostHttpRequest, HostHttpResponse, ResponseMode, ResponseStream,
+    read_body,
+};
+use crate::wasm::utilities::host_sql::{SqlDatabases, SqlError};
 use crate::wasm::utilities::host_validator::is_host_allowed;
-use crate::wasm::utilities::task_config::{HostRequest, TaskConfig, TaskResult};
+use crate::wasm::utilities::http_client::send_wasi_request;
+use crate::wasm::utilities::http_mock::{MockResponse, find_mock, load_mock_response};
+use crate::wasm::utilities::http_trace::{BodyDirection, CapturedBody, HttpTrace, capture_body};
+use crate::wasm::utilities::stdio_capture::StdioCapture;
+use crate::wasm::utilities::task_config::{HostRequest, TaskConfig, TaskInvocation, TaskResult};
+use crate::wasm::utilities::task_handle::{
+    SubtaskResult, SubtaskTracker, TaskHandle, join_all, spawn_limited,
+};
+use crate::wasm::utilities::task_progress::{ProgressEvent, TaskMetrics};
+use crate::wasm::utilities::task_runner::task_outcome;
 
-use capsule::host::api::{Host, HttpError, HttpResponse, TaskError};
+use capsule::host::api::{Host, HostTaskHandle, HttpError, HttpResponse, TaskError, TaskRequest};
+use capsule::host::kv as host_kv;
+use capsule::host::log as host_log;
+use capsule::host::progress as host_progress;
+use capsule::host::scheduler as host_scheduler;
+use capsule::host::sql as host_sql;
+use capsule::http::client as http_client;
 
 bindgen!({
     path: "../capsule-wit",
-    world: "capsule-agent",
-    async: true
+    world: "capsule-agent-v2",
+    async: true,
+    trappable_imports: [
+        "spawn-task",
+        "[method]task-handle.poll",
+        "[method]task-handle.wait",
+        "[method]task-handle.cancel",
+    ],
+    with: {
+        "wasi": wasmtime_wasi::bindings,
+        "capsule:host/api/task-handle": crate::wasm::utilities::task_handle::TaskHandle,
+    },
 });
 
 pub use capsule::host::api as host_api;
+pub use capsule::host::types as host_types;
 
 pub struct State {
     pub ctx: WasiCtx,
@@ -36,10 +78,320 @@ pub struct State {
     pub table: ResourceTable,
     pub limits: StoreLimits,
     pub runtime: Option<Arc<Runtime>>,
+    pub task_id: String,
     pub policy: ExecutionPolicy,
     pub wasm_path: PathBuf,
     pub peak_memory_bytes: u64,
-    pub host_requests: Vec<HostRequest>,
+    pub host_requests: HttpTrace,
+    pub logs: GuestLogs,
+    pub metrics: TaskMetrics,
+    pub sql: SqlDatabases,
+    pub seed: Option<u64>,
+    pub subtasks_started: u64,
+    pub fs: SandboxFs,
+    pub stdio: Option<StdioCapture>,
+    pub subtask_slots: Arc<Semaphore>,
+}
+
+impl State {
+    fn subtask_runtime(&self) -> Result<Arc<Runtime>, TaskError> {
+        self.runtime.as_ref().map(Arc::clone).ok_or_else(|| {
+            TaskError::InternalError(
+                "No runtime available for recursive task execution".to_string(),
+            )
+        })
+    }
+
+    fn subtask_parent(&mut self) -> ExecutionPolicy {
+        let mut parent = self.policy.clone();
+        if let Some(seed) = self.seed {
+            self.subtasks_started += 1;
+            parent.seed = Some(derive_seed(seed, self.subtasks_started));
+        }
+        parent
+    }
+
+    fn spawn_subtask(
+        &mut self,
+        runtime: Arc<Runtime>,
+        invocation: Result<TaskInvocation, TaskError>,
+        config: String,
+        tracker: SubtaskTracker,
+    ) -> AbortOnDropJoinHandle<SubtaskResult> {
+        let slots = Arc::clone(&self.subtask_slots);
+        let parent = self.subtask_parent();
+        let wasm_path = self.wasm_path.clone();
+
+        spawn_limited(slots, async move {
+            run_subtask(runtime, parent, wasm_path, invocation?, config, tracker).await
+        })
+    }
+
+    fn kv_store(&self, namespace: &str) -> Result<(KvStore, Option<u64>), KvError> {
+        let grant = self
+            .policy
+            .kv
+            .as_ref()
+            .filter(|kv| kv.allows(namespace))
+            .ok_or_else(|| KvError::AccessDenied(namespace.to_string()))?;
+
+        let runtime = self.runtime.as_ref().ok_or_else(|| {
+            KvError::DatabaseError("No runtime available for key-value access".to_string())
+        })?;
+
+        Ok((runtime.kv.clone(), grant.max_bytes))
+    }
+
+    async fn with_kv<T, F>(&mut self, namespace: String, work: F) -> Result<T, host_kv::KvError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&KvStore, &str, Option<u64>) -> Result<T, KvError> + Send + 'static,
+    {
+        let (kv, max_bytes) = self.kv_store(&namespace).map_err(kv_error)?;
+
+        wasmtime_wasi::runtime::spawn_blocking(move || work(&kv, &namespace, max_bytes))
+            .await
+            .map_err(kv_error)
+    }
+
+    fn http_capture_limit(&self) -> Option<usize> {
+        self.runtime.as_ref().and_then(|r| r.http_capture_limit)
+    }
+
+    fn http_client(&self) -> reqwest::Client {
+        self.runtime
+            .as_ref()
+            .map(|r| r.http_client.clone())
+            .unwrap_or_default()
+    }
+
+    fn cassette(&self) -> Option<Arc<Cassette>> {
+        self.runtime.as_ref().and_then(|r| r.cassette.clone())
+    }
+
+    fn canned_response(
+        &self,
+        method: &str,
+        url: &str,
+    ) -> Option<Result<(MockResponse, bool), String>> {
+        let runtime = self.runtime.as_ref()?;
+
+        if let Some(mock) = find_mock(&runtime.capsule_toml.mocks, method, url) {
+            return Some(load_mock_response(mock).map(|response| (response, true)));
+        }
+
+        let cassette = runtime.cassette.as_ref().filter(|c| c.is_replaying())?;
+
+        Some(
+            cassette
+                .replay(method, url)
+                .map(|interaction| {
+                    let response = MockResponse {
+                        status: interaction.status,
+                        body: interaction.body_bytes(),
+                        headers: interaction.headers,
+                    };
+                    (response, false)
+                })
+                .map_err(|e| e.to_string()),
+        )
+    }
+
+    async fn send_host_request(
+        &mut self,
+        request: HostHttpRequest,
+        mode: ResponseMode,
+    ) -> Result<HostHttpResponse, HostHttpError> {
+        let host = request
+            .url
+            .parse::<reqwest::Url>()
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_string()))
+            .unwrap_or("unknown".to_string());
+
+        if !is_host_allowed(&host, &self.policy.allowed_hosts) {
+            return Err(HostHttpError::HostNotAllowed(host));
+        }
+
+        let method = request.method.to_uppercase();
+        let url = request.url;
+
+        let client = self.http_client();
+
+        let mut request_builder = match method.as_str() {
+            "GET" => client.get(&url),
+            "POST" => client.post(&url),
+            "PUT" => client.put(&url),
+            "DELETE" => client.delete(&url),
+            "PATCH" => client.patch(&url),
+            "HEAD" => client.head(&url),
+            _ => {
+                return Err(HostHttpError::InvalidUrl(format!(
+                    "Unsupported method: {}",
+                    request.method
+                )));
+            }
+        };
+
+        let trace = self.host_requests.clone();
+        let capture_limit = self.http_capture_limit();
+        let max_response_bytes = request.max_response_bytes;
+
+        let index = trace.push(HostRequest {
+            method: method.clone(),
+            url: url.clone(),
+            headers: Some(request.headers.clone()),
+            body: request
+                .body
+                .as_ref()
+                .map(|b| String::from_utf8_lossy(b).into_owned()),
+            ..Default::default()
+        });
+
+        if let Some(canned) = self.canned_response(&method, &url) {
+            let (response, mocked) = canned.map_err(|e| {
+                trace.update(index, |entry| entry.error = Some(e.clone()));
+                HostHttpError::NetworkError(e)
+            })?;
+
+            trace.update(index, |entry| {
+                trace_canned_response(entry, &response, capture_limit, mocked)
+            });
+
+            if let Some(limit) = max_response_bytes
+                && response.body.len() as u64 > limit
+            {
+                return Err(HostHttpError::ResponseTooLarge(limit));
+            }
+
+            return Ok(HostHttpResponse {
+                status: response.status,
+                headers: response.headers,
+                body: HostHttpBody::Buffered(response.body),
+            });
+        }
+
+        for (key, value) in &request.headers {
+            request_builder = request_builder.header(key, value);
+        }
+
+        if let Some(body) = request.body {
+            request_builder = request_builder.body(body);
+        }
+
+        if let Some(timeout) = request.timeout {
+            request_builder = request_builder.timeout(timeout);
+        }
+
+        let start_time = std::time::Instant::now();
+
+        let mut response = match request_builder.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                trace.update(index, |entry| {
+                    entry.latency_ms = Some(start_time.elapsed().as_millis() as u64);
+                    entry.error = Some(e.to_string());
+                });
+                return Err(e.into());
+            }
+        };
+
+        let status = response.status().as_u16();
+        let response_headers: Vec<(String, String)> = response
+            .headers()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+            .collect();
+
+        trace.update(index, |entry| {
+            entry.status = Some(status);
+            entry.latency_ms = Some(start_time.elapsed().as_millis() as u64);
+        });
+
+        if let Some(limit) = max_response_bytes
+            && response.content_length().is_some_and(|len| len > limit)
+        {
+            let err = HostHttpError::ResponseTooLarge(limit);
+            trace.update(index, |entry| entry.error = Some(err.to_string()));
+            return Err(err);
+        }
+
+        let recording = self.cassette().filter(|c| c.is_recording());
+
+        if mode == ResponseMode::Streaming {
+            let keep_body = capture_limit.is_some() || recording.is_some();
+            let headers = response_headers.clone();
+
+            let stream =
+                ResponseStream::new(response, max_response_bytes, keep_body, move |summary| {
+                    trace.update(index, |entry| {
+                        entry.response_size = Some(summary.size);
+                        entry.response_body = summary
+                            .body
+                            .as_deref()
+                            .and_then(|body| capture_body(body, capture_limit));
+                        if summary.error.is_some() {
+                            entry.error = summary.error.clone();
+                        }
+                    });
+
+                    if let Some(cassette) = recording
+                        && summary.complete
+                        && let Some(body) = summary.body
+                    {
+                        cassette.record(Interaction::new(&method, &url, status, headers, &body));
+                    }
+                });
+
+            return Ok(HostHttpResponse {
+                status,
+                headers: response_headers,
+                body: HostHttpBody::Streaming(stream),
+            });
+        }
+
+        let body = match read_body(&mut response, max_response_bytes).await {
+            Ok(body) => body,
+            Err(e) => {
+                trace.update(index, |entry| entry.error = Some(e.to_string()));
+                return Err(e);
+            }
+        };
+
+        trace.update(index, |entry| {
+            entry.response_size = Some(body.len() as u64);
+            entry.response_body = capture_body(&body, capture_limit);
+        });
+
+        if let Some(cassette) = recording {
+            cassette.record(Interaction::new(
+                &method,
+                &url,
+                status,
+                response_headers.clone(),
+                &body,
+            ));
+        }
+
+        Ok(HostHttpResponse {
+            status,
+            headers: response_headers,
+            body: HostHttpBody::Buffered(body),
+        })
+    }
+}
+
+fn trace_canned_response(
+    entry: &mut HostRequest,
+    response: &MockResponse,
+    capture_limit: Option<usize>,
+    mocked: bool,
+) {
+    entry.status = Some(response.status);
+    entry.latency_ms = Some(0);
+    entry.response_size = Some(response.body.len() as u64);
+    entry.response_body = capture_body(&response.body, capture_limit);
+    entry.mocked = mocked;
 }
 
 impl WasiView for State {
@@ -78,117 +430,309 @@ impl WasiHttpView for State {
             .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
             .collect();
 
-        self.host_requests.push(HostRequest {
-            method: request.method().to_string().to_uppercase(),
-            url: request.uri().to_string(),
+        let method = request.method().to_string().to_uppercase();
+        let url = request.uri().to_string();
+
+        let trace = self.host_requests.clone();
+        let capture_limit = self.http_capture_limit();
+        let cassette = self.cassette();
+
+        let index = trace.push(HostRequest {
+            method: method.clone(),
+            url: url.clone(),
             headers: Some(headers),
-            body: Some("Hidden".to_string()), // Can't have it from send_request
+            ..Default::default()
         });
 
-        Ok(default_send_request(request, config))
-    }
-}
+        if let Some(canned) = self.canned_response(&method, &url) {
+            let (response, mocked) = match canned {
+                Ok(canned) => canned,
+                Err(e) => {
+                    trace.update(index, |entry| entry.error = Some(e.clone()));
+                    return Err(ErrorCode::InternalError(Some(e)).into());
+                }
+            };
 
-impl Host for State {
-    async fn schedule_task(
-        &mut self,
-        name: String,
-        args: String,
-        config: String,
-    ) -> Result<String, TaskError> {
-        let runtime = match &self.runtime {
-            Some(r) => Arc::clone(r),
-            None => {
-                return Err(TaskError::InternalError(
-                    "No runtime available for recursive task execution".to_string(),
-                ));
+            trace.update(index, |entry| {
+                trace_canned_response(entry, &response, capture_limit, mocked)
+            });
+
+            let mut builder = Response::builder().status(response.status);
+            for (key, value) in &response.headers {
+                builder = builder.header(key, value);
             }
-        };
 
-        let task_config: TaskConfig = serde_json::from_str(&config).unwrap_or_default();
-        let mut policy = task_config.to_execution_policy(&runtime.capsule_toml);
+            let resp = builder
+                .body(
+                    Full::new(Bytes::from(response.body))
+                        .map_err(|never| match never {})
+                        .boxed(),
+                )
+                .map_err(|e| ErrorCode::InternalError(Some(e.to_string())))?;
 
-        policy.mounts.extend(self.policy.mounts.iter().cloned());
-        let max_retries = policy.max_retries;
+            return Ok(HostFutureIncomingResponse::ready(Ok(Ok(
+                IncomingResponse {
+                    resp,
+                    worker: None,
+                    between_bytes_timeout: config.between_bytes_timeout,
+                },
+            ))));
+        }
 
-        let mut last_error: Option<String> = None;
+        let request = request.map(|body| {
+            CapturedBody::new(
+                body,
+                BodyDirection::Request,
+                trace.clone(),
+                index,
+                capture_limit,
+            )
+            .boxed()
+        });
 
-        for attempt in 0..=max_retries {
-            let create_cmd = CreateInstance::new(policy.clone(), vec![])
-                .task_name(&name)
-                .wasm_path(self.wasm_path.clone());
+        let client = self.http_client();
+        let start_time = std::time::Instant::now();
 
-            let (store, instance, task_id) = match runtime.execute(create_cmd).await {
-                Ok(result) => result,
-                Err(e) => {
-                    runtime
-                        .task_reporter
-                        .lock()
-                        .await
-                        .task_failed(&name, &e.to_string());
-                    last_error = Some(format!("Failed to create instance: {}", e));
-                    continue;
+        let handle = wasmtime_wasi::runtime::spawn(async move {
+            let result = send_wasi_request(client, request, config).await;
+            let latency_ms = start_time.elapsed().as_millis() as u64;
+
+            match result {
+                Ok(mut response) => {
+                    let status = response.resp.status().as_u16();
+
+                    trace.update(index, |entry| {
+                        entry.status = Some(status);
+                        entry.latency_ms = Some(latency_ms);
+                    });
+
+                    let response_headers: Vec<(String, String)> = response
+                        .resp
+                        .headers()
+                        .iter()
+                        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+                        .collect();
+
+                    response.resp = response.resp.map(|body| {
+                        let captured = CapturedBody::new(
+                            body,
+                            BodyDirection::Response,
+                            trace,
+                            index,
+                            capture_limit,
+                        );
+
+                        match cassette.filter(|c| c.is_recording()) {
+                            Some(cassette) => captured
+                                .record_into(cassette, method, url, status, response_headers)
+                                .boxed(),
+                            None => captured.boxed(),
+                        }
+                    });
+
+                    Ok(Ok(response))
                 }
-            };
+                Err(code) => {
+                    trace.update(index, |entry| {
+                        entry.latency_ms = Some(latency_ms);
+                        entry.error = Some(format!("{:?}", code));
+                    });
 
-            let args_json = format!(
-                r#"{{"task_name": "{}", "args": {}, "kwargs": {{}}}}"#,
-                name, args
-            );
+                    Ok(Err(code))
+                }
+            }
+        });
 
-            runtime
-                .task_reporter
-                .lock()
-                .await
-                .task_running(&name, &task_id);
+        Ok(HostFutureIncomingResponse::pending(handle))
+    }
+}
+
+async fn mark_interrupted(runtime: Option<Arc<Runtime>>, task_id: Option<String>) {
+    if let (Some(runtime), Some(task_id)) = (runtime, task_id) {
+        let _ = runtime
+            .log
+            .update_state(&task_id, InstanceState::Interrupted)
+            .await;
+    }
+}
+
+fn subtask_invocation(name: &str, args: &str) -> Result<TaskInvocation, TaskError> {
+    let args = serde_json::from_str(args)
+        .map_err(|e| TaskError::InvalidArgs(format!("Invalid task arguments: {}", e)))?;
+
+    Ok(TaskInvocation::new(name, args))
+}
+
+async fn run_subtask(
+    runtime: Arc<Runtime>,
+    parent: ExecutionPolicy,
+    wasm_path: PathBuf,
+    invocation: TaskInvocation,
+    config: String,
+    tracker: SubtaskTracker,
+) -> SubtaskResult {
+    let name = invocation.task_name.clone();
+    let task_config: TaskConfig = serde_json::from_str(&config).unwrap_or_default();
+    let mut policy = task_config.to_execution_policy(&runtime.capsule_toml);
 
-            let start_time = std::time::Instant::now();
+    policy.mounts.extend(parent.mounts.iter().cloned());
+    policy.host_env = parent.host_env.clone();
+    policy.inherit_stdio = parent.inherit_stdio;
+    policy.output_events = parent.output_events.clone();
+    policy.progress_events = parent.progress_events.clone();
+    if policy.kv.is_none() {
+        policy.kv = parent.kv.clone();
+    }
+    if policy.sql.is_none() {
+        policy.sql = parent.sql.clone();
+    }
+    if parent.deterministic {
+        policy.deterministic = true;
+        policy.seed = parent.seed;
+    }
+    let max_retries = policy.max_retries;
+
+    let mut last_error: Option<String> = None;
+
+    for attempt in 0..=max_retries {
+        let create_cmd = CreateInstance::new(policy.clone(), vec![])
+            .task_name(&name)
+            .wasm_path(wasm_path.clone());
+
+        let (store, instance, task_id) = match runtime.execute(create_cmd).await {
+            Ok(result) => result,
+            Err(e) => {
+                runtime
+                    .task_reporter
+                    .lock()
+                    .await
+                    .task_failed(&name, &e.to_string());
+                last_error = Some(format!("Failed to create instance: {}", e));
+                continue;
+            }
+        };
+
+        tracker.set(&task_id);
+
+        runtime
+            .task_reporter
+            .lock()
+            .await
+            .task_running(&name, &task_id);
 
-            let run_cmd = RunInstance::new(task_id, policy.clone(), store, instance, args_json);
+        let start_time = std::time::Instant::now();
 
-            match runtime.execute(run_cmd).await {
-                Ok(result) => {
-                    if result.is_empty() {
-                        last_error = Some("Task failed".to_string());
-                        if attempt < max_retries {
-                            continue;
+        let run_cmd =
+            RunInstance::new(task_id, policy.clone(), store, instance, invocation.clone());
+
+        match runtime.execute(run_cmd).await {
+            Ok(result) => {
+                if result.is_empty() {
+                    last_error = Some("Task failed".to_string());
+                    if attempt < max_retries {
+                        continue;
+                    }
+                } else {
+                    match serde_json::from_str::<TaskResult>(&result) {
+                        Ok(task_result) if task_result.success => {
+                            let elapsed = start_time.elapsed();
+                            runtime
+                                .task_reporter
+                                .lock()
+                                .await
+                                .task_completed_with_time(&name, elapsed);
+
+                            return Ok(result);
                         }
-                    } else {
-                        match serde_json::from_str::<TaskResult>(&result) {
-                            Ok(task_result) if task_result.success => {
-                                let elapsed = start_time.elapsed();
-                                runtime
-                                    .task_reporter
-                                    .lock()
-                                    .await
-                                    .task_completed_with_time(&name, elapsed);
-
-                                return Ok(result);
+                        Ok(_) => {
+                            if attempt < max_retries {
+                                continue;
                             }
-                            Ok(_) => {
-                                if attempt < max_retries {
-                                    continue;
-                                }
 
-                                return Ok(result);
-                            }
-                            Err(_) => {
-                                if attempt < max_retries {
-                                    continue;
-                                }
+                            return Ok(result);
+                        }
+                        Err(_) => {
+                            if attempt < max_retries {
+                                continue;
                             }
                         }
                     }
                 }
-                Err(_) => {
-                    if attempt < max_retries {
-                        continue;
-                    }
+            }
+            Err(_) => {
+                if attempt < max_retries {
+                    continue;
                 }
             }
         }
+    }
+
+    Ok(last_error.unwrap_or_else(|| "Unknown error".to_string()))
+}
+
+impl Host for State {
+    async fn schedule_task(
+        &mut self,
+        name: String,
+        args: String,
+        config: String,
+    ) -> Result<String, TaskError> {
+        let runtime = self.subtask_runtime()?;
+        let invocation = subtask_invocation(&name, &args)?;
 
-        Ok(last_error.unwrap_or_else(|| "Unknown error".to_string()))
+        run_subtask(
+            runtime,
+            self.subtask_parent(),
+            self.wasm_path.clone(),
+            invocation,
+            config,
+            SubtaskTracker::default(),
+        )
+        .await
+    }
+
+    async fn schedule_tasks(
+        &mut self,
+        requests: Vec<TaskRequest>,
+    ) -> Vec<Result<String, TaskError>> {
+        let runtime = match self.subtask_runtime() {
+            Ok(runtime) => runtime,
+            Err(e) => return requests.iter().map(|_| Err(e.clone())).collect(),
+        };
+
+        let handles: Vec<_> = requests
+            .into_iter()
+            .map(|request| {
+                self.spawn_subtask(
+                    Arc::clone(&runtime),
+                    subtask_invocation(&request.task_name, &request.args_json),
+                    request.config_json,
+                    SubtaskTracker::default(),
+                )
+            })
+            .collect();
+
+        join_all(handles).await
+    }
+
+    async fn spawn_task(
+        &mut self,
+        name: String,
+        args: String,
+        config: String,
+    ) -> wasmtime::Result<Resource<TaskHandle>> {
+        let tracker = SubtaskTracker::default();
+        let job = match self.subtask_runtime() {
+            Ok(runtime) => self.spawn_subtask(
+                runtime,
+                subtask_invocation(&name, &args),
+                config,
+                tracker.clone(),
+            ),
+            Err(e) => wasmtime_wasi::runtime::spawn(async move { Err(e) }),
+        };
+
+        Ok(self.table.push(TaskHandle::new(job, tracker))?)
     }
 
     async fn http_request(
@@ -198,72 +742,332 @@ impl Host for State {
         headers: Vec<(String, String)>,
         body: Option<String>,
     ) -> Result<HttpResponse, HttpError> {
-        let host = url
-            .parse::<reqwest::Url>()
-            .ok()
-            .and_then(|u| u.host_str().map(|h| h.to_string()))
-            .unwrap_or("unknown".to_string());
+        let request = HostHttpRequest {
+            method,
+            url,
+            headers,
+            body: body.map(String::into_bytes),
+            ..Default::default()
+        };
 
-        let allowed = is_host_allowed(&host, &self.policy.allowed_hosts);
+        let response = self
+            .send_host_request(request, ResponseMode::Buffered)
+            .await
+            .map_err(|e| match e {
+                HostHttpError::HostNotAllowed(_) => {
+                    HttpError::InvalidUrl("Host not allowed".to_string())
+                }
+                HostHttpError::InvalidUrl(msg) => HttpError::InvalidUrl(msg),
+                HostHttpError::Timeout(msg) => HttpError::Timeout(msg),
+                HostHttpError::NetworkError(msg) => HttpError::NetworkError(msg),
+                e @ HostHttpError::ResponseTooLarge(_) => HttpError::NetworkError(e.to_string()),
+            })?;
 
-        if !allowed {
-            return Err(HttpError::InvalidUrl("Host not allowed".to_string()));
-        }
+        let body = match response.body {
+            HostHttpBody::Buffered(body) => String::from_utf8_lossy(&body).into_owned(),
+            HostHttpBody::Streaming(_) => unreachable!("buffered request returned a stream"),
+        };
 
-        let client = reqwest::Client::new();
+        Ok(HttpResponse {
+            status: response.status,
+            headers: response.headers,
+            body,
+        })
+    }
+}
 
-        let mut request_builder = match method.to_uppercase().as_str() {
-            "GET" => client.get(&url),
-            "POST" => client.post(&url),
-            "PUT" => client.put(&url),
-            "DELETE" => client.delete(&url),
-            "PATCH" => client.patch(&url),
-            "HEAD" => client.head(&url),
-            _ => {
-                return Err(HttpError::InvalidUrl(format!(
-                    "Unsupported method: {}",
-                    method
-                )));
+fn client_request(
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: http_client::RequestBody,
+    options: http_client::RequestOptions,
+) -> HostHttpRequest {
+    let body = match body {
+        http_client::RequestBody::Empty => None,
+        http_client::RequestBody::Text(text) => Some(text.into_bytes()),
+        http_client::RequestBody::Binary(bytes) => Some(bytes),
+    };
+
+    HostHttpRequest {
+        method,
+        url,
+        headers,
+        body,
+        timeout: options.timeout_ms.map(std::time::Duration::from_millis),
+        max_response_bytes: options.max_response_bytes,
+    }
+}
+
+fn client_error(err: HostHttpError) -> http_client::HttpError {
+    match err {
+        HostHttpError::HostNotAllowed(host) => http_client::HttpError::HostNotAllowed(host),
+        HostHttpError::InvalidUrl(msg) => http_client::HttpError::InvalidUrl(msg),
+        HostHttpError::NetworkError(msg) => http_client::HttpError::NetworkError(msg),
+        HostHttpError::Timeout(msg) => http_client::HttpError::Timeout(msg),
+        HostHttpError::ResponseTooLarge(limit) => http_client::HttpError::ResponseTooLarge(limit),
+    }
+}
+
+impl host_types::Host for State {}
+
+impl host_scheduler::Host for State {
+    async fn schedule_task(
+        &mut self,
+        invocation: host_types::TaskInvocation,
+        config: String,
+    ) -> host_types::TaskOutcome {
+        let invocation = match TaskInvocation::try_from(invocation) {
+            Ok(invocation) => invocation,
+            Err(failure) => {
+                return host_types::TaskOutcome {
+                    value: Err(failure),
+                    execution_json: None,
+                };
             }
         };
 
-        for (key, value) in headers.clone() {
-            request_builder = request_builder.header(key, value);
+        let result = match self.subtask_runtime() {
+            Ok(runtime) => {
+                run_subtask(
+                    runtime,
+                    self.subtask_parent(),
+                    self.wasm_path.clone(),
+                    invocation,
+                    config,
+                    SubtaskTracker::default(),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+
+        task_outcome(result)
+    }
+}
+
+impl HostTaskHandle for State {
+    async fn poll(
+        &mut self,
+        handle: Resource<TaskHandle>,
+    ) -> wasmtime::Result<Option<SubtaskResult>> {
+        Ok(self.table.get_mut(&handle)?.poll().await)
+    }
+
+    async fn wait(&mut self, handle: Resource<TaskHandle>) -> wasmtime::Result<SubtaskResult> {
+        Ok(self.table.get_mut(&handle)?.wait().await)
+    }
+
+    async fn cancel(&mut self, handle: Resource<TaskHandle>) -> wasmtime::Result<bool> {
+        let handle = self.table.get_mut(&handle)?;
+        let task_id = handle.running_task_id();
+
+        if !handle.cancel() {
+            return Ok(false);
         }
 
-        if let Some(body_content) = body.clone() {
-            request_builder = request_builder.body(body_content);
+        mark_interrupted(self.runtime.clone(), task_id).await;
+        Ok(true)
+    }
+
+    async fn drop(&mut self, handle: Resource<TaskHandle>) -> wasmtime::Result<()> {
+        let mut handle = self.table.delete(handle)?;
+        let task_id = handle.running_task_id();
+
+        if handle.cancel() {
+            mark_interrupted(self.runtime.clone(), task_id).await;
         }
 
-        self.host_requests.push(HostRequest {
-            method: method.to_uppercase(),
-            url: url.clone(),
-            headers: Some(headers.clone()),
+        Ok(())
+    }
+}
+
+impl http_client::Host for State {
+    async fn send(
+        &mut self,
+        method: String,
+        url: String,
+        headers: Vec<(String, String)>,
+        body: http_client::RequestBody,
+        options: http_client::RequestOptions,
+    ) -> Result<http_client::Response, http_client::HttpError> {
+        let request = client_request(method, url, headers, body, options);
+
+        let response = self
+            .send_host_request(request, ResponseMode::Buffered)
+            .await
+            .map_err(client_error)?;
+
+        let body = match response.body {
+            HostHttpBody::Buffered(body) => body,
+            HostHttpBody::Streaming(_) => unreachable!("buffered request returned a stream"),
+        };
+
+        Ok(http_client::Response {
+            status: response.status,
+            headers: response.headers,
             body,
-        });
+        })
+    }
 
-        let response = request_builder
-            .send()
+    async fn send_streaming(
+        &mut self,
+        method: String,
+        url: String,
+        headers: Vec<(String, String)>,
+        body: http_client::RequestBody,
+        options: http_client::RequestOptions,
+    ) -> Result<http_client::StreamingResponse, http_client::HttpError> {
+        let request = client_request(method, url, headers, body, options);
+
+        let response = self
+            .send_host_request(request, ResponseMode::Streaming)
             .await
-            .map_err(|e| HttpError::NetworkError(e.to_string()))?;
+            .map_err(client_error)?;
 
-        let status = response.status().as_u16();
-        let response_headers: Vec<(String, String)> = response
-            .headers()
-            .iter()
-            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
-            .collect();
+        let body: Resource<wasmtime_wasi::InputStream> = self
+            .table
+            .push(response.body.into_input_stream())
+            .map_err(|e| http_client::HttpError::NetworkError(e.to_string()))?;
 
-        let body_text = response
-            .text()
+        Ok(http_client::StreamingResponse {
+            status: response.status,
+            headers: response.headers,
+            body,
+        })
+    }
+}
+
+fn kv_error(err: KvError) -> host_kv::KvError {
+    match err {
+        KvError::AccessDenied(namespace) => host_kv::KvError::AccessDenied(namespace),
+        KvError::QuotaExceeded(limit) => host_kv::KvError::QuotaExceeded(limit),
+        KvError::InvalidKey(msg) => host_kv::KvError::InvalidKey(msg),
+        KvError::DatabaseError(msg) => host_kv::KvError::InternalError(msg),
+    }
+}
+
+impl host_kv::Host for State {
+    async fn get(
+        &mut self,
+        namespace: String,
+        key: String,
+    ) -> Result<Option<Vec<u8>>, host_kv::KvError> {
+        self.with_kv(namespace, move |kv, namespace, _| kv.get(namespace, &key))
             .await
-            .map_err(|e| HttpError::NetworkError(e.to_string()))?;
+    }
 
-        Ok(HttpResponse {
-            status,
-            headers: response_headers,
-            body: body_text,
+    async fn set(
+        &mut self,
+        namespace: String,
+        key: String,
+        value: Vec<u8>,
+        ttl_ms: Option<u64>,
+    ) -> Result<(), host_kv::KvError> {
+        self.with_kv(namespace, move |kv, namespace, max_bytes| {
+            kv.set(namespace, &key, &value, ttl_ms, max_bytes)
+        })
+        .await
+    }
+
+    async fn delete(&mut self, namespace: String, key: String) -> Result<bool, host_kv::KvError> {
+        self.with_kv(namespace, move |kv, namespace, _| {
+            kv.delete(namespace, &key)
         })
+        .await
+    }
+
+    async fn list_keys(
+        &mut self,
+        namespace: String,
+        prefix: String,
+    ) -> Result<Vec<String>, host_kv::KvError> {
+        self.with_kv(namespace, move |kv, namespace, _| {
+            kv.list(namespace, &prefix)
+        })
+        .await
+    }
+
+    async fn compare_and_swap(
+        &mut self,
+        namespace: String,
+        key: String,
+        expected: Option<Vec<u8>>,
+        value: Vec<u8>,
+        ttl_ms: Option<u64>,
+    ) -> Result<bool, host_kv::KvError> {
+        self.with_kv(namespace, move |kv, namespace, max_bytes| {
+            kv.compare_and_swap(
+                namespace,
+                &key,
+                expected.as_deref(),
+                &value,
+                ttl_ms,
+                max_bytes,
+            )
+        })
+        .await
+    }
+}
+
+impl From<host_log::Level> for GuestLogLevel {
+    fn from(level: host_log::Level) -> Self {
+        match level {
+            host_log::Level::Trace => GuestLogLevel::Trace,
+            host_log::Level::Debug => GuestLogLevel::Debug,
+            host_log::Level::Info => GuestLogLevel::Info,
+            host_log::Level::Warn => GuestLogLevel::Warn,
+            host_log::Level::Error => GuestLogLevel::Error,
+        }
+    }
+}
+
+impl host_log::Host for State {
+    async fn write(
+        &mut self,
+        level: host_log::Level,
+        message: String,
+        fields: Vec<(String, String)>,
+    ) {
+        let entry = GuestLogEntry::new(level.into(), message, fields);
+
+        if let Some(runtime) = self.runtime.clone() {
+            runtime
+                .task_reporter
+                .lock()
+                .await
+                .guest_log(&self.policy.name, &entry);
+        }
+
+        self.logs.push(entry);
+    }
+}
+
+impl host_progress::Host for State {
+    async fn report(&mut self, current: u64, total: Option<u64>, message: Option<String>) {
+        let event = ProgressEvent {
+            task_id: self.task_id.clone(),
+            task_name: self.policy.name.clone(),
+            current,
+            total,
+            message,
+        };
+
+        if let Some(runtime) = self.runtime.clone() {
+            runtime.task_reporter.lock().await.task_progress(&event);
+        }
+
+        if let Some(events) = &self.policy.progress_events {
+            let _ = events.send(event);
+        }
+    }
+
+    async fn set_metric(&mut self, name: String, value: f64) {
+        self.metrics.set(&name, value);
+    }
+
+    async fn add_metric(&mut self, name: String, delta: f64) {
+        self.metrics.add(&name, delta);
     }
 }
 
@@ -290,3 +1094,71 @@ impl ResourceLimiter for State {
         self.limits.table_growing(current, desired, maximum)
     }
 }
+
+fn sql_param(value: host_sql::SqlValue) -> Value {
+    match value {
+        host_sql::SqlValue::Null => Value::Null,
+        host_sql::SqlValue::Integer(i) => Value::Integer(i),
+        host_sql::SqlValue::Real(f) => Value::Real(f),
+        host_sql::SqlValue::Text(s) => Value::Text(s),
+        host_sql::SqlValue::Blob(b) => Value::Blob(b),
+    }
+}
+
+fn sql_value(value: Value) -> host_sql::SqlValue {
+    match value {
+        Value::Null => host_sql::SqlValue::Null,
+        Value::Integer(i) => host_sql::SqlValue::Integer(i),
+        Value::Real(f) => host_sql::SqlValue::Real(f),
+        Value::Text(s) => host_sql::SqlValue::Text(s),
+        Value::Blob(b) => host_sql::SqlValue::Blob(b),
+    }
+}
+
+fn sql_error(err: SqlError) -> host_sql::SqlError {
+    match err {
+        SqlError::AccessDenied(database) => host_sql::SqlError::AccessDenied(database),
+        SqlError::ReadOnly(database) => host_sql::SqlError::ReadOnly(database),
+        SqlError::Timeout(ms) => host_sql::SqlError::Timeout(ms),
+        SqlError::QueryFailed(msg) => host_sql::SqlError::QueryFailed(msg),
+    }
+}
+
+impl host_sql::Host for State {
+    async fn query(
+        &mut self,
+        database: String,
+        statement: String,
+        params: Vec<host_sql::SqlValue>,
+    ) -> Result<host_sql::QueryResult, host_sql::SqlError> {
+        let params = params.into_iter().map(sql_param).collect();
+        let result = self
+            .sql
+            .query(&database, &statement, params)
+            .await
+            .map_err(sql_error)?;
+
+        Ok(host_sql::QueryResult {
+            columns: result.columns,
+            rows: result
+                .rows
+                .into_iter()
+                .map(|row| row.into_iter().map(sql_value).collect())
+                .collect(),
+            truncated: result.truncated,
+        })
+    }
+
+    async fn execute(
+        &mut self,
+        database: String,
+        statement: String,
+        params: Vec<host_sql::SqlValue>,
+    ) -> Result<u64, host_sql::SqlError> {
+        let params = params.into_iter().map(sql_param).collect();
+        self.sql
+            .execute(&database, &statement, params)
+            .await
+            .map_err(sql_error)
+    }
+}
@@ -0,0 +1,348 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    Record,
+    Replay,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CassetteConfig {
+    pub mode: CassetteMode,
+    pub path: PathBuf,
+}
+
+impl CassetteConfig {
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            mode: CassetteMode::Record,
+            path: path.into(),
+        }
+    }
+
+    pub fn replay(path: impl Into<PathBuf>) -> Self {
+        Self {
+            mode: CassetteMode::Replay,
+            path: path.into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CassetteError {
+    FsError(String),
+    ParseError(String),
+    Unmatched(String),
+}
+
+impl fmt::Display for CassetteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CassetteError::FsError(msg) => write!(f, "Cassette error > {}", msg),
+            CassetteError::ParseError(msg) => write!(f, "Cassette error > {}", msg),
+            CassetteError::Unmatched(msg) => {
+                write!(f, "Cassette error > No recorded response for {}", msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CassetteError {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub url: String,
+    pub status: u16,
+
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_base64: Option<String>,
+}
+
+impl Interaction {
+    pub fn new(
+        method: &str,
+        url: &str,
+        status: u16,
+        headers: Vec<(String, String)>,
+        body: &[u8],
+    ) -> Self {
+        let (body, body_base64) = match std::str::from_utf8(body) {
+            Ok(text) => (Some(text.to_string()), None),
+            Err(_) => (None, Some(STANDARD.encode(body))),
+        };
+
+        Self {
+            method: method.to_uppercase(),
+            url: url.to_string(),
+            status,
+            headers,
+            body,
+            body_base64,
+        }
+    }
+
+    pub fn body_bytes(&self) -> Vec<u8> {
+        if let Some(encoded) = &self.body_base64 {
+            return STANDARD.decode(encoded).unwrap_or_default();
+        }
+
+        self.body.clone().unwrap_or_default().into_bytes()
+    }
+
+    fn matches(&self, method: &str, url: &str) -> bool {
+        self.method.eq_ignore_ascii_case(method) && self.url == url
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+pub struct Cassette {
+    mode: CassetteMode,
+    path: PathBuf,
+    interactions: Mutex<Vec<Interaction>>,
+    used: Mutex<Vec<bool>>,
+    unsaved: AtomicBool,
+}
+
+impl Cassette {
+    pub fn open(config: &CassetteConfig) -> Result<Self, CassetteError> {
+        let interactions = match config.mode {
+            CassetteMode::Record => Vec::new(),
+            CassetteMode::Replay => Self::load(&config.path)?,
+        };
+
+        Ok(Self {
+            mode: config.mode,
+            path: config.path.clone(),
+            used: Mutex::new(vec![false; interactions.len()]),
+            interactions: Mutex::new(interactions),
+            unsaved: AtomicBool::new(false),
+        })
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.mode == CassetteMode::Replay
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.mode == CassetteMode::Record
+    }
+
+    pub fn replay(&self, method: &str, url: &str) -> Result<Interaction, CassetteError> {
+        let interactions = self.interactions.lock().expect("cassette lock poisoned");
+        let mut used = self.used.lock().expect("cassette lock poisoned");
+
+        let position = interactions
+            .iter()
+            .enumerate()
+            .position(|(i, interaction)| !used[i] && interaction.matches(method, url))
+            .ok_or_else(|| {
+                CassetteError::Unmatched(format!("{} {}", method.to_uppercase(), url))
+            })?;
+
+        used[position] = true;
+
+        Ok(interactions[position].clone())
+    }
+
+    pub fn record(&self, interaction: Interaction) {
+        let mut interactions = self.interactions.lock().expect("cassette lock poisoned");
+        interactions.push(interaction);
+        self.unsaved.store(true, Ordering::SeqCst);
+    }
+
+    pub fn flush(&self) -> Result<(), CassetteError> {
+        if !self.is_recording() {
+            return Ok(());
+        }
+
+        let interactions = self.interactions.lock().expect("cassette lock poisoned");
+        Self::save(&self.path, &interactions)?;
+        self.unsaved.store(false, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    fn load(path: &Path) -> Result<Vec<Interaction>, CassetteError> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            CassetteError::FsError(format!("Failed to read '{}': {}", path.display(), e))
+        })?;
+
+        let file: CassetteFile = serde_json::from_str(&contents).map_err(|e| {
+            CassetteError::ParseError(format!("Failed to parse '{}': {}", path.display(), e))
+        })?;
+
+        Ok(file.interactions)
+    }
+
+    fn save(path: &Path, interactions: &[Interaction]) -> Result<(), CassetteError> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent).map_err(|e| CassetteError::FsError(e.to_string()))?;
+        }
+
+        let file = CassetteFile {
+            interactions: interactions.to_vec(),
+        };
+
+        let contents = serde_json::to_string_pretty(&file)
+            .map_err(|e| CassetteError::ParseError(e.to_string()))?;
+
+        std::fs::write(path, contents).map_err(|e| {
+            CassetteError::FsError(format!("Failed to write '{}': {}", path.display(), e))
+        })
+    }
+}
+
+impl Drop for Cassette {
+    fn drop(&mut self) {
+        if self.unsaved.load(Ordering::SeqCst)
+            && let Err(e) = self.flush()
+        {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interaction_text_body() {
+        let interaction = Interaction::new("get", "https://example.com", 200, vec![], b"hello");
+
+        assert_eq!(interaction.method, "GET");
+        assert_eq!(interaction.body, Some("hello".to_string()));
+        assert_eq!(interaction.body_base64, None);
+        assert_eq!(interaction.body_bytes(), b"hello".to_vec());
+    }
+
+    #[test]
+    fn test_interaction_binary_body() {
+        let bytes = vec![0xff, 0x00, 0xfe];
+        let interaction = Interaction::new("GET", "https://example.com", 200, vec![], &bytes);
+
+        assert_eq!(interaction.body, None);
+        assert!(interaction.body_base64.is_some());
+        assert_eq!(interaction.body_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_record_then_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassette.json");
+
+        let recorder = Cassette::open(&CassetteConfig::record(&path)).unwrap();
+        recorder.record(Interaction::new(
+            "GET",
+            "https://example.com/a",
+            200,
+            vec![],
+            b"first",
+        ));
+        recorder.record(Interaction::new(
+            "GET",
+            "https://example.com/a",
+            200,
+            vec![],
+            b"second",
+        ));
+        assert!(!path.exists());
+
+        recorder.flush().unwrap();
+
+        let player = Cassette::open(&CassetteConfig::replay(&path)).unwrap();
+
+        assert_eq!(
+            player.replay("GET", "https://example.com/a").unwrap().body,
+            Some("first".to_string())
+        );
+        assert_eq!(
+            player.replay("get", "https://example.com/a").unwrap().body,
+            Some("second".to_string())
+        );
+        assert!(matches!(
+            player.replay("GET", "https://example.com/a"),
+            Err(CassetteError::Unmatched(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsaved_interactions_are_written_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassette.json");
+
+        let recorder = Cassette::open(&CassetteConfig::record(&path)).unwrap();
+        recorder.record(Interaction::new(
+            "GET",
+            "https://example.com",
+            200,
+            vec![],
+            b"body",
+        ));
+        drop(recorder);
+
+        let player = Cassette::open(&CassetteConfig::replay(&path)).unwrap();
+
+        assert!(player.replay("GET", "https://example.com").is_ok());
+    }
+
+    #[test]
+    fn test_flush_reports_write_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("not_a_dir");
+        std::fs::write(&blocker, "").unwrap();
+
+        let recorder =
+            Cassette::open(&CassetteConfig::record(blocker.join("cassette.json"))).unwrap();
+        recorder.record(Interaction::new(
+            "GET",
+            "https://example.com",
+            200,
+            vec![],
+            b"body",
+        ));
+
+        assert!(matches!(recorder.flush(), Err(CassetteError::FsError(_))));
+    }
+
+    #[test]
+    fn test_replay_unmatched_request_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassette.json");
+        std::fs::write(&path, r#"{"interactions": []}"#).unwrap();
+
+        let player = Cassette::open(&CassetteConfig::replay(&path)).unwrap();
+
+        assert!(matches!(
+            player.replay("POST", "https://example.com"),
+            Err(CassetteError::Unmatched(_))
+        ));
+    }
+
+    #[test]
+    fn test_replay_missing_file_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = Cassette::open(&CassetteConfig::replay(dir.path().join("missing.json")));
+        assert!(matches!(result, Err(CassetteError::FsError(_))));
+    }
+}
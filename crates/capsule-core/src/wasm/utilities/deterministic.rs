@@ -0,0 +1,248 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use cap_rand::SeedableRng;
+use cap_rand::rngs::StdRng;
+use wasmtime::{CallHook, Store};
+use wasmtime_wasi::{HostMonotonicClock, HostWallClock, RngCore, WasiCtxBuilder};
+
+pub const VIRTUAL_EPOCH_SECS: u64 = 1_704_067_200;
+pub const NANOS_PER_FUEL: u64 = 1;
+
+pub fn generate_seed() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    hasher.finish()
+}
+
+pub fn derive_seed(seed: u64, stream: u64) -> u64 {
+    seeded_rng(seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15)).next_u64()
+}
+
+pub fn seeded_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+pub fn configure_wasi(builder: &mut WasiCtxBuilder, seed: u64) -> VirtualClock {
+    let clock = VirtualClock::new();
+    let mut insecure = seeded_rng(derive_seed(seed, 0));
+    let insecure_seed = (u128::from(insecure.next_u64()) << 64) | u128::from(insecure.next_u64());
+
+    builder
+        .wall_clock(clock.wall())
+        .monotonic_clock(clock.monotonic())
+        .secure_random(seeded_rng(seed))
+        .insecure_random(insecure)
+        .insecure_random_seed(insecure_seed);
+
+    clock
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VirtualClock {
+    fuel_consumed: Arc<AtomicU64>,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance_to(&self, fuel_consumed: u64) {
+        self.fuel_consumed
+            .fetch_max(fuel_consumed, Ordering::Relaxed);
+    }
+
+    pub fn elapsed_nanos(&self) -> u64 {
+        self.fuel_consumed
+            .load(Ordering::Relaxed)
+            .saturating_mul(NANOS_PER_FUEL)
+    }
+
+    pub fn monotonic(&self) -> VirtualMonotonicClock {
+        VirtualMonotonicClock(self.clone())
+    }
+
+    pub fn wall(&self) -> VirtualWallClock {
+        VirtualWallClock(self.clone())
+    }
+
+    pub fn attach<T>(self, store: &mut Store<T>, fuel_limit: u64) {
+        store.call_hook(move |store, hook| {
+            if matches!(hook, CallHook::CallingHost) {
+                self.advance_to(fuel_limit.saturating_sub(store.get_fuel()?));
+            }
+            Ok(())
+        });
+    }
+}
+
+pub struct VirtualMonotonicClock(VirtualClock);
+
+impl HostMonotonicClock for VirtualMonotonicClock {
+    fn resolution(&self) -> u64 {
+        NANOS_PER_FUEL
+    }
+
+    fn now(&self) -> u64 {
+        self.0.elapsed_nanos()
+    }
+}
+
+pub struct VirtualWallClock(VirtualClock);
+
+impl HostWallClock for VirtualWallClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(NANOS_PER_FUEL)
+    }
+
+    fn now(&self) -> Duration {
+        Duration::from_secs(VIRTUAL_EPOCH_SECS) + Duration::from_nanos(self.0.elapsed_nanos())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmtime::component::ResourceTable;
+    use wasmtime::{Caller, Config, Engine, Func, Val};
+    use wasmtime_wasi::bindings::clocks::{monotonic_clock, wall_clock};
+    use wasmtime_wasi::bindings::random::{insecure, random};
+    use wasmtime_wasi::{WasiCtx, WasiImpl, WasiView};
+
+    struct ClockView {
+        ctx: WasiCtx,
+        table: ResourceTable,
+    }
+
+    impl WasiView for ClockView {
+        fn ctx(&mut self) -> &mut WasiCtx {
+            &mut self.ctx
+        }
+
+        fn table(&mut self) -> &mut ResourceTable {
+            &mut self.table
+        }
+    }
+
+    fn seeded_view(seed: u64) -> (ClockView, VirtualClock) {
+        let mut builder = WasiCtxBuilder::new();
+        let clock = configure_wasi(&mut builder, seed);
+
+        let view = ClockView {
+            ctx: builder.build(),
+            table: ResourceTable::new(),
+        };
+
+        (view, clock)
+    }
+
+    fn guest_random(seed: u64) -> (Vec<u8>, u64) {
+        let (mut view, _clock) = seeded_view(seed);
+        let mut wasi = WasiImpl(&mut view);
+
+        (
+            random::Host::get_random_bytes(&mut wasi, 16).unwrap(),
+            insecure::Host::get_insecure_random_u64(&mut wasi).unwrap(),
+        )
+    }
+
+    fn guest_now_after(fuel: u64) -> u64 {
+        const FUEL_LIMIT: u64 = 1_000_000;
+
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).unwrap();
+
+        let (view, clock) = seeded_view(7);
+        let mut store = Store::new(&engine, view);
+        store.set_fuel(FUEL_LIMIT).unwrap();
+        clock.attach(&mut store, FUEL_LIMIT);
+
+        let now = Func::wrap(&mut store, |mut caller: Caller<'_, ClockView>| {
+            monotonic_clock::Host::now(&mut WasiImpl(caller.data_mut())).unwrap()
+        });
+
+        store.set_fuel(FUEL_LIMIT - fuel).unwrap();
+
+        let mut result = [Val::I64(0)];
+        now.call(&mut store, &[], &mut result).unwrap();
+        result[0].unwrap_i64() as u64
+    }
+
+    #[test]
+    fn test_seeded_rng_is_reproducible() {
+        let mut a = [0u8; 32];
+        let mut b = [0u8; 32];
+        let mut c = [0u8; 32];
+
+        seeded_rng(42).fill_bytes(&mut a);
+        seeded_rng(42).fill_bytes(&mut b);
+        seeded_rng(43).fill_bytes(&mut c);
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_derive_seed() {
+        assert_eq!(derive_seed(7, 1), derive_seed(7, 1));
+        assert_ne!(derive_seed(7, 1), derive_seed(7, 2));
+        assert_ne!(derive_seed(7, 1), derive_seed(8, 1));
+    }
+
+    #[test]
+    fn test_seeded_wasi_is_reproducible() {
+        assert_eq!(guest_random(42), guest_random(42));
+        assert_ne!(guest_random(42).0, guest_random(43).0);
+        assert_ne!(guest_random(42).1, guest_random(43).1);
+
+        let (mut view, clock) = seeded_view(42);
+        let mut wasi = WasiImpl(&mut view);
+
+        let start = wall_clock::Host::now(&mut wasi).unwrap();
+        assert_eq!((start.seconds, start.nanoseconds), (VIRTUAL_EPOCH_SECS, 0));
+        assert_eq!(monotonic_clock::Host::now(&mut wasi).unwrap(), 0);
+
+        clock.advance_to(2_500);
+
+        assert_eq!(
+            monotonic_clock::Host::now(&mut wasi).unwrap(),
+            2_500 * NANOS_PER_FUEL
+        );
+    }
+
+    #[test]
+    fn test_clock_follows_fuel() {
+        assert_eq!(guest_now_after(1_500), 1_500 * NANOS_PER_FUEL);
+        assert_eq!(guest_now_after(1_500), guest_now_after(1_500));
+        assert!(guest_now_after(3_000) > guest_now_after(1_500));
+    }
+
+    #[test]
+    fn test_virtual_clock() {
+        let clock = VirtualClock::new();
+        let monotonic = clock.monotonic();
+        let wall = clock.wall();
+
+        assert_eq!(monotonic.now(), 0);
+        assert_eq!(wall.now(), Duration::from_secs(VIRTUAL_EPOCH_SECS));
+
+        clock.advance_to(1_500);
+        clock.advance_to(1_000);
+
+        assert_eq!(monotonic.now(), 1_500 * NANOS_PER_FUEL);
+        assert_eq!(
+            wall.now(),
+            Duration::from_secs(VIRTUAL_EPOCH_SECS) + Duration::from_nanos(1_500 * NANOS_PER_FUEL)
+        );
+    }
+}
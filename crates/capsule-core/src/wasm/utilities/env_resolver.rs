@@ -0,0 +1,363 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EnvVariables {
+    Names(Vec<String>),
+    Map(BTreeMap<String, String>),
+}
+
+impl EnvVariables {
+    pub fn split(self) -> (Vec<String>, BTreeMap<String, String>) {
+        match self {
+            EnvVariables::Names(names) => (names, BTreeMap::new()),
+            EnvVariables::Map(values) => (Vec::new(), values),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EnvError {
+    MissingRequired {
+        name: String,
+        host_name: String,
+        message: Option<String>,
+    },
+    InvalidValue {
+        name: String,
+        value: String,
+    },
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvError::MissingRequired {
+                name,
+                host_name,
+                message,
+            } => {
+                write!(
+                    f,
+                    "Required environment variable '{}' is not set (needed for '{}')",
+                    host_name, name
+                )?;
+                if let Some(message) = message {
+                    write!(f, ": {}", message)?;
+                }
+                Ok(())
+            }
+            EnvError::InvalidValue { name, value } => {
+                write!(
+                    f,
+                    "Invalid value for environment variable '{}': '{}'. Use a literal, $HOST_NAME, ${{HOST_NAME:-default}} or ${{HOST_NAME:?message}}",
+                    name, value
+                )
+            }
+        }
+    }
+}
+
+impl Error for EnvError {}
+
+#[derive(Debug, PartialEq, Eq)]
+enum EnvValue {
+    Literal(String),
+    Host {
+        name: String,
+        default: Option<String>,
+        required: Option<Option<String>>,
+    },
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_value(name: &str, value: &str) -> Result<EnvValue, EnvError> {
+    let invalid = || EnvError::InvalidValue {
+        name: name.to_string(),
+        value: value.to_string(),
+    };
+
+    if let Some(literal) = value.strip_prefix("$$") {
+        return Ok(EnvValue::Literal(format!("${}", literal)));
+    }
+
+    if let Some(inner) = value.strip_prefix("${") {
+        let inner = inner.strip_suffix('}').ok_or_else(invalid)?;
+
+        let (host_name, default, required) = if let Some((host, default)) = inner.split_once(":-") {
+            (host, Some(default.to_string()), None)
+        } else if let Some((host, message)) = inner.split_once(":?") {
+            let message = (!message.is_empty()).then(|| message.to_string());
+            (host, None, Some(message))
+        } else {
+            (inner, None, None)
+        };
+
+        if !is_identifier(host_name) {
+            return Err(invalid());
+        }
+
+        return Ok(EnvValue::Host {
+            name: host_name.to_string(),
+            default,
+            required,
+        });
+    }
+
+    match value.strip_prefix('$') {
+        Some(host_name) if is_identifier(host_name) => Ok(EnvValue::Host {
+            name: host_name.to_string(),
+            default: None,
+            required: None,
+        }),
+        _ => Ok(EnvValue::Literal(value.to_string())),
+    }
+}
+
+pub fn resolve_env(
+    names: &[String],
+    values: &BTreeMap<String, String>,
+    host_env: &HashMap<String, String>,
+) -> Result<Vec<(String, String)>, EnvError> {
+    let lookup = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .or_else(|| host_env.get(name).cloned())
+    };
+
+    let mut envs: Vec<(String, String)> = names
+        .iter()
+        .filter_map(|name| lookup(name).map(|value| (name.clone(), value)))
+        .collect();
+
+    for (name, value) in values {
+        let resolved = match parse_value(name, value)? {
+            EnvValue::Literal(value) => Some(value),
+            EnvValue::Host {
+                name: host_name,
+                default,
+                required,
+            } => match (lookup(&host_name).or(default), required) {
+                (Some(value), _) => Some(value),
+                (None, Some(message)) => {
+                    return Err(EnvError::MissingRequired {
+                        name: name.clone(),
+                        host_name,
+                        message,
+                    });
+                }
+                (None, None) => None,
+            },
+        };
+
+        if let Some(value) = resolved {
+            envs.retain(|(existing, _)| existing != name);
+            envs.push((name.clone(), value));
+        }
+    }
+
+    Ok(envs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host_env() -> HashMap<String, String> {
+        HashMap::from([
+            ("CAPSULE_TEST_OPENAI_KEY".to_string(), "sk-test".to_string()),
+            ("CAPSULE_TEST_REGION".to_string(), "eu-west-1".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(
+            parse_value("A", "plain").unwrap(),
+            EnvValue::Literal("plain".to_string())
+        );
+        assert_eq!(
+            parse_value("A", "$$HOME").unwrap(),
+            EnvValue::Literal("$HOME".to_string())
+        );
+        assert_eq!(
+            parse_value("A", "$5.00").unwrap(),
+            EnvValue::Literal("$5.00".to_string())
+        );
+        assert_eq!(
+            parse_value("A", "${HOST:-fallback}").unwrap(),
+            EnvValue::Host {
+                name: "HOST".to_string(),
+                default: Some("fallback".to_string()),
+                required: None,
+            }
+        );
+        assert_eq!(
+            parse_value("A", "${HOST:?}").unwrap(),
+            EnvValue::Host {
+                name: "HOST".to_string(),
+                default: None,
+                required: Some(None),
+            }
+        );
+        assert!(matches!(
+            parse_value("A", "${HOST"),
+            Err(EnvError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            parse_value("A", "${1HOST}"),
+            Err(EnvError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_map_form() {
+        let values = BTreeMap::from([
+            (
+                "OPENAI_API_KEY".to_string(),
+                "$CAPSULE_TEST_OPENAI_KEY".to_string(),
+            ),
+            ("MODE".to_string(), "batch".to_string()),
+            (
+                "REGION".to_string(),
+                "${CAPSULE_TEST_REGION:-us-east-1}".to_string(),
+            ),
+            (
+                "ZONE".to_string(),
+                "${CAPSULE_TEST_MISSING_ZONE:-a}".to_string(),
+            ),
+            (
+                "OPTIONAL".to_string(),
+                "$CAPSULE_TEST_MISSING_OPTIONAL".to_string(),
+            ),
+        ]);
+
+        let envs = resolve_env(&[], &values, &host_env()).unwrap();
+
+        assert_eq!(
+            envs,
+            vec![
+                ("MODE".to_string(), "batch".to_string()),
+                ("OPENAI_API_KEY".to_string(), "sk-test".to_string()),
+                ("REGION".to_string(), "eu-west-1".to_string()),
+                ("ZONE".to_string(), "a".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_names_and_overrides() {
+        let names = vec![
+            "CAPSULE_TEST_REGION".to_string(),
+            "CAPSULE_TEST_MISSING".to_string(),
+        ];
+        let values = BTreeMap::from([("CAPSULE_TEST_REGION".to_string(), "local".to_string())]);
+
+        let envs = resolve_env(&names, &values, &host_env()).unwrap();
+
+        assert_eq!(
+            envs,
+            vec![("CAPSULE_TEST_REGION".to_string(), "local".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_names_are_an_allow_list() {
+        let names = vec!["CAPSULE_TEST_REGION".to_string()];
+
+        let envs = resolve_env(&names, &BTreeMap::new(), &host_env()).unwrap();
+
+        assert_eq!(
+            envs,
+            vec![("CAPSULE_TEST_REGION".to_string(), "eu-west-1".to_string())]
+        );
+        assert!(
+            resolve_env(&[], &BTreeMap::new(), &host_env())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_process_env_takes_precedence() {
+        let Ok(path) = std::env::var("PATH") else {
+            return;
+        };
+
+        let host_env = HashMap::from([("PATH".to_string(), "from-env-file".to_string())]);
+        let values = BTreeMap::from([("SEARCH_PATH".to_string(), "$PATH".to_string())]);
+
+        let envs = resolve_env(&["PATH".to_string()], &values, &host_env).unwrap();
+
+        assert_eq!(
+            envs,
+            vec![
+                ("PATH".to_string(), path.clone()),
+                ("SEARCH_PATH".to_string(), path),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_variable_names() {
+        let values = BTreeMap::from([(
+            "OPTIONAL".to_string(),
+            "${CAPSULE_TEST_UNKNOWN}".to_string(),
+        )]);
+        assert!(resolve_env(&[], &values, &host_env()).unwrap().is_empty());
+
+        let values = BTreeMap::from([("BROKEN".to_string(), "${NOT-A-NAME}".to_string())]);
+        let err = resolve_env(&[], &values, &host_env()).unwrap_err();
+
+        assert_eq!(
+            err,
+            EnvError::InvalidValue {
+                name: "BROKEN".to_string(),
+                value: "${NOT-A-NAME}".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_required_variable_missing() {
+        let values = BTreeMap::from([(
+            "DATABASE_URL".to_string(),
+            "${CAPSULE_TEST_MISSING_DB:?set it in .env}".to_string(),
+        )]);
+
+        let err = resolve_env(&[], &values, &host_env()).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Required environment variable 'CAPSULE_TEST_MISSING_DB' is not set (needed for 'DATABASE_URL'): set it in .env"
+        );
+    }
+
+    #[test]
+    fn test_env_variables_deserialize() {
+        let names: EnvVariables = serde_json::from_str(r#"["A", "B"]"#).unwrap();
+        assert_eq!(
+            names.split(),
+            (vec!["A".to_string(), "B".to_string()], BTreeMap::new())
+        );
+
+        let map: EnvVariables = serde_json::from_str(r#"{"A": "$B"}"#).unwrap();
+        assert_eq!(
+            map.split(),
+            (
+                Vec::new(),
+                BTreeMap::from([("A".to_string(), "$B".to_string())])
+            )
+        );
+    }
+}
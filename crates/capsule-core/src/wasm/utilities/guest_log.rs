@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_GUEST_LOG_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuestLogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl fmt::Display for GuestLogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let level_str = match self {
+            GuestLogLevel::Trace => "TRACE",
+            GuestLogLevel::Debug => "DEBUG",
+            GuestLogLevel::Info => "INFO",
+            GuestLogLevel::Warn => "WARN",
+            GuestLogLevel::Error => "ERROR",
+        };
+        write!(f, "{}", level_str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestLogEntry {
+    pub timestamp: String,
+    pub level: GuestLogLevel,
+    pub message: String,
+
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+}
+
+impl GuestLogEntry {
+    pub fn new(level: GuestLogLevel, message: String, fields: Vec<(String, String)>) -> Self {
+        Self {
+            timestamp: Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+            level,
+            message,
+            fields: fields.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct GuestLogs {
+    entries: Arc<Mutex<Vec<GuestLogEntry>>>,
+}
+
+impl GuestLogs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, entry: GuestLogEntry) -> bool {
+        let mut entries = self.entries.lock().expect("guest log lock poisoned");
+        if entries.len() >= MAX_GUEST_LOG_ENTRIES {
+            return false;
+        }
+        entries.push(entry);
+        true
+    }
+
+    pub fn snapshot(&self) -> Vec<GuestLogEntry> {
+        self.entries
+            .lock()
+            .expect("guest log lock poisoned")
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guest_log_entry() {
+        let entry = GuestLogEntry::new(
+            GuestLogLevel::Warn,
+            "retrying".to_string(),
+            vec![
+                ("attempt".to_string(), "2".to_string()),
+                ("url".to_string(), "https://example.com".to_string()),
+            ],
+        );
+
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["level"], "warn");
+        assert_eq!(json["message"], "retrying");
+        assert_eq!(json["fields"]["attempt"], "2");
+        assert_eq!(entry.level.to_string(), "WARN");
+    }
+
+    #[test]
+    fn test_guest_logs_limit() {
+        let logs = GuestLogs::new();
+
+        for i in 0..MAX_GUEST_LOG_ENTRIES {
+            assert!(logs.push(GuestLogEntry::new(
+                GuestLogLevel::Info,
+                i.to_string(),
+                vec![]
+            )));
+        }
+        assert!(!logs.push(GuestLogEntry::new(
+            GuestLogLevel::Info,
+            "dropped".to_string(),
+            vec![]
+        )));
+
+        let snapshot = logs.snapshot();
+        assert_eq!(snapshot.len(), MAX_GUEST_LOG_ENTRIES);
+        assert_eq!(snapshot[0].message, "0");
+    }
+}
@@ -0,0 +1,315 @@
+use std::fmt;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use tokio::sync::mpsc;
+use wasmtime_wasi::pipe::MemoryInputPipe;
+use wasmtime_wasi::runtime::AbortOnDropJoinHandle;
+use wasmtime_wasi::{HostInputStream, InputStream, StreamError, Subscribe};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseMode {
+    Buffered,
+    Streaming,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HostHttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+    pub timeout: Option<Duration>,
+    pub max_response_bytes: Option<u64>,
+}
+
+pub struct HostHttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: HostHttpBody,
+}
+
+pub enum HostHttpBody {
+    Buffered(Vec<u8>),
+    Streaming(ResponseStream),
+}
+
+impl HostHttpBody {
+    pub fn into_input_stream(self) -> InputStream {
+        match self {
+            HostHttpBody::Buffered(body) => Box::new(MemoryInputPipe::new(body)),
+            HostHttpBody::Streaming(stream) => Box::new(stream),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostHttpError {
+    HostNotAllowed(String),
+    InvalidUrl(String),
+    NetworkError(String),
+    Timeout(String),
+    ResponseTooLarge(u64),
+}
+
+impl fmt::Display for HostHttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostHttpError::HostNotAllowed(host) => write!(f, "Host not allowed > {}", host),
+            HostHttpError::InvalidUrl(msg) => write!(f, "Invalid URL > {}", msg),
+            HostHttpError::NetworkError(msg) => write!(f, "Network error > {}", msg),
+            HostHttpError::Timeout(msg) => write!(f, "Timeout > {}", msg),
+            HostHttpError::ResponseTooLarge(limit) => {
+                write!(f, "Response too large > exceeded {} bytes", limit)
+            }
+        }
+    }
+}
+
+impl From<reqwest::Error> for HostHttpError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            HostHttpError::Timeout(err.to_string())
+        } else {
+            HostHttpError::NetworkError(err.to_string())
+        }
+    }
+}
+
+pub async fn read_body(
+    response: &mut reqwest::Response,
+    max_bytes: Option<u64>,
+) -> Result<Vec<u8>, HostHttpError> {
+    let mut body = Vec::new();
+
+    while let Some(chunk) = response.chunk().await? {
+        if let Some(limit) = max_bytes
+            && (body.len() + chunk.len()) as u64 > limit
+        {
+            return Err(HostHttpError::ResponseTooLarge(limit));
+        }
+
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body)
+}
+
+#[derive(Debug, Default)]
+pub struct StreamSummary {
+    pub size: u64,
+    pub body: Option<Vec<u8>>,
+    pub error: Option<String>,
+    pub complete: bool,
+}
+
+pub struct ResponseStream {
+    closed: bool,
+    buffer: Option<Result<Bytes, StreamError>>,
+    receiver: mpsc::Receiver<Result<Bytes, StreamError>>,
+    _worker: AbortOnDropJoinHandle<()>,
+}
+
+impl ResponseStream {
+    pub fn new(
+        mut response: reqwest::Response,
+        max_bytes: Option<u64>,
+        keep_body: bool,
+        on_finish: impl FnOnce(StreamSummary) + Send + 'static,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(1);
+
+        let worker = wasmtime_wasi::runtime::spawn(async move {
+            let mut summary = StreamSummary {
+                body: keep_body.then(Vec::new),
+                ..Default::default()
+            };
+
+            loop {
+                let item = match response.chunk().await {
+                    Ok(Some(chunk)) => {
+                        summary.size += chunk.len() as u64;
+
+                        if let Some(limit) = max_bytes
+                            && summary.size > limit
+                        {
+                            let message = format!("Response exceeded {} bytes", limit);
+                            summary.error = Some(message.clone());
+                            Err(StreamError::LastOperationFailed(anyhow!(message)))
+                        } else {
+                            if let Some(body) = summary.body.as_mut() {
+                                body.extend_from_slice(&chunk);
+                            }
+                            Ok(chunk)
+                        }
+                    }
+                    Ok(None) => {
+                        summary.complete = true;
+                        Err(StreamError::Closed)
+                    }
+                    Err(e) => {
+                        summary.error = Some(e.to_string());
+                        Err(StreamError::LastOperationFailed(e.into()))
+                    }
+                };
+
+                let done = item.is_err();
+
+                if sender.send(item).await.is_err() || done {
+                    break;
+                }
+            }
+
+            on_finish(summary);
+        });
+
+        Self {
+            closed: false,
+            buffer: None,
+            receiver,
+            _worker: worker,
+        }
+    }
+}
+
+#[wasmtime_wasi::async_trait]
+impl HostInputStream for ResponseStream {
+    fn read(&mut self, size: usize) -> Result<Bytes, StreamError> {
+        if self.closed {
+            return Err(StreamError::Closed);
+        }
+
+        let next = match self.buffer.take() {
+            Some(next) => next,
+            None => match self.receiver.try_recv() {
+                Ok(next) => next,
+                Err(mpsc::error::TryRecvError::Empty) => return Ok(Bytes::new()),
+                Err(mpsc::error::TryRecvError::Disconnected) => Err(StreamError::Closed),
+            },
+        };
+
+        match next {
+            Ok(mut bytes) => {
+                let rest = bytes.split_off(bytes.len().min(size));
+                if !rest.is_empty() {
+                    self.buffer = Some(Ok(rest));
+                }
+                Ok(bytes)
+            }
+            Err(e) => {
+                self.closed = true;
+                Err(e)
+            }
+        }
+    }
+}
+
+#[wasmtime_wasi::async_trait]
+impl Subscribe for ResponseStream {
+    async fn ready(&mut self) {
+        if self.buffer.is_some() || self.closed {
+            return;
+        }
+
+        match self.receiver.recv().await {
+            Some(next) => self.buffer = Some(next),
+            None => self.closed = true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn run_async<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create runtime")
+            .block_on(future)
+    }
+
+    fn response(body: &'static str) -> reqwest::Response {
+        reqwest::Response::from(hyper::Response::new(body))
+    }
+
+    async fn drain(stream: &mut InputStream) -> Result<Vec<u8>, StreamError> {
+        let mut collected = Vec::new();
+
+        loop {
+            match stream.blocking_read(4).await {
+                Ok(bytes) => collected.extend_from_slice(&bytes),
+                Err(StreamError::Closed) => return Ok(collected),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_body_respects_limit() {
+        run_async(async {
+            let body = read_body(&mut response("hello"), Some(5)).await.unwrap();
+            assert_eq!(body, b"hello".to_vec());
+
+            let err = read_body(&mut response("hello world"), Some(5))
+                .await
+                .unwrap_err();
+            assert_eq!(err, HostHttpError::ResponseTooLarge(5));
+        });
+    }
+
+    #[test]
+    fn test_buffered_body_as_input_stream() {
+        run_async(async {
+            let mut stream = HostHttpBody::Buffered(vec![0, 159, 146, 150]).into_input_stream();
+            assert_eq!(drain(&mut stream).await.unwrap(), vec![0, 159, 146, 150]);
+        });
+    }
+
+    #[test]
+    fn test_response_stream_reports_summary() {
+        let summary = Arc::new(Mutex::new(None));
+        let sink = summary.clone();
+
+        run_async(async move {
+            let stream = ResponseStream::new(response("streamed body"), None, true, move |s| {
+                *sink.lock().unwrap() = Some(s);
+            });
+
+            let mut stream: InputStream = Box::new(stream);
+            assert_eq!(drain(&mut stream).await.unwrap(), b"streamed body".to_vec());
+        });
+
+        let summary = summary.lock().unwrap().take().unwrap();
+        assert_eq!(summary.size, 13);
+        assert_eq!(summary.body, Some(b"streamed body".to_vec()));
+        assert_eq!(summary.error, None);
+        assert!(summary.complete);
+    }
+
+    #[test]
+    fn test_response_stream_enforces_limit() {
+        let summary = Arc::new(Mutex::new(None));
+        let sink = summary.clone();
+
+        run_async(async move {
+            let stream = ResponseStream::new(response("too large"), Some(4), false, move |s| {
+                *sink.lock().unwrap() = Some(s);
+            });
+
+            let mut stream: InputStream = Box::new(stream);
+            assert!(matches!(
+                drain(&mut stream).await,
+                Err(StreamError::LastOperationFailed(_))
+            ));
+        });
+
+        let summary = summary.lock().unwrap().take().unwrap();
+        assert!(summary.error.is_some());
+        assert_eq!(summary.body, None);
+        assert!(!summary.complete);
+    }
+}
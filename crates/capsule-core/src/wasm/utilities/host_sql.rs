@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rusqlite::config::DbConfig;
+use rusqlite::limits::Limit;
+use rusqlite::types::Value;
+use rusqlite::{Connection, ErrorCode, OpenFlags, params_from_iter};
+
+pub const DEFAULT_SQL_MAX_ROWS: u64 = 10_000;
+pub const DEFAULT_SQL_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SqlError {
+    AccessDenied(String),
+    ReadOnly(String),
+    Timeout(u64),
+    QueryFailed(String),
+}
+
+impl fmt::Display for SqlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SqlError::AccessDenied(database) => {
+                write!(f, "SQL error > Database '{}' is not granted", database)
+            }
+            SqlError::ReadOnly(database) => {
+                write!(f, "SQL error > Database '{}' is read-only", database)
+            }
+            SqlError::Timeout(ms) => write!(f, "SQL error > Query exceeded {} ms", ms),
+            SqlError::QueryFailed(msg) => write!(f, "SQL error > {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SqlError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqlQueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    pub truncated: bool,
+}
+
+struct SqlDatabase {
+    path: PathBuf,
+    read_only: bool,
+    connection: Option<Arc<Mutex<Connection>>>,
+}
+
+pub struct SqlDatabases {
+    databases: HashMap<String, SqlDatabase>,
+    max_rows: u64,
+    timeout: Duration,
+}
+
+fn database_name(name: &str) -> String {
+    name.trim_start_matches("./").trim_matches('/').to_string()
+}
+
+fn open_connection(path: &Path, read_only: bool) -> Result<Connection, rusqlite::Error> {
+    let access = if read_only {
+        OpenFlags::SQLITE_OPEN_READ_ONLY
+    } else {
+        OpenFlags::SQLITE_OPEN_READ_WRITE
+    };
+
+    let conn = Connection::open_with_flags(path, access | OpenFlags::SQLITE_OPEN_NO_MUTEX)?;
+
+    conn.set_limit(Limit::SQLITE_LIMIT_ATTACHED, 0)?;
+    conn.set_db_config(DbConfig::SQLITE_DBCONFIG_DEFENSIVE, true)?;
+    conn.set_db_config(DbConfig::SQLITE_DBCONFIG_TRUSTED_SCHEMA, false)?;
+
+    Ok(conn)
+}
+
+fn sql_error(database: &str, timeout: Duration, err: rusqlite::Error) -> SqlError {
+    match err.sqlite_error_code() {
+        Some(ErrorCode::OperationInterrupted) => SqlError::Timeout(timeout.as_millis() as u64),
+        Some(ErrorCode::ReadOnly) => SqlError::ReadOnly(database.to_string()),
+        _ => SqlError::QueryFailed(err.to_string()),
+    }
+}
+
+fn run_query(
+    conn: &Connection,
+    statement: &str,
+    params: Vec<Value>,
+    max_rows: u64,
+) -> Result<SqlQueryResult, rusqlite::Error> {
+    let mut stmt = conn.prepare(statement)?;
+
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    let column_count = columns.len();
+
+    let mut rows = stmt.query(params_from_iter(params))?;
+    let mut result = SqlQueryResult {
+        columns,
+        rows: Vec::new(),
+        truncated: false,
+    };
+
+    while let Some(row) = rows.next()? {
+        if result.rows.len() as u64 >= max_rows {
+            result.truncated = true;
+            break;
+        }
+
+        let values = (0..column_count)
+            .map(|i| row.get::<_, Value>(i))
+            .collect::<Result<Vec<_>, _>>()?;
+        result.rows.push(values);
+    }
+
+    Ok(result)
+}
+
+impl SqlDatabases {
+    pub fn new(max_rows: Option<u64>, timeout: Option<Duration>) -> Self {
+        Self {
+            databases: HashMap::new(),
+            max_rows: max_rows.unwrap_or(DEFAULT_SQL_MAX_ROWS),
+            timeout: timeout.unwrap_or(DEFAULT_SQL_TIMEOUT),
+        }
+    }
+
+    pub fn grant(&mut self, name: &str, path: PathBuf, read_only: bool) {
+        self.databases.insert(
+            database_name(name),
+            SqlDatabase {
+                path,
+                read_only,
+                connection: None,
+            },
+        );
+    }
+
+    fn connection(&mut self, name: &str) -> Result<Arc<Mutex<Connection>>, SqlError> {
+        let database = self
+            .databases
+            .get_mut(&database_name(name))
+            .ok_or_else(|| SqlError::AccessDenied(name.to_string()))?;
+
+        if let Some(conn) = &database.connection {
+            return Ok(Arc::clone(conn));
+        }
+
+        let conn = open_connection(&database.path, database.read_only)
+            .map_err(|e| SqlError::QueryFailed(e.to_string()))?;
+        let conn = Arc::new(Mutex::new(conn));
+        database.connection = Some(Arc::clone(&conn));
+
+        Ok(conn)
+    }
+
+    async fn run<T, F>(&mut self, name: &str, work: F) -> Result<T, SqlError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, rusqlite::Error> + Send + 'static,
+    {
+        let conn = self.connection(name)?;
+        let timeout = self.timeout;
+
+        let interrupt = conn
+            .lock()
+            .map_err(|e| SqlError::QueryFailed(e.to_string()))?
+            .get_interrupt_handle();
+        let _timer = wasmtime_wasi::runtime::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            interrupt.interrupt();
+        });
+
+        let database = name.to_string();
+        wasmtime_wasi::runtime::spawn_blocking(move || {
+            let conn = conn
+                .lock()
+                .map_err(|e| SqlError::QueryFailed(e.to_string()))?;
+            work(&conn).map_err(|e| sql_error(&database, timeout, e))
+        })
+        .await
+    }
+
+    pub async fn query(
+        &mut self,
+        name: &str,
+        statement: &str,
+        params: Vec<Value>,
+    ) -> Result<SqlQueryResult, SqlError> {
+        let statement = statement.to_string();
+        let max_rows = self.max_rows;
+
+        self.run(name, move |conn| {
+            run_query(conn, &statement, params, max_rows)
+        })
+        .await
+    }
+
+    pub async fn execute(
+        &mut self,
+        name: &str,
+        statement: &str,
+        params: Vec<Value>,
+    ) -> Result<u64, SqlError> {
+        let statement = statement.to_string();
+
+        self.run(name, move |conn| {
+            conn.execute(&statement, params_from_iter(params))
+                .map(|changed| changed as u64)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_async<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create runtime")
+            .block_on(future)
+    }
+
+    fn fixture() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("data.db");
+
+        let conn = Connection::open(&path).expect("Failed to create database");
+        conn.execute_batch(
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT, price REAL);
+             INSERT INTO items (name, price) VALUES ('a', 1.5), ('b', 2.5), ('c', 3.5);",
+        )
+        .expect("Failed to seed database");
+
+        (dir, path)
+    }
+
+    #[test]
+    fn test_query_with_params() {
+        let (_dir, path) = fixture();
+        let mut sql = SqlDatabases::new(None, None);
+        sql.grant("/data/data.db", path, false);
+
+        let result = run_async(sql.query(
+            "data/data.db",
+            "SELECT name, price FROM items WHERE price > ?1 ORDER BY id",
+            vec![Value::Real(2.0)],
+        ))
+        .expect("Failed to query");
+
+        assert_eq!(result.columns, vec!["name", "price"]);
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![Value::Text("b".to_string()), Value::Real(2.5)],
+                vec![Value::Text("c".to_string()), Value::Real(3.5)],
+            ]
+        );
+        assert!(!result.truncated);
+
+        let changed = run_async(sql.execute(
+            "data/data.db",
+            "DELETE FROM items WHERE name = ?1",
+            vec![Value::Text("a".to_string())],
+        ))
+        .expect("Failed to execute");
+        assert_eq!(changed, 1);
+    }
+
+    #[test]
+    fn test_row_limit() {
+        let (_dir, path) = fixture();
+        let mut sql = SqlDatabases::new(Some(2), None);
+        sql.grant("data.db", path, true);
+
+        let result = run_async(sql.query("data.db", "SELECT id FROM items", vec![]))
+            .expect("Failed to query");
+
+        assert_eq!(result.rows.len(), 2);
+        assert!(result.truncated);
+    }
+
+    #[test]
+    fn test_access_rules() {
+        let (_dir, path) = fixture();
+        let mut sql = SqlDatabases::new(None, None);
+        sql.grant("data.db", path, true);
+
+        assert_eq!(
+            run_async(sql.query("other.db", "SELECT 1", vec![])),
+            Err(SqlError::AccessDenied("other.db".to_string()))
+        );
+        assert_eq!(
+            run_async(sql.execute("data.db", "DELETE FROM items", vec![])),
+            Err(SqlError::ReadOnly("data.db".to_string()))
+        );
+        assert!(matches!(
+            run_async(sql.execute("data.db", "ATTACH DATABASE 'other.db' AS other", vec![])),
+            Err(SqlError::QueryFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let (_dir, path) = fixture();
+        let mut sql = SqlDatabases::new(None, Some(Duration::from_millis(50)));
+        sql.grant("data.db", path, true);
+
+        let result = run_async(sql.query(
+            "data.db",
+            "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n) SELECT count(*) FROM n",
+            vec![],
+        ));
+
+        assert_eq!(result, Err(SqlError::Timeout(50)));
+    }
+}
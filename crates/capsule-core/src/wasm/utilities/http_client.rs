@@ -0,0 +1,146 @@
+use std::path::Path;
+
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use http_body_util::combinators::BoxBody;
+use hyper::Request;
+use wasmtime_wasi_http::bindings::http::types::ErrorCode;
+use wasmtime_wasi_http::types::{IncomingResponse, OutgoingRequestConfig};
+
+use crate::config::manifest::NetworkConfig;
+
+pub fn build_http_client(network: Option<&NetworkConfig>) -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder();
+
+    let Some(network) = network else {
+        return builder.build().map_err(|e| e.to_string());
+    };
+
+    if let Some(proxy_url) = &network.proxy {
+        let mut proxy = reqwest::Proxy::all(proxy_url)
+            .map_err(|e| format!("Invalid proxy '{}': {}", proxy_url, e))?;
+
+        if let Some(no_proxy) = &network.no_proxy {
+            proxy = proxy.no_proxy(reqwest::NoProxy::from_string(&no_proxy.join(",")));
+        }
+
+        builder = builder.proxy(proxy);
+    }
+
+    if let Some(ca_bundle) = &network.ca_bundle {
+        let pem = read_pem(ca_bundle)?;
+        let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("Invalid CA bundle '{}': {}", ca_bundle, e))?;
+
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    if let Some(client_cert) = &network.client_cert {
+        let mut pem = read_pem(client_cert)?;
+
+        if let Some(client_key) = &network.client_key {
+            pem.push(b'\n');
+            pem.extend(read_pem(client_key)?);
+        }
+
+        let identity = reqwest::Identity::from_pem(&pem)
+            .map_err(|e| format!("Invalid client certificate '{}': {}", client_cert, e))?;
+
+        builder = builder.identity(identity);
+    }
+
+    builder
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+fn read_pem(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(Path::new(path)).map_err(|e| format!("Failed to read '{}': {}", path, e))
+}
+
+pub async fn send_wasi_request(
+    client: reqwest::Client,
+    request: Request<BoxBody<Bytes, ErrorCode>>,
+    config: OutgoingRequestConfig,
+) -> Result<IncomingResponse, ErrorCode> {
+    let request = request.map(|body| {
+        reqwest::Body::wrap(body.map_err(|e| std::io::Error::other(format!("{:?}", e))))
+    });
+
+    let request = reqwest::Request::try_from(request)
+        .map_err(|e| ErrorCode::InternalError(Some(e.to_string())))?;
+
+    let response = tokio::time::timeout(
+        config.connect_timeout + config.first_byte_timeout,
+        client.execute(request),
+    )
+    .await
+    .map_err(|_| ErrorCode::ConnectionReadTimeout)?
+    .map_err(error_code)?;
+
+    let resp = hyper::Response::from(response).map(|body| {
+        body.map_err(|e| ErrorCode::InternalError(Some(e.to_string())))
+            .boxed()
+    });
+
+    Ok(IncomingResponse {
+        resp,
+        worker: None,
+        between_bytes_timeout: config.between_bytes_timeout,
+    })
+}
+
+fn error_code(err: reqwest::Error) -> ErrorCode {
+    if err.is_timeout() {
+        ErrorCode::ConnectionTimeout
+    } else if err.is_connect() {
+        ErrorCode::ConnectionRefused
+    } else {
+        ErrorCode::InternalError(Some(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_default_client() {
+        assert!(build_http_client(None).is_ok());
+        assert!(build_http_client(Some(&NetworkConfig::default())).is_ok());
+    }
+
+    #[test]
+    fn test_build_client_with_proxy() {
+        let network = NetworkConfig {
+            proxy: Some("http://proxy.internal:3128".to_string()),
+            no_proxy: Some(vec!["localhost".to_string(), ".internal".to_string()]),
+            ..Default::default()
+        };
+
+        assert!(build_http_client(Some(&network)).is_ok());
+    }
+
+    #[test]
+    fn test_build_client_with_invalid_proxy() {
+        let network = NetworkConfig {
+            proxy: Some("not a url".to_string()),
+            ..Default::default()
+        };
+
+        assert!(build_http_client(Some(&network)).is_err());
+    }
+
+    #[test]
+    fn test_build_client_with_missing_ca_bundle() {
+        let network = NetworkConfig {
+            ca_bundle: Some("does/not/exist.pem".to_string()),
+            ..Default::default()
+        };
+
+        let err = build_http_client(Some(&network)).unwrap_err();
+        assert!(err.contains("does/not/exist.pem"));
+    }
+}
@@ -0,0 +1,174 @@
+use std::path::Path;
+
+use crate::config::manifest::HttpMock;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+pub fn find_mock<'a>(mocks: &'a [HttpMock], method: &str, url: &str) -> Option<&'a HttpMock> {
+    mocks.iter().find(|mock| {
+        let method_matches = match mock.method.as_deref() {
+            None | Some("*") => true,
+            Some(expected) => expected.eq_ignore_ascii_case(method),
+        };
+
+        method_matches && matches_url_pattern(&mock.url, url)
+    })
+}
+
+pub fn load_mock_response(mock: &HttpMock) -> Result<MockResponse, String> {
+    let body = match (&mock.body, &mock.body_file) {
+        (Some(body), _) => body.clone().into_bytes(),
+        (None, Some(body_file)) => std::fs::read(Path::new(body_file))
+            .map_err(|e| format!("Failed to read mock body file '{}': {}", body_file, e))?,
+        (None, None) => Vec::new(),
+    };
+
+    let mut headers: Vec<(String, String)> = mock
+        .headers
+        .as_ref()
+        .map(|h| h.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default();
+    headers.sort();
+
+    Ok(MockResponse {
+        status: mock.status.unwrap_or(200),
+        headers,
+        body,
+    })
+}
+
+fn matches_url_pattern(pattern: &str, url: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+
+    if parts.len() == 1 {
+        return pattern == url;
+    }
+
+    let first = parts[0];
+    let last = parts[parts.len() - 1];
+
+    if !url.starts_with(first) || url.len() < first.len() + last.len() || !url.ends_with(last) {
+        return false;
+    }
+
+    let mut remaining = &url[first.len()..url.len() - last.len()];
+
+    for part in &parts[1..parts.len() - 1] {
+        match remaining.find(part) {
+            Some(position) => remaining = &remaining[position + part.len()..],
+            None => return false,
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn mock(method: Option<&str>, url: &str) -> HttpMock {
+        HttpMock {
+            method: method.map(|m| m.to_string()),
+            url: url.to_string(),
+            status: None,
+            headers: None,
+            body: None,
+            body_file: None,
+        }
+    }
+
+    #[test]
+    fn test_matches_url_pattern() {
+        assert!(matches_url_pattern(
+            "https://api.example.com/users",
+            "https://api.example.com/users"
+        ));
+        assert!(!matches_url_pattern(
+            "https://api.example.com/users",
+            "https://api.example.com/users/1"
+        ));
+        assert!(matches_url_pattern(
+            "https://api.example.com/users/*",
+            "https://api.example.com/users/42"
+        ));
+        assert!(matches_url_pattern(
+            "https://*.example.com/*/items",
+            "https://eu.example.com/v2/items"
+        ));
+        assert!(!matches_url_pattern(
+            "https://*.example.com/*/items",
+            "https://eu.example.org/v2/items"
+        ));
+        assert!(matches_url_pattern("*", "https://anything.dev"));
+    }
+
+    #[test]
+    fn test_find_mock_respects_method_and_order() {
+        let mocks = vec![
+            mock(Some("POST"), "https://api.example.com/*"),
+            mock(None, "https://api.example.com/users/*"),
+            mock(Some("*"), "https://api.example.com/*"),
+        ];
+
+        let found = find_mock(&mocks, "post", "https://api.example.com/users/1").unwrap();
+        assert_eq!(found.method.as_deref(), Some("POST"));
+
+        let found = find_mock(&mocks, "GET", "https://api.example.com/users/1").unwrap();
+        assert_eq!(found.method, None);
+
+        let found = find_mock(&mocks, "GET", "https://api.example.com/other").unwrap();
+        assert_eq!(found.method.as_deref(), Some("*"));
+
+        assert!(find_mock(&mocks, "GET", "https://other.com").is_none());
+    }
+
+    #[test]
+    fn test_load_mock_response_inline_body() {
+        let mut m = mock(Some("GET"), "https://api.example.com");
+        m.status = Some(201);
+        m.body = Some("{\"ok\": true}".to_string());
+        m.headers = Some(HashMap::from([(
+            "content-type".to_string(),
+            "application/json".to_string(),
+        )]));
+
+        let response = load_mock_response(&m).unwrap();
+
+        assert_eq!(response.status, 201);
+        assert_eq!(response.body, b"{\"ok\": true}".to_vec());
+        assert_eq!(
+            response.headers,
+            vec![("content-type".to_string(), "application/json".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_load_mock_response_body_file() {
+        let path = std::env::temp_dir().join("capsule_mock_body.bin");
+        std::fs::write(&path, [0xde, 0xad, 0xbe, 0xef]).unwrap();
+
+        let mut m = mock(None, "https://api.example.com");
+        m.body_file = Some(path.to_string_lossy().to_string());
+
+        let response = load_mock_response(&m).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, vec![0xde, 0xad, 0xbe, 0xef]);
+    }
+
+    #[test]
+    fn test_load_mock_response_missing_file() {
+        let mut m = mock(None, "https://api.example.com");
+        m.body_file = Some("does/not/exist.json".to_string());
+
+        assert!(load_mock_response(&m).is_err());
+    }
+}
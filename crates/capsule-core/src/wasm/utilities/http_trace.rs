@@ -0,0 +1,356 @@
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use http_body::{Body, Frame, SizeHint};
+use http_body_util::combinators::BoxBody;
+use wasmtime_wasi_http::bindings::http::types::ErrorCode;
+
+use crate::wasm::utilities::cassette::{Cassette, Interaction};
+use crate::wasm::utilities::task_config::HostRequest;
+
+#[derive(Clone, Default)]
+pub struct HttpTrace {
+    entries: Arc<Mutex<Vec<HostRequest>>>,
+}
+
+impl HttpTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, request: HostRequest) -> usize {
+        let mut entries = self.entries.lock().expect("http trace lock poisoned");
+        entries.push(request);
+        entries.len() - 1
+    }
+
+    pub fn update(&self, index: usize, f: impl FnOnce(&mut HostRequest)) {
+        let mut entries = self.entries.lock().expect("http trace lock poisoned");
+        if let Some(entry) = entries.get_mut(index) {
+            f(entry);
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<HostRequest> {
+        self.entries
+            .lock()
+            .expect("http trace lock poisoned")
+            .clone()
+    }
+}
+
+pub fn capture_body(bytes: &[u8], capture_limit: Option<usize>) -> Option<String> {
+    capture_limit
+        .map(|limit| String::from_utf8_lossy(&bytes[..bytes.len().min(limit)]).into_owned())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyDirection {
+    Request,
+    Response,
+}
+
+pub struct CapturedBody {
+    inner: BoxBody<Bytes, ErrorCode>,
+    direction: BodyDirection,
+    trace: HttpTrace,
+    index: usize,
+    size: u64,
+    buffer: Option<Vec<u8>>,
+    limit: usize,
+    finished: bool,
+    recording: Option<PendingInteraction>,
+}
+
+struct PendingInteraction {
+    cassette: Arc<Cassette>,
+    method: String,
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl CapturedBody {
+    pub fn new(
+        inner: BoxBody<Bytes, ErrorCode>,
+        direction: BodyDirection,
+        trace: HttpTrace,
+        index: usize,
+        capture_limit: Option<usize>,
+    ) -> Self {
+        Self {
+            inner,
+            direction,
+            trace,
+            index,
+            size: 0,
+            buffer: capture_limit.map(|_| Vec::new()),
+            limit: capture_limit.unwrap_or(0),
+            finished: false,
+            recording: None,
+        }
+    }
+
+    pub fn record_into(
+        mut self,
+        cassette: Arc<Cassette>,
+        method: String,
+        url: String,
+        status: u16,
+        headers: Vec<(String, String)>,
+    ) -> Self {
+        self.recording = Some(PendingInteraction {
+            cassette,
+            method,
+            url,
+            status,
+            headers,
+            body: Vec::new(),
+        });
+        self
+    }
+
+    fn record_chunk(&mut self, chunk: &Bytes) {
+        self.size += chunk.len() as u64;
+
+        if let Some(buffer) = self.buffer.as_mut() {
+            let remaining = self.limit.saturating_sub(buffer.len());
+            buffer.extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+        }
+
+        if let Some(recording) = self.recording.as_mut() {
+            recording.body.extend_from_slice(chunk);
+        }
+    }
+
+    fn finish(&mut self, complete: bool, error: Option<String>) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+
+        if let Some(recording) = self.recording.take()
+            && complete
+        {
+            recording.cassette.record(Interaction::new(
+                &recording.method,
+                &recording.url,
+                recording.status,
+                recording.headers,
+                &recording.body,
+            ));
+        }
+
+        let size = self.size;
+        let body = self
+            .buffer
+            .take()
+            .map(|b| String::from_utf8_lossy(&b).into_owned());
+
+        self.trace.update(self.index, |entry| {
+            match self.direction {
+                BodyDirection::Request => entry.body = body,
+                BodyDirection::Response => {
+                    entry.response_size = Some(size);
+                    entry.response_body = body;
+                }
+            }
+
+            if error.is_some() {
+                entry.error = error;
+            }
+        });
+    }
+}
+
+impl Body for CapturedBody {
+    type Data = Bytes;
+    type Error = ErrorCode;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let polled = Pin::new(&mut self.inner).poll_frame(cx);
+
+        match &polled {
+            Poll::Ready(Some(Ok(frame))) => {
+                if let Some(chunk) = frame.data_ref() {
+                    self.record_chunk(chunk);
+                }
+            }
+            Poll::Ready(Some(Err(e))) => self.finish(false, Some(format!("{:?}", e))),
+            Poll::Ready(None) => self.finish(true, None),
+            Poll::Pending => {}
+        }
+
+        polled
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for CapturedBody {
+    fn drop(&mut self) {
+        let complete = self.inner.is_end_stream();
+        self.finish(complete, None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wasm::utilities::cassette::{CassetteConfig, CassetteError};
+    use http_body_util::{BodyExt, Full};
+
+    struct Chunks(Vec<&'static str>);
+
+    impl Body for Chunks {
+        type Data = Bytes;
+        type Error = ErrorCode;
+
+        fn poll_frame(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+            if self.0.is_empty() {
+                return Poll::Ready(None);
+            }
+
+            let chunk = self.0.remove(0);
+            Poll::Ready(Some(Ok(Frame::data(Bytes::from(chunk)))))
+        }
+    }
+
+    fn boxed(data: &'static str) -> BoxBody<Bytes, ErrorCode> {
+        Full::new(Bytes::from(data))
+            .map_err(|never| match never {})
+            .boxed()
+    }
+
+    fn run_async<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("Failed to create runtime")
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_trace_push_and_update() {
+        let trace = HttpTrace::new();
+        let index = trace.push(HostRequest {
+            method: "GET".to_string(),
+            url: "https://example.com".to_string(),
+            ..Default::default()
+        });
+
+        trace.update(index, |entry| entry.status = Some(204));
+
+        let entries = trace.snapshot();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].status, Some(204));
+    }
+
+    #[test]
+    fn test_response_body_is_measured_and_capped() {
+        let trace = HttpTrace::new();
+        let index = trace.push(HostRequest::default());
+
+        let body = CapturedBody::new(
+            boxed("hello world"),
+            BodyDirection::Response,
+            trace.clone(),
+            index,
+            Some(5),
+        );
+
+        let collected = run_async(body.collect()).unwrap().to_bytes();
+        assert_eq!(collected, Bytes::from("hello world"));
+
+        let entry = &trace.snapshot()[0];
+        assert_eq!(entry.response_size, Some(11));
+        assert_eq!(entry.response_body, Some("hello".to_string()));
+    }
+
+    #[test]
+    fn test_capture_body() {
+        assert_eq!(capture_body(b"hello", None), None);
+        assert_eq!(capture_body(b"hello", Some(3)), Some("hel".to_string()));
+        assert_eq!(capture_body(b"hi", Some(10)), Some("hi".to_string()));
+    }
+
+    #[test]
+    fn test_request_body_not_captured_without_limit() {
+        let trace = HttpTrace::new();
+        let index = trace.push(HostRequest::default());
+
+        let body = CapturedBody::new(
+            boxed("payload"),
+            BodyDirection::Request,
+            trace.clone(),
+            index,
+            None,
+        );
+
+        run_async(body.collect()).unwrap();
+
+        let entry = &trace.snapshot()[0];
+        assert_eq!(entry.body, None);
+        assert_eq!(entry.response_size, None);
+    }
+
+    #[test]
+    fn test_only_fully_read_bodies_are_recorded() {
+        let path = std::env::temp_dir().join("capsule_http_trace_cassette.json");
+        let cassette = Arc::new(Cassette::open(&CassetteConfig::record(&path)).unwrap());
+
+        let recorded = |chunks: Vec<&'static str>, url: &str| {
+            CapturedBody::new(
+                BoxBody::new(Chunks(chunks)),
+                BodyDirection::Response,
+                HttpTrace::new(),
+                0,
+                None,
+            )
+            .record_into(
+                Arc::clone(&cassette),
+                "GET".to_string(),
+                url.to_string(),
+                200,
+                vec![],
+            )
+        };
+
+        let mut partial = recorded(vec!["first", "second"], "https://example.com/partial");
+        run_async(partial.frame()).unwrap().unwrap();
+        drop(partial);
+
+        let full = recorded(vec!["first", "second"], "https://example.com/full");
+        run_async(full.collect()).unwrap();
+
+        cassette.flush().unwrap();
+        let player = Cassette::open(&CassetteConfig::replay(&path)).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            player
+                .replay("GET", "https://example.com/full")
+                .unwrap()
+                .body,
+            Some("firstsecond".to_string())
+        );
+        assert!(matches!(
+            player.replay("GET", "https://example.com/partial"),
+            Err(CassetteError::Unmatched(_))
+        ));
+    }
+}
@@ -1,7 +1,21 @@
 pub mod cache;
+pub mod cassette;
+pub mod deterministic;
+pub mod env_resolver;
+pub mod guest_log;
+pub mod host_http;
+pub mod host_sql;
 pub mod host_validator;
+pub mod http_client;
+pub mod http_mock;
+pub mod http_trace;
 pub mod introspection;
 pub mod path_validator;
+pub mod stdin_input;
+pub mod stdio_capture;
 pub mod task_config;
+pub mod task_handle;
+pub mod task_progress;
 pub mod task_reporter;
+pub mod task_runner;
 pub mod wit_manager;
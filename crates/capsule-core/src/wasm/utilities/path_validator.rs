@@ -2,12 +2,18 @@ use std::error::Error;
 use std::fmt;
 use std::path::{Path, PathBuf};
 
+use walkdir::WalkDir;
+
+use crate::wasm::filesystem::archive::ArchiveFormat;
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum FileAccessMode {
     ReadOnly,
 
     #[default]
     ReadWrite,
+
+    CopyOnWrite,
 }
 
 #[derive(Debug)]
@@ -15,14 +21,19 @@ pub struct ParsedPath {
     pub path: PathBuf,
     pub guest_path: String,
     pub mode: FileAccessMode,
+    pub archive: Option<ArchiveFormat>,
+    pub files: Option<Vec<PathBuf>>,
 }
 
 #[derive(Debug)]
 pub enum PathValidationError {
     AbsolutePathNotAllowed(String),
+    RootNotAllowed(String),
     EscapesProjectDirectory(String),
     PathNotFound(String),
     InvalidMode(String),
+    ArchiveNotReadOnly(String),
+    NoGlobMatches(String),
 }
 
 impl fmt::Display for PathValidationError {
@@ -31,6 +42,13 @@ impl fmt::Display for PathValidationError {
             PathValidationError::AbsolutePathNotAllowed(path) => {
                 write!(f, "Absolute paths are not allowed: {}", path)
             }
+            PathValidationError::RootNotAllowed(path) => {
+                write!(
+                    f,
+                    "Path is not under an allowed root in [filesystem] allowed_roots: {}",
+                    path
+                )
+            }
             PathValidationError::EscapesProjectDirectory(path) => {
                 write!(f, "Path escapes project directory: {}", path)
             }
@@ -40,10 +58,16 @@ impl fmt::Display for PathValidationError {
             PathValidationError::InvalidMode(mode) => {
                 write!(
                     f,
-                    "Invalid access mode '{}'. Use :ro (read-only) or :rw (read-write)",
+                    "Invalid access mode '{}'. Use :ro (read-only), :rw (read-write) or :cow (copy-on-write)",
                     mode
                 )
             }
+            PathValidationError::ArchiveNotReadOnly(path) => {
+                write!(f, "Archives can only be mounted read-only: {}", path)
+            }
+            PathValidationError::NoGlobMatches(pattern) => {
+                write!(f, "Pattern matched no files: {}", pattern)
+            }
         }
     }
 }
@@ -57,6 +81,9 @@ fn parse_path_spec(path_spec: &str) -> (String, Option<String>, FileAccessMode)
     } else if path_spec.ends_with(":rw") {
         let stripped = path_spec.strip_suffix(":rw").unwrap();
         (stripped, FileAccessMode::ReadWrite)
+    } else if path_spec.ends_with(":cow") {
+        let stripped = path_spec.strip_suffix(":cow").unwrap();
+        (stripped, FileAccessMode::CopyOnWrite)
     } else {
         (path_spec, FileAccessMode::default())
     };
@@ -73,44 +100,184 @@ fn parse_path_spec(path_spec: &str) -> (String, Option<String>, FileAccessMode)
 fn to_absolute_guest_path(path: &str) -> String {
     let stripped = path.trim_start_matches("./");
 
-    if stripped.starts_with('/') {
+    if stripped == "." {
+        "/".to_string()
+    } else if stripped.starts_with('/') {
         stripped.to_string()
     } else {
         format!("/{}", stripped)
     }
 }
 
-pub fn validate_path(
-    path_spec: &str,
+fn is_glob(segment: &str) -> bool {
+    segment.contains('*') || segment.contains('?')
+}
+
+fn split_glob(host_str: &str) -> (String, Option<Vec<String>>) {
+    let segments: Vec<&str> = host_str.split('/').collect();
+
+    let Some(idx) = segments.iter().position(|segment| is_glob(segment)) else {
+        return (host_str.to_string(), None);
+    };
+
+    let base = match segments[..idx].join("/") {
+        base if !base.is_empty() => base,
+        _ if host_str.starts_with('/') => "/".to_string(),
+        _ => ".".to_string(),
+    };
+
+    let pattern = segments[idx..]
+        .iter()
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.to_string())
+        .collect();
+
+    (base, Some(pattern))
+}
+
+fn matches_segment(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| matches_segment(rest, &name[i..])),
+        Some(('?', rest)) => !name.is_empty() && matches_segment(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && matches_segment(rest, &name[1..]),
+    }
+}
+
+fn matches_glob(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((segment, rest)) if segment == "**" => {
+            (0..=path.len()).any(|i| matches_glob(rest, &path[i..]))
+        }
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, remaining)) => {
+                let segment: Vec<char> = segment.chars().collect();
+                let name: Vec<char> = name.chars().collect();
+                matches_segment(&segment, &name) && matches_glob(rest, remaining)
+            }
+            None => false,
+        },
+    }
+}
+
+fn expand_glob(base: &Path, pattern: &[String]) -> Vec<PathBuf> {
+    let mut walker = WalkDir::new(base).min_depth(1).sort_by_file_name();
+
+    if !pattern.iter().any(|segment| segment == "**") {
+        walker = walker.max_depth(pattern.len());
+    }
+
+    walker
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(base).ok()?.to_path_buf();
+            let segments: Vec<String> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect();
+
+            matches_glob(pattern, &segments).then_some(relative)
+        })
+        .collect()
+}
+
+fn resolve_host_path(
+    base: &str,
+    host_str: &str,
     project_root: &Path,
-) -> Result<ParsedPath, PathValidationError> {
-    let (host_str, guest_alias, mode) = parse_path_spec(path_spec);
-    let p = Path::new(&host_str);
+    allowed_roots: &[PathBuf],
+) -> Result<PathBuf, PathValidationError> {
+    let p = Path::new(base);
 
     if p.is_absolute() {
-        return Err(PathValidationError::AbsolutePathNotAllowed(host_str));
+        if allowed_roots.is_empty() {
+            return Err(PathValidationError::AbsolutePathNotAllowed(
+                host_str.to_string(),
+            ));
+        }
+
+        let resolved = p
+            .canonicalize()
+            .map_err(|_| PathValidationError::PathNotFound(host_str.to_string()))?;
+
+        let allowed = allowed_roots
+            .iter()
+            .filter_map(|root| root.canonicalize().ok())
+            .any(|root| resolved.starts_with(root));
+
+        if !allowed {
+            return Err(PathValidationError::RootNotAllowed(host_str.to_string()));
+        }
+
+        return Ok(resolved);
     }
 
     let joined = project_root.join(p);
     let resolved = joined
         .canonicalize()
-        .map_err(|_| PathValidationError::PathNotFound(host_str.clone()))?;
+        .map_err(|_| PathValidationError::PathNotFound(host_str.to_string()))?;
 
     let canonical_root = project_root
         .canonicalize()
-        .map_err(|_| PathValidationError::EscapesProjectDirectory(host_str.clone()))?;
+        .map_err(|_| PathValidationError::EscapesProjectDirectory(host_str.to_string()))?;
 
     if !resolved.starts_with(&canonical_root) {
-        return Err(PathValidationError::EscapesProjectDirectory(host_str));
+        return Err(PathValidationError::EscapesProjectDirectory(
+            host_str.to_string(),
+        ));
     }
 
-    let raw_guest = guest_alias.unwrap_or_else(|| host_str.clone());
+    Ok(resolved)
+}
+
+pub fn validate_path(
+    path_spec: &str,
+    project_root: &Path,
+    allowed_roots: &[PathBuf],
+) -> Result<ParsedPath, PathValidationError> {
+    let (host_str, guest_alias, mode) = parse_path_spec(path_spec);
+    let (base, pattern) = split_glob(&host_str);
+
+    let resolved = resolve_host_path(&base, &host_str, project_root, allowed_roots)?;
+
+    let files = match pattern {
+        Some(pattern) => {
+            let files = expand_glob(&resolved, &pattern);
+
+            if files.is_empty() {
+                return Err(PathValidationError::NoGlobMatches(host_str));
+            }
+
+            Some(files)
+        }
+        None => None,
+    };
+
+    let archive = if files.is_none() && resolved.is_file() {
+        ArchiveFormat::detect(&resolved)
+    } else {
+        None
+    };
+
+    let mode = match archive {
+        Some(_) if path_spec.ends_with(":rw") || path_spec.ends_with(":cow") => {
+            return Err(PathValidationError::ArchiveNotReadOnly(host_str));
+        }
+        Some(_) => FileAccessMode::ReadOnly,
+        None => mode,
+    };
+
+    let raw_guest = guest_alias.unwrap_or(base);
     let guest_path = to_absolute_guest_path(&raw_guest);
 
     Ok(ParsedPath {
         path: resolved,
         guest_path,
         mode,
+        archive,
+        files,
     })
 }
 
@@ -123,7 +290,7 @@ mod tests {
     fn test_absolute_path_rejected() {
         let temp = std::env::temp_dir();
 
-        let result = validate_path("/absolute/path", &temp);
+        let result = validate_path("/absolute/path", &temp, &[]);
         assert!(matches!(
             result,
             Err(PathValidationError::AbsolutePathNotAllowed(_))
@@ -137,7 +304,7 @@ mod tests {
         let test_dir = current.join(".capsule_test");
         let _ = fs::create_dir(&test_dir);
 
-        let result = validate_path("./.capsule_test", &current);
+        let result = validate_path("./.capsule_test", &current, &[]);
 
         let _ = fs::remove_dir(&test_dir);
 
@@ -150,7 +317,7 @@ mod tests {
     fn test_non_existent_path_fails() {
         let current = std::env::current_dir().unwrap();
 
-        let result = validate_path("./nonexistent_dir", &current);
+        let result = validate_path("./nonexistent_dir", &current, &[]);
         assert!(matches!(result, Err(PathValidationError::PathNotFound(_))));
     }
 
@@ -160,7 +327,7 @@ mod tests {
         let subdir = temp.join("test_subdir");
         let _ = fs::create_dir(&subdir);
 
-        let result = validate_path("../", &subdir);
+        let result = validate_path("../", &subdir, &[]);
 
         let _ = fs::remove_dir(&subdir);
 
@@ -170,6 +337,121 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_archive_mounted_read_only() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("fixtures.tar.gz"), b"").unwrap();
+        fs::write(root.path().join("notes.txt"), b"").unwrap();
+
+        let parsed = validate_path("./fixtures.tar.gz::fixtures", root.path(), &[]).unwrap();
+        assert_eq!(parsed.guest_path, "/fixtures");
+        assert_eq!(parsed.mode, FileAccessMode::ReadOnly);
+        assert_eq!(parsed.archive, Some(ArchiveFormat::TarGz));
+
+        let parsed = validate_path("./notes.txt", root.path(), &[]).unwrap();
+        assert_eq!(parsed.archive, None);
+
+        for spec in ["./fixtures.tar.gz:rw", "./fixtures.tar.gz::fixtures:cow"] {
+            assert!(matches!(
+                validate_path(spec, root.path(), &[]),
+                Err(PathValidationError::ArchiveNotReadOnly(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_absolute_path_under_allowed_root() {
+        let project = tempfile::tempdir().unwrap();
+        let shared = tempfile::tempdir().unwrap();
+        let models = shared.path().join("models");
+        fs::create_dir(&models).unwrap();
+
+        let spec = format!("{}::models:ro", models.display());
+        let parsed = validate_path(&spec, project.path(), &[shared.path().to_path_buf()]).unwrap();
+        assert_eq!(parsed.path, models.canonicalize().unwrap());
+        assert_eq!(parsed.guest_path, "/models");
+        assert_eq!(parsed.mode, FileAccessMode::ReadOnly);
+
+        let other = tempfile::tempdir().unwrap();
+        let result = validate_path(
+            &other.path().display().to_string(),
+            project.path(),
+            &[shared.path().to_path_buf()],
+        );
+        assert!(matches!(
+            result,
+            Err(PathValidationError::RootNotAllowed(_))
+        ));
+
+        let escaped = format!("{}/../", models.display());
+        let result = validate_path(&escaped, project.path(), &[models]);
+        assert!(matches!(
+            result,
+            Err(PathValidationError::RootNotAllowed(_))
+        ));
+    }
+
+    #[test]
+    fn test_glob_expands_to_files() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("data/nested")).unwrap();
+        fs::write(root.path().join("data/a.csv"), b"").unwrap();
+        fs::write(root.path().join("data/b.csv"), b"").unwrap();
+        fs::write(root.path().join("data/notes.txt"), b"").unwrap();
+        fs::write(root.path().join("data/nested/c.csv"), b"").unwrap();
+
+        let parsed = validate_path("./data/*.csv:ro", root.path(), &[]).unwrap();
+        assert_eq!(
+            parsed.path,
+            root.path().join("data").canonicalize().unwrap()
+        );
+        assert_eq!(parsed.guest_path, "/data");
+        assert_eq!(parsed.mode, FileAccessMode::ReadOnly);
+        assert_eq!(
+            parsed.files,
+            Some(vec![PathBuf::from("a.csv"), PathBuf::from("b.csv")])
+        );
+
+        let parsed = validate_path("./data/**/*.csv::inputs", root.path(), &[]).unwrap();
+        assert_eq!(parsed.guest_path, "/inputs");
+        assert_eq!(
+            parsed.files,
+            Some(vec![
+                PathBuf::from("a.csv"),
+                PathBuf::from("b.csv"),
+                PathBuf::from("nested/c.csv"),
+            ])
+        );
+
+        let parsed = validate_path("./data/?.csv", root.path(), &[]).unwrap();
+        assert_eq!(parsed.files.map(|files| files.len()), Some(2));
+
+        assert!(matches!(
+            validate_path("./data/*.json", root.path(), &[]),
+            Err(PathValidationError::NoGlobMatches(_))
+        ));
+    }
+
+    #[test]
+    fn test_split_glob() {
+        assert_eq!(split_glob("./data"), ("./data".to_string(), None));
+        assert_eq!(
+            split_glob("./data/*.csv"),
+            ("./data".to_string(), Some(vec!["*.csv".to_string()]))
+        );
+        assert_eq!(
+            split_glob("*.csv"),
+            (".".to_string(), Some(vec!["*.csv".to_string()]))
+        );
+        assert_eq!(
+            split_glob("/var/lib/**/*.bin"),
+            (
+                "/var/lib".to_string(),
+                Some(vec!["**".to_string(), "*.bin".to_string()])
+            )
+        );
+    }
+
     #[test]
     fn test_parse_mode_readonly() {
         let (path, guest, mode) = parse_path_spec("./data:ro");
@@ -186,6 +468,14 @@ mod tests {
         assert_eq!(mode, FileAccessMode::ReadWrite);
     }
 
+    #[test]
+    fn test_parse_mode_copy_on_write() {
+        let (path, guest, mode) = parse_path_spec("./repo::workspace:cow");
+        assert_eq!(path, "./repo");
+        assert_eq!(guest, Some("workspace".to_string()));
+        assert_eq!(mode, FileAccessMode::CopyOnWrite);
+    }
+
     #[test]
     fn test_parse_mode_default() {
         let (path, guest, mode) = parse_path_spec("./data");
@@ -216,6 +506,7 @@ mod tests {
         assert_eq!(to_absolute_guest_path("data"), "/data");
         assert_eq!(to_absolute_guest_path("/data"), "/data");
         assert_eq!(to_absolute_guest_path("/"), "/");
+        assert_eq!(to_absolute_guest_path("."), "/");
         assert_eq!(to_absolute_guest_path("workspace"), "/workspace");
         assert_eq!(to_absolute_guest_path("./nested/dir"), "/nested/dir");
     }
@@ -0,0 +1,110 @@
+use std::error::Error;
+use std::fmt;
+use std::io::Read;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+pub const DEFAULT_STDIN_LIMIT: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum StdinError {
+    TooLarge { limit: u64 },
+    InvalidBase64(String),
+    Conflict,
+    Io(String),
+}
+
+impl fmt::Display for StdinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StdinError::TooLarge { limit } => {
+                write!(f, "Stdin payload exceeds the {} byte limit", limit)
+            }
+            StdinError::InvalidBase64(msg) => write!(f, "Invalid base64 stdin payload: {}", msg),
+            StdinError::Conflict => write!(f, "Use either 'stdin' or 'stdin_base64', not both"),
+            StdinError::Io(msg) => write!(f, "Failed to read stdin: {}", msg),
+        }
+    }
+}
+
+impl Error for StdinError {}
+
+fn check_limit(data: Vec<u8>, limit: u64) -> Result<Vec<u8>, StdinError> {
+    if data.len() as u64 > limit {
+        return Err(StdinError::TooLarge { limit });
+    }
+    Ok(data)
+}
+
+pub fn read_stdin<R: Read>(reader: R, limit: u64) -> Result<Vec<u8>, StdinError> {
+    let mut data = Vec::new();
+
+    reader
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut data)
+        .map_err(|e| StdinError::Io(e.to_string()))?;
+
+    check_limit(data, limit)
+}
+
+pub fn decode_stdin(
+    text: Option<String>,
+    base64: Option<String>,
+    limit: u64,
+) -> Result<Option<Vec<u8>>, StdinError> {
+    let data = match (text, base64) {
+        (Some(_), Some(_)) => return Err(StdinError::Conflict),
+        (Some(text), None) => text.into_bytes(),
+        (None, Some(encoded)) => STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| StdinError::InvalidBase64(e.to_string()))?,
+        (None, None) => return Ok(None),
+    };
+
+    check_limit(data, limit).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_stdin_limit() {
+        let data = read_stdin(&b"{\"rows\": 3}"[..], 64).unwrap();
+        assert_eq!(data, b"{\"rows\": 3}");
+
+        let data = read_stdin(&b"12345678"[..], 8).unwrap();
+        assert_eq!(data.len(), 8);
+
+        assert_eq!(
+            read_stdin(&b"123456789"[..], 8),
+            Err(StdinError::TooLarge { limit: 8 })
+        );
+    }
+
+    #[test]
+    fn test_decode_stdin() {
+        assert_eq!(decode_stdin(None, None, 8).unwrap(), None);
+        assert_eq!(
+            decode_stdin(Some("hi".to_string()), None, 8).unwrap(),
+            Some(b"hi".to_vec())
+        );
+        assert_eq!(
+            decode_stdin(None, Some("AAEC/w==".to_string()), 8).unwrap(),
+            Some(vec![0, 1, 2, 255])
+        );
+        assert_eq!(
+            decode_stdin(Some("too long".to_string()), None, 4),
+            Err(StdinError::TooLarge { limit: 4 })
+        );
+        assert!(matches!(
+            decode_stdin(None, Some("not base64!".to_string()), 64),
+            Err(StdinError::InvalidBase64(_))
+        ));
+        assert_eq!(
+            decode_stdin(Some("a".to_string()), Some("YQ==".to_string()), 8),
+            Err(StdinError::Conflict)
+        );
+    }
+}
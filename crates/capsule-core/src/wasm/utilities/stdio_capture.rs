@@ -0,0 +1,199 @@
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+use wasmtime_wasi::{HostOutputStream, StdoutStream, StreamResult, Subscribe, async_trait};
+
+pub const DEFAULT_CAPTURE_LIMIT: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputKind {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputEvent {
+    pub task_id: String,
+    pub task_name: String,
+    pub stream: OutputKind,
+    pub data: String,
+}
+
+#[derive(Debug, Default)]
+struct CaptureBuffer {
+    data: Vec<u8>,
+    limit: usize,
+    truncated: bool,
+}
+
+impl CaptureBuffer {
+    fn push(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+
+        if self.data.len() > self.limit {
+            let excess = self.data.len() - self.limit;
+            self.data.drain(..excess);
+            self.truncated = true;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct EventTarget {
+    sender: UnboundedSender<OutputEvent>,
+    task_id: String,
+    task_name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CaptureStream {
+    kind: OutputKind,
+    buffer: Arc<Mutex<CaptureBuffer>>,
+    events: Option<EventTarget>,
+}
+
+impl CaptureStream {
+    fn new(kind: OutputKind, limit: u64, events: Option<EventTarget>) -> Self {
+        Self {
+            kind,
+            buffer: Arc::new(Mutex::new(CaptureBuffer {
+                limit: limit as usize,
+                ..Default::default()
+            })),
+            events,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.lock().unwrap().data).into_owned()
+    }
+
+    pub fn truncated(&self) -> bool {
+        self.buffer.lock().unwrap().truncated
+    }
+}
+
+impl StdoutStream for CaptureStream {
+    fn stream(&self) -> Box<dyn HostOutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+#[async_trait]
+impl Subscribe for CaptureStream {
+    async fn ready(&mut self) {}
+}
+
+#[async_trait]
+impl HostOutputStream for CaptureStream {
+    fn write(&mut self, bytes: Bytes) -> StreamResult<()> {
+        self.buffer.lock().unwrap().push(&bytes);
+
+        if let Some(events) = &self.events {
+            let _ = events.sender.send(OutputEvent {
+                task_id: events.task_id.clone(),
+                task_name: events.task_name.clone(),
+                stream: self.kind,
+                data: String::from_utf8_lossy(&bytes).into_owned(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        Ok(64 * 1024)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StdioCapture {
+    stdout: CaptureStream,
+    stderr: CaptureStream,
+}
+
+impl StdioCapture {
+    pub fn new(
+        limit: u64,
+        events: Option<UnboundedSender<OutputEvent>>,
+        task_id: &str,
+        task_name: &str,
+    ) -> Self {
+        let events = events.map(|sender| EventTarget {
+            sender,
+            task_id: task_id.to_string(),
+            task_name: task_name.to_string(),
+        });
+
+        Self {
+            stdout: CaptureStream::new(OutputKind::Stdout, limit, events.clone()),
+            stderr: CaptureStream::new(OutputKind::Stderr, limit, events),
+        }
+    }
+
+    pub fn stdout(&self) -> CaptureStream {
+        self.stdout.clone()
+    }
+
+    pub fn stderr(&self) -> CaptureStream {
+        self.stderr.clone()
+    }
+
+    pub fn truncated(&self) -> bool {
+        self.stdout.truncated() || self.stderr.truncated()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_keeps_tail_when_truncated() {
+        let capture = StdioCapture::new(8, None, "task", "main");
+        let mut stdout = capture.stdout().stream();
+
+        stdout.write(Bytes::from_static(b"hello ")).unwrap();
+        assert!(!capture.truncated());
+
+        stdout.write(Bytes::from_static(b"world!")).unwrap();
+
+        assert_eq!(capture.stdout().text(), "o world!");
+        assert_eq!(capture.stderr().text(), "");
+        assert!(capture.truncated());
+    }
+
+    #[test]
+    fn test_capture_streams_events() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let capture = StdioCapture::new(DEFAULT_CAPTURE_LIMIT, Some(tx), "abc", "main");
+
+        capture
+            .stderr()
+            .stream()
+            .write(Bytes::from_static(b"warning\n"))
+            .unwrap();
+
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            OutputEvent {
+                task_id: "abc".to_string(),
+                task_name: "main".to_string(),
+                stream: OutputKind::Stderr,
+                data: "warning\n".to_string(),
+            }
+        );
+        assert_eq!(capture.stderr().text(), "warning\n");
+    }
+}
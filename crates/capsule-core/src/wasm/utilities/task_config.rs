@@ -1,7 +1,16 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 use crate::config::manifest::CapsuleToml;
-use crate::wasm::execution_policy::{Compute, ExecutionPolicy};
+use crate::wasm::execution_policy::{
+    Compute, ExecutionPolicy, KvPolicy, OutputsPolicy, ScratchPolicy, SqlPolicy,
+};
+use crate::wasm::filesystem::audit::FileAccessSummary;
+use crate::wasm::filesystem::outputs::Artifact;
+use crate::wasm::filesystem::overlay::OverlayChange;
+use crate::wasm::utilities::env_resolver::EnvVariables;
+use crate::wasm::utilities::guest_log::GuestLogEntry;
 
 #[derive(Serialize, Deserialize)]
 pub struct TaskResult {
@@ -15,6 +24,30 @@ pub struct TaskResult {
 pub struct TaskError {
     pub error_type: String,
     pub message: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traceback: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskInvocation {
+    pub task_name: String,
+
+    #[serde(default)]
+    pub args: Vec<serde_json::Value>,
+
+    #[serde(default)]
+    pub kwargs: serde_json::Map<String, serde_json::Value>,
+}
+
+impl TaskInvocation {
+    pub fn new(task_name: &str, args: Vec<serde_json::Value>) -> Self {
+        Self {
+            task_name: task_name.to_string(),
+            args,
+            kwargs: serde_json::Map::new(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -25,14 +58,49 @@ pub struct TaskExecution {
     pub fuel_consumed: u64,
     pub ram_used: u64,
     pub host_requests: Vec<HostRequest>,
+
+    #[serde(default)]
+    pub overlay_changes: Vec<OverlayChange>,
+
+    #[serde(default)]
+    pub file_access: Option<FileAccessSummary>,
+
+    #[serde(default)]
+    pub artifacts: Vec<Artifact>,
+
+    #[serde(default)]
+    pub stdout: String,
+
+    #[serde(default)]
+    pub stderr: String,
+
+    #[serde(default)]
+    pub output_truncated: bool,
+
+    #[serde(default)]
+    pub logs: Vec<GuestLogEntry>,
+
+    #[serde(default)]
+    pub metrics: BTreeMap<String, f64>,
+
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HostRequest {
     pub method: String,
     pub url: String,
     pub headers: Option<Vec<(String, String)>>,
     pub body: Option<String>,
+    pub status: Option<u16>,
+    pub response_size: Option<u64>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+    pub response_body: Option<String>,
+
+    #[serde(default)]
+    pub mocked: bool,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -52,7 +120,47 @@ pub struct TaskConfig {
     allowed_hosts: Option<Vec<String>>,
 
     #[serde(alias = "envVariables")]
-    env_variables: Option<Vec<String>>,
+    env_variables: Option<EnvVariables>,
+
+    scratch: Option<String>,
+
+    #[serde(alias = "scratchInodes")]
+    scratch_inodes: Option<u64>,
+
+    #[serde(alias = "maxWriteBytes")]
+    max_write_bytes: Option<String>,
+
+    #[serde(alias = "maxFilesCreated")]
+    max_files_created: Option<u64>,
+
+    #[serde(alias = "auditFiles")]
+    audit_files: Option<bool>,
+
+    outputs: Option<bool>,
+
+    #[serde(alias = "maxOutputBytes")]
+    max_output_bytes: Option<String>,
+
+    #[serde(alias = "maxConcurrency")]
+    max_concurrency: Option<usize>,
+
+    #[serde(alias = "kvNamespaces")]
+    kv_namespaces: Option<Vec<String>>,
+
+    #[serde(alias = "kvMaxBytes")]
+    kv_max_bytes: Option<String>,
+
+    #[serde(alias = "sqlDatabases")]
+    sql_databases: Option<Vec<String>>,
+
+    #[serde(alias = "sqlMaxRows")]
+    sql_max_rows: Option<u64>,
+
+    #[serde(alias = "sqlTimeout")]
+    sql_timeout: Option<String>,
+
+    deterministic: Option<bool>,
+    seed: Option<u64>,
 }
 
 impl TaskConfig {
@@ -104,12 +212,50 @@ impl TaskConfig {
             .or_else(|| default_policy.and_then(|p| p.default_allowed_hosts.clone()))
             .unwrap_or_default();
 
-        let env_variables = self
+        let (env_variables, env_values) = self
             .env_variables
             .clone()
             .or_else(|| default_policy.and_then(|p| p.default_env_variables.clone()))
+            .map(EnvVariables::split)
             .unwrap_or_default();
 
+        let scratch =
+            (self.scratch.is_some() || self.scratch_inodes.is_some()).then(|| ScratchPolicy {
+                max_bytes: self
+                    .scratch
+                    .as_ref()
+                    .and_then(|s| Self::parse_ram_string(s)),
+                max_inodes: self.scratch_inodes,
+            });
+
+        let outputs =
+            (self.outputs.unwrap_or(false) || self.max_output_bytes.is_some()).then(|| {
+                OutputsPolicy {
+                    max_bytes: self
+                        .max_output_bytes
+                        .as_ref()
+                        .and_then(|s| Self::parse_ram_string(s)),
+                }
+            });
+
+        let kv = self.kv_namespaces.as_ref().map(|namespaces| KvPolicy {
+            namespaces: namespaces.clone(),
+            max_bytes: self
+                .kv_max_bytes
+                .as_ref()
+                .and_then(|s| Self::parse_ram_string(s)),
+        });
+
+        let sql = self.sql_databases.as_ref().map(|databases| SqlPolicy {
+            databases: databases.clone(),
+            max_rows: self.sql_max_rows,
+            timeout_ms: self
+                .sql_timeout
+                .as_ref()
+                .and_then(|s| humantime::parse_duration(s).ok())
+                .map(|d| d.as_millis() as u64),
+        });
+
         ExecutionPolicy::new()
             .name(self.name.clone())
             .compute(compute)
@@ -119,6 +265,21 @@ impl TaskConfig {
             .allowed_files(allowed_files)
             .allowed_hosts(allowed_hosts)
             .env_variables(env_variables)
+            .env_values(env_values)
+            .scratch(scratch)
+            .max_write_bytes(
+                self.max_write_bytes
+                    .as_ref()
+                    .and_then(|s| Self::parse_ram_string(s)),
+            )
+            .max_files_created(self.max_files_created)
+            .audit_files(self.audit_files)
+            .outputs(outputs)
+            .max_concurrency(self.max_concurrency)
+            .kv(kv)
+            .sql(sql)
+            .deterministic(self.deterministic.or(self.seed.map(|_| true)))
+            .seed(self.seed)
     }
 
     pub fn parse_ram_string(s: &str) -> Option<u64> {
@@ -150,7 +311,18 @@ impl TaskConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::database::Database;
+    use crate::config::kv::{KvError, KvStore};
     use crate::config::manifest::DefaultPolicy;
+    use crate::wasm::filesystem::sandbox::SandboxFs;
+    use crate::wasm::utilities::env_resolver::resolve_env;
+    use crate::wasm::utilities::path_validator::{FileAccessMode, validate_path};
+    use std::collections::HashMap;
+
+    fn policy_from(source: &str) -> ExecutionPolicy {
+        let config: TaskConfig = serde_json::from_str(source).unwrap();
+        config.to_execution_policy(&CapsuleToml::default())
+    }
 
     #[test]
     fn test_parse_ram_string() {
@@ -217,7 +389,8 @@ mod tests {
             max_retries: Some(3),
             allowed_files: Some(vec!["./data".to_string()]),
             allowed_hosts: Some(vec!["https://example.com".to_string()]),
-            env_variables: Some(vec!["FOO".to_string()]),
+            env_variables: Some(EnvVariables::Names(vec!["FOO".to_string()])),
+            ..Default::default()
         };
 
         let policy = config.to_execution_policy(&CapsuleToml::default());
@@ -229,6 +402,159 @@ mod tests {
         assert_eq!(policy.max_retries, 3);
     }
 
+    #[test]
+    fn test_to_execution_policy_fields() {
+        let cases = [
+            (
+                r#"{"scratch": "16MB", "scratchInodes": 100}"#,
+                "scratch",
+                serde_json::json!({"max_bytes": 16 * 1024 * 1024, "max_inodes": 100}),
+            ),
+            (
+                r#"{"scratchInodes": 5}"#,
+                "scratch",
+                serde_json::json!({"max_bytes": null, "max_inodes": 5}),
+            ),
+            ("{}", "scratch", serde_json::Value::Null),
+            (
+                r#"{"maxWriteBytes": "1MB"}"#,
+                "max_write_bytes",
+                serde_json::json!(1024 * 1024),
+            ),
+            (
+                r#"{"max_files_created": 50}"#,
+                "max_files_created",
+                serde_json::json!(50),
+            ),
+            ("{}", "max_write_bytes", serde_json::Value::Null),
+            (
+                r#"{"outputs": true}"#,
+                "outputs",
+                serde_json::json!({"max_bytes": null}),
+            ),
+            (
+                r#"{"maxOutputBytes": "2KB"}"#,
+                "outputs",
+                serde_json::json!({"max_bytes": 2048}),
+            ),
+            ("{}", "outputs", serde_json::Value::Null),
+            (
+                r#"{"deterministic": true}"#,
+                "deterministic",
+                serde_json::json!(true),
+            ),
+            (r#"{"seed": 7}"#, "deterministic", serde_json::json!(true)),
+            (r#"{"seed": 7}"#, "seed", serde_json::json!(7)),
+            ("{}", "deterministic", serde_json::json!(false)),
+        ];
+
+        for (source, field, expected) in cases {
+            let policy = serde_json::to_value(policy_from(source)).unwrap();
+
+            assert_eq!(policy[field], expected, "{} from {}", field, source);
+        }
+    }
+
+    #[test]
+    fn test_audit_files_enable_file_audit() {
+        let audited = |source| {
+            SandboxFs::for_policy(&policy_from(source))
+                .audit()
+                .is_some()
+        };
+
+        assert!(audited(r#"{"auditFiles": true}"#));
+        assert!(audited(r#"{"audit_files": true}"#));
+        assert!(!audited(r#"{"auditFiles": false}"#));
+        assert!(!audited(r#"{"maxWriteBytes": "1MB"}"#));
+    }
+
+    #[test]
+    fn test_env_map_resolves_against_host_env() {
+        let policy = policy_from(
+            r#"{"envVariables": {
+                "API_KEY": "$CAPSULE_TEST_HOST_KEY",
+                "MODE": "batch",
+                "REGION": "${CAPSULE_TEST_MISSING_REGION:-eu}"
+            }}"#,
+        );
+        let host_env = HashMap::from([("CAPSULE_TEST_HOST_KEY".to_string(), "secret".to_string())]);
+
+        let mut envs = resolve_env(&policy.env_variables, &policy.env_values, &host_env).unwrap();
+        envs.sort();
+
+        assert_eq!(
+            envs,
+            vec![
+                ("API_KEY".to_string(), "secret".to_string()),
+                ("MODE".to_string(), "batch".to_string()),
+                ("REGION".to_string(), "eu".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_concurrency_caps_subtask_slots() {
+        assert_eq!(
+            policy_from(r#"{"maxConcurrency": 3}"#).concurrency_limit(),
+            3
+        );
+        assert_eq!(
+            policy_from(r#"{"max_concurrency": 0}"#).concurrency_limit(),
+            1
+        );
+        assert_eq!(
+            policy_from("{}").concurrency_limit(),
+            crate::wasm::execution_policy::DEFAULT_MAX_CONCURRENCY
+        );
+    }
+
+    #[test]
+    fn test_kv_grant_enforces_quota() {
+        let kv = policy_from(r#"{"kvNamespaces": ["jobs"], "kvMaxBytes": "1KB"}"#)
+            .kv
+            .unwrap();
+        let store = KvStore::new(Database::new(None, "trace.db-wal").unwrap()).unwrap();
+
+        assert!(kv.allows("jobs"));
+        assert!(!kv.allows("cache"));
+
+        store
+            .set("jobs", "small", &[0; 512], None, kv.max_bytes)
+            .unwrap();
+        assert_eq!(
+            store.set("jobs", "large", &[0; 1024], None, kv.max_bytes),
+            Err(KvError::QuotaExceeded(1024))
+        );
+
+        assert!(policy_from(r#"{"kvMaxBytes": "1MB"}"#).kv.is_none());
+    }
+
+    #[test]
+    fn test_sql_grant_keeps_access_mode() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("data")).unwrap();
+        std::fs::write(root.path().join("data/app.db"), b"").unwrap();
+
+        let sql = policy_from(
+            r#"{"sqlDatabases": ["./data/app.db:ro"], "sqlMaxRows": 100, "sqlTimeout": "2s"}"#,
+        )
+        .sql
+        .unwrap();
+
+        assert_eq!(sql.max_rows, Some(100));
+        assert_eq!(sql.timeout_ms, Some(2000));
+
+        let parsed = validate_path(&sql.databases[0], root.path(), &[]).unwrap();
+        assert_eq!(
+            parsed.path,
+            root.path().join("data/app.db").canonicalize().unwrap()
+        );
+        assert_eq!(parsed.mode, FileAccessMode::ReadOnly);
+
+        assert!(policy_from(r#"{"sqlMaxRows": 100}"#).sql.is_none());
+    }
+
     #[test]
     fn test_to_execution_policy_compute_variants() {
         let low = TaskConfig {
@@ -279,8 +605,9 @@ mod tests {
                 default_max_retries: Some(5),
                 default_allowed_files: Some(vec!["./default".to_string()]),
                 default_allowed_hosts: Some(vec!["https://default.com".to_string()]),
-                default_env_variables: Some(vec!["FOO".to_string()]),
+                default_env_variables: Some(EnvVariables::Names(vec!["FOO".to_string()])),
             }),
+            ..Default::default()
         };
 
         let config = TaskConfig::default();
@@ -309,8 +636,9 @@ mod tests {
                 default_max_retries: Some(2),
                 default_allowed_files: Some(vec!["./default.txt".to_string()]),
                 default_allowed_hosts: Some(vec!["*".to_string()]),
-                default_env_variables: Some(vec!["FOO".to_string()]),
+                default_env_variables: Some(EnvVariables::Names(vec!["FOO".to_string()])),
             }),
+            ..Default::default()
         };
 
         let config = TaskConfig {
@@ -321,7 +649,8 @@ mod tests {
             max_retries: Some(10),
             allowed_files: Some(vec!["./custom".to_string()]),
             allowed_hosts: Some(vec!["https://custom.com".to_string()]),
-            env_variables: Some(vec!["BAR".to_string()]),
+            env_variables: Some(EnvVariables::Names(vec!["BAR".to_string()])),
+            ..Default::default()
         };
 
         let policy = config.to_execution_policy(&capsule_toml);
@@ -347,8 +676,9 @@ mod tests {
                 default_max_retries: Some(3),
                 default_allowed_files: Some(vec!["./default".to_string()]),
                 default_allowed_hosts: Some(vec!["*".to_string()]),
-                default_env_variables: Some(vec!["FOO".to_string()]),
+                default_env_variables: Some(EnvVariables::Names(vec!["FOO".to_string()])),
             }),
+            ..Default::default()
         };
 
         let config = TaskConfig {
@@ -360,6 +690,7 @@ mod tests {
             allowed_files: None,
             allowed_hosts: None,
             env_variables: None,
+            ..Default::default()
         };
 
         let policy = config.to_execution_policy(&capsule_toml);
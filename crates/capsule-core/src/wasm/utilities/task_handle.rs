@@ -0,0 +1,181 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Semaphore;
+use wasmtime_wasi::runtime::AbortOnDropJoinHandle;
+
+use crate::wasm::state::host_api::TaskError;
+
+pub type SubtaskResult = Result<String, TaskError>;
+
+#[derive(Debug, Clone, Default)]
+pub struct SubtaskTracker(Arc<Mutex<Option<String>>>);
+
+impl SubtaskTracker {
+    pub fn set(&self, task_id: &str) {
+        *self.0.lock().unwrap() = Some(task_id.to_string());
+    }
+
+    pub fn get(&self) -> Option<String> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+pub fn spawn_limited<F>(slots: Arc<Semaphore>, job: F) -> AbortOnDropJoinHandle<SubtaskResult>
+where
+    F: Future<Output = SubtaskResult> + Send + 'static,
+{
+    wasmtime_wasi::runtime::spawn(async move {
+        let _permit = slots
+            .acquire_owned()
+            .await
+            .map_err(|e| TaskError::InternalError(format!("Failed to schedule task: {}", e)))?;
+
+        job.await
+    })
+}
+
+pub async fn join_all(handles: Vec<AbortOnDropJoinHandle<SubtaskResult>>) -> Vec<SubtaskResult> {
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(handle.await);
+    }
+
+    results
+}
+
+pub struct TaskHandle {
+    job: Option<AbortOnDropJoinHandle<SubtaskResult>>,
+    result: Option<SubtaskResult>,
+    tracker: SubtaskTracker,
+}
+
+impl TaskHandle {
+    pub fn new(job: AbortOnDropJoinHandle<SubtaskResult>, tracker: SubtaskTracker) -> Self {
+        Self {
+            job: Some(job),
+            result: None,
+            tracker,
+        }
+    }
+
+    pub fn running_task_id(&self) -> Option<String> {
+        self.job.as_ref()?;
+        self.tracker.get()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.job.as_ref().is_some_and(|job| !job.is_finished())
+    }
+
+    async fn finish(&mut self) {
+        if let Some(job) = self.job.take() {
+            self.result = Some(job.await);
+        }
+    }
+
+    pub async fn poll(&mut self) -> Option<SubtaskResult> {
+        if self.job.as_ref().is_some_and(|job| job.is_finished()) {
+            self.finish().await;
+        }
+        self.result.clone()
+    }
+
+    pub async fn wait(&mut self) -> SubtaskResult {
+        self.finish().await;
+        self.result
+            .clone()
+            .unwrap_or_else(|| Err(TaskError::InternalError("Task has no result".to_string())))
+    }
+
+    pub fn cancel(&mut self) -> bool {
+        if !self.is_running() {
+            return false;
+        }
+
+        if let Some(job) = self.job.take() {
+            job.abort();
+        }
+        self.result = Some(Err(TaskError::InternalError(
+            "Task was cancelled".to_string(),
+        )));
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use wasmtime_wasi::runtime::{in_tokio, spawn};
+
+    #[test]
+    fn test_spawn_limited_caps_concurrency() {
+        in_tokio(async {
+            let slots = Arc::new(Semaphore::new(2));
+            let running = Arc::new(AtomicUsize::new(0));
+            let peak = Arc::new(AtomicUsize::new(0));
+
+            let handles = (0..6u64)
+                .map(|index| {
+                    let running = Arc::clone(&running);
+                    let peak = Arc::clone(&peak);
+
+                    spawn_limited(Arc::clone(&slots), async move {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+
+                        tokio::time::sleep(Duration::from_millis(30 - index * 5)).await;
+
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        Ok(index.to_string())
+                    })
+                })
+                .collect();
+
+            let results = join_all(handles).await;
+
+            assert_eq!(peak.load(Ordering::SeqCst), 2);
+            assert_eq!(
+                results.into_iter().map(Result::unwrap).collect::<Vec<_>>(),
+                vec!["0", "1", "2", "3", "4", "5"]
+            );
+        });
+    }
+
+    #[test]
+    fn test_cancel_running_task() {
+        in_tokio(async {
+            let tracker = SubtaskTracker::default();
+            tracker.set("child-1");
+
+            let mut handle = TaskHandle::new(spawn(std::future::pending()), tracker);
+
+            assert!(handle.poll().await.is_none());
+            assert_eq!(handle.running_task_id(), Some("child-1".to_string()));
+            assert!(handle.cancel());
+            assert!(!handle.cancel());
+            assert_eq!(handle.running_task_id(), None);
+            assert!(matches!(
+                handle.wait().await,
+                Err(TaskError::InternalError(msg)) if msg == "Task was cancelled"
+            ));
+        });
+    }
+
+    #[test]
+    fn test_finished_task_keeps_result() {
+        in_tokio(async {
+            let mut handle = TaskHandle::new(
+                spawn(async { Ok("{\"result\": 1}".to_string()) }),
+                SubtaskTracker::default(),
+            );
+
+            assert!(matches!(handle.wait().await, Ok(r) if r == "{\"result\": 1}"));
+            assert!(!handle.cancel());
+            assert!(matches!(handle.poll().await, Some(Ok(r)) if r == "{\"result\": 1}"));
+        });
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+pub const MAX_METRICS: usize = 256;
+pub const MAX_METRIC_NAME_BYTES: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    pub task_id: String,
+    pub task_name: String,
+    pub current: u64,
+    pub total: Option<u64>,
+    pub message: Option<String>,
+}
+
+impl ProgressEvent {
+    pub fn describe(&self) -> String {
+        let step = match self.total {
+            Some(total) => format!("{}/{}", self.current, total),
+            None => self.current.to_string(),
+        };
+
+        match &self.message {
+            Some(message) => format!("{} {}", step, message),
+            None => step,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TaskMetrics {
+    values: BTreeMap<String, f64>,
+}
+
+impl TaskMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn accepts(&self, name: &str, value: f64) -> bool {
+        value.is_finite()
+            && !name.is_empty()
+            && name.len() <= MAX_METRIC_NAME_BYTES
+            && (self.values.contains_key(name) || self.values.len() < MAX_METRICS)
+    }
+
+    pub fn set(&mut self, name: &str, value: f64) -> bool {
+        if !self.accepts(name, value) {
+            return false;
+        }
+        self.values.insert(name.to_string(), value);
+        true
+    }
+
+    pub fn add(&mut self, name: &str, delta: f64) -> bool {
+        let value = self.values.get(name).copied().unwrap_or_default() + delta;
+        self.set(name, value)
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, f64> {
+        self.values.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_describe() {
+        let mut event = ProgressEvent {
+            task_id: "t1".to_string(),
+            task_name: "main".to_string(),
+            current: 3,
+            total: Some(10),
+            message: Some("embedding".to_string()),
+        };
+        assert_eq!(event.describe(), "3/10 embedding");
+
+        event.total = None;
+        event.message = None;
+        assert_eq!(event.describe(), "3");
+    }
+
+    #[test]
+    fn test_task_metrics() {
+        let mut metrics = TaskMetrics::new();
+
+        assert!(metrics.add("items", 1.0));
+        assert!(metrics.add("items", 2.0));
+        assert!(metrics.set("tokens", 512.0));
+        assert!(!metrics.set("tokens", f64::NAN));
+        assert!(!metrics.add("tokens", f64::INFINITY));
+        assert!(!metrics.set("", 1.0));
+
+        assert_eq!(
+            metrics.snapshot(),
+            BTreeMap::from([("items".to_string(), 3.0), ("tokens".to_string(), 512.0)])
+        );
+    }
+
+    #[test]
+    fn test_task_metrics_limit() {
+        let mut metrics = TaskMetrics::new();
+
+        for i in 0..MAX_METRICS {
+            assert!(metrics.set(&format!("m{}", i), 1.0));
+        }
+        assert!(!metrics.set("one_more", 1.0));
+        assert!(metrics.add("m0", 1.0));
+        assert_eq!(metrics.snapshot().len(), MAX_METRICS);
+    }
+}
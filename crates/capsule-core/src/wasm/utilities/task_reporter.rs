@@ -6,6 +6,9 @@ use chrono::Utc;
 use colored::Colorize;
 use indicatif::{ProgressBar, ProgressStyle};
 
+use crate::wasm::utilities::guest_log::{GuestLogEntry, GuestLogLevel};
+use crate::wasm::utilities::task_progress::ProgressEvent;
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum LogLevel {
     Verbose,
@@ -134,6 +137,59 @@ impl TaskReporter {
         }
     }
 
+    pub fn guest_log(&mut self, task_name: &str, entry: &GuestLogEntry) {
+        if self.log_level != LogLevel::Verbose {
+            return;
+        }
+
+        let level = entry.level.to_string();
+        let level = match entry.level {
+            GuestLogLevel::Trace | GuestLogLevel::Debug => level.dimmed(),
+            GuestLogLevel::Info => level.cyan(),
+            GuestLogLevel::Warn => level.yellow(),
+            GuestLogLevel::Error => level.red(),
+        };
+
+        let fields = entry
+            .fields
+            .iter()
+            .map(|(key, value)| format!(" {}={}", key.dimmed(), value))
+            .collect::<String>();
+
+        self.finish_spinner();
+        eprintln!(
+            "{} {} [{}] {}{}",
+            Self::timestamp(),
+            level,
+            task_name.magenta(),
+            entry.message,
+            fields
+        );
+    }
+
+    pub fn task_progress(&mut self, event: &ProgressEvent) {
+        match self.log_level {
+            LogLevel::Silent => {}
+            LogLevel::Verbose => eprintln!(
+                "{} {} [{}] {}",
+                Self::timestamp(),
+                "PROGRESS".cyan(),
+                event.task_name.magenta(),
+                event.describe()
+            ),
+            LogLevel::Normal => {
+                let message = format!("[{}] {}", event.task_name, event.describe());
+                match &self.spinner {
+                    Some(spinner) => spinner.set_message(message),
+                    None => {
+                        self.spinner = Some(self.create_spinner(&message));
+                        self.is_active.store(true, Ordering::SeqCst);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn start_progress(&mut self, message: &str) {
         if self.log_level == LogLevel::Silent {
             return;
@@ -254,6 +310,32 @@ mod tests {
         assert!(!reporter.is_active.load(Ordering::SeqCst));
     }
 
+    #[test]
+    fn test_task_progress_updates_spinner() {
+        let mut reporter = TaskReporter::new(LogLevel::Normal);
+        let event = ProgressEvent {
+            task_id: "task_123".to_string(),
+            task_name: "test_task".to_string(),
+            current: 3,
+            total: Some(10),
+            message: None,
+        };
+
+        reporter.task_progress(&event);
+        assert!(reporter.is_active.load(Ordering::SeqCst));
+        assert_eq!(
+            reporter.spinner.as_ref().map(|s| s.message()),
+            Some("[test_task] 3/10".to_string())
+        );
+
+        reporter.task_completed("test_task");
+        assert!(reporter.spinner.is_none());
+
+        let mut silent = TaskReporter::new(LogLevel::Silent);
+        silent.task_progress(&event);
+        assert!(silent.spinner.is_none());
+    }
+
     #[test]
     fn test_finish_spinner_clears_active() {
         let mut reporter = TaskReporter::new(LogLevel::Normal);
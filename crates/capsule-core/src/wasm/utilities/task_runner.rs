@@ -0,0 +1,351 @@
+use serde_json::Value;
+use wasmtime::Store;
+use wasmtime::component::{Component, Linker};
+
+use crate::wasm::state::host_api::TaskError as SubtaskError;
+use crate::wasm::state::host_types::{ErrorCode, TaskFailure, TaskOutcome};
+use crate::wasm::state::{CapsuleAgentV2, CapsuleAgentV2Pre, State, host_types};
+use crate::wasm::utilities::task_config::{TaskError, TaskInvocation, TaskResult};
+use crate::wasm::utilities::task_handle::SubtaskResult;
+
+mod v1 {
+    wasmtime::component::bindgen!({
+        path: "../capsule-wit",
+        world: "capsule-agent",
+        async: true,
+        with: {
+            "wasi": wasmtime_wasi::bindings,
+            "capsule:host/api": crate::wasm::state::capsule::host::api,
+            "capsule:host/kv": crate::wasm::state::capsule::host::kv,
+            "capsule:host/log": crate::wasm::state::capsule::host::log,
+            "capsule:host/progress": crate::wasm::state::capsule::host::progress,
+            "capsule:host/sql": crate::wasm::state::capsule::host::sql,
+            "capsule:http/client": crate::wasm::state::capsule::http::client,
+        },
+    });
+}
+
+const TASK_RUNNER_V2: &str = "capsule:host/task-runner-v2";
+
+pub enum AgentInstance {
+    V1(v1::CapsuleAgent),
+    V2(CapsuleAgentV2),
+}
+
+impl AgentInstance {
+    pub async fn instantiate(
+        store: &mut Store<State>,
+        component: &Component,
+        linker: &Linker<State>,
+    ) -> wasmtime::Result<Self> {
+        let pre = linker.instantiate_pre(component)?;
+
+        if component.export_index(None, TASK_RUNNER_V2).is_some() {
+            Ok(Self::V2(
+                CapsuleAgentV2Pre::new(pre)?
+                    .instantiate_async(store)
+                    .await?,
+            ))
+        } else {
+            Ok(Self::V1(
+                v1::CapsuleAgentPre::new(pre)?
+                    .instantiate_async(store)
+                    .await?,
+            ))
+        }
+    }
+
+    pub async fn run(
+        &self,
+        store: &mut Store<State>,
+        invocation: &TaskInvocation,
+    ) -> wasmtime::Result<Result<Value, TaskError>> {
+        match self {
+            Self::V1(agent) => {
+                let args_json = serde_json::to_string(invocation)?;
+                let output = agent
+                    .capsule_host_task_runner()
+                    .call_run(store, &args_json)
+                    .await?;
+
+                Ok(v1_output(output))
+            }
+            Self::V2(agent) => {
+                let output = agent
+                    .capsule_host_task_runner_v2()
+                    .call_run(store, &wit_invocation(invocation))
+                    .await?;
+
+                Ok(v2_output(output))
+            }
+        }
+    }
+}
+
+pub fn failure(code: ErrorCode, message: impl Into<String>) -> TaskFailure {
+    TaskFailure {
+        code,
+        message: message.into(),
+        traceback: None,
+    }
+}
+
+fn json_value(json: String) -> Value {
+    serde_json::from_str(&json).unwrap_or(Value::String(json))
+}
+
+/// v1 compatibility only: v1 guests have no typed error channel, so a result
+/// shaped like `{"error_type": .., "message": ..}` is read as a task failure.
+/// v2 results go through `v2_output` and are never inspected this way.
+fn v1_output(output: Result<String, String>) -> Result<Value, TaskError> {
+    let json = output.map_err(|message| TaskError {
+        error_type: "task_error".to_string(),
+        message,
+        traceback: None,
+    })?;
+
+    let result_object = json_value(json);
+    let result = result_object
+        .get("result")
+        .cloned()
+        .unwrap_or(result_object);
+
+    match (result.get("error_type"), result.get("message")) {
+        (Some(error_type), Some(message)) => Err(TaskError {
+            error_type: error_type.as_str().unwrap_or_default().to_string(),
+            message: message.as_str().unwrap_or_default().to_string(),
+            traceback: result
+                .get("traceback")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        }),
+        _ => Ok(result),
+    }
+}
+
+fn v2_output(output: Result<String, TaskFailure>) -> Result<Value, TaskError> {
+    output.map(json_value).map_err(TaskError::from)
+}
+
+fn wit_invocation(invocation: &TaskInvocation) -> host_types::TaskInvocation {
+    host_types::TaskInvocation {
+        task_name: invocation.task_name.clone(),
+        args: invocation.args.iter().map(Value::to_string).collect(),
+        kwargs: invocation
+            .kwargs
+            .iter()
+            .map(|(key, value)| (key.clone(), value.to_string()))
+            .collect(),
+    }
+}
+
+impl TryFrom<host_types::TaskInvocation> for TaskInvocation {
+    type Error = TaskFailure;
+
+    fn try_from(invocation: host_types::TaskInvocation) -> Result<Self, Self::Error> {
+        let parse = |json: &str| {
+            serde_json::from_str::<Value>(json)
+                .map_err(|e| failure(ErrorCode::InvalidArgs, format!("Invalid argument: {}", e)))
+        };
+
+        Ok(Self {
+            task_name: invocation.task_name,
+            args: invocation
+                .args
+                .iter()
+                .map(|arg| parse(arg))
+                .collect::<Result<_, _>>()?,
+            kwargs: invocation
+                .kwargs
+                .into_iter()
+                .map(|(key, value)| parse(&value).map(|value| (key, value)))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+pub fn error_type(code: ErrorCode) -> &'static str {
+    match code {
+        ErrorCode::TaskError => "task_error",
+        ErrorCode::TaskNotFound => "task_not_found",
+        ErrorCode::InvalidArgs => "invalid_args",
+        ErrorCode::Timeout => "timeout",
+        ErrorCode::HostError => "host_error",
+    }
+}
+
+fn error_code(error_type: &str) -> ErrorCode {
+    match error_type {
+        "task_error" => ErrorCode::TaskError,
+        "task_not_found" => ErrorCode::TaskNotFound,
+        "invalid_args" => ErrorCode::InvalidArgs,
+        "timeout" => ErrorCode::Timeout,
+        _ => ErrorCode::HostError,
+    }
+}
+
+impl From<TaskFailure> for TaskError {
+    fn from(failure: TaskFailure) -> Self {
+        TaskError {
+            error_type: error_type(failure.code).to_string(),
+            message: failure.message,
+            traceback: failure.traceback,
+        }
+    }
+}
+
+impl From<TaskError> for TaskFailure {
+    fn from(error: TaskError) -> Self {
+        TaskFailure {
+            code: error_code(&error.error_type),
+            message: error.message,
+            traceback: error.traceback,
+        }
+    }
+}
+
+impl From<SubtaskError> for TaskFailure {
+    fn from(error: SubtaskError) -> Self {
+        match error {
+            SubtaskError::InvalidName(msg) => failure(ErrorCode::TaskNotFound, msg),
+            SubtaskError::InvalidArgs(msg) => failure(ErrorCode::InvalidArgs, msg),
+            SubtaskError::InvalidConfig(msg) | SubtaskError::InternalError(msg) => {
+                failure(ErrorCode::HostError, msg)
+            }
+        }
+    }
+}
+
+pub fn task_outcome(result: SubtaskResult) -> TaskOutcome {
+    let json = match result {
+        Ok(json) => json,
+        Err(e) => {
+            return TaskOutcome {
+                value: Err(e.into()),
+                execution_json: None,
+            };
+        }
+    };
+
+    let task_result = match serde_json::from_str::<TaskResult>(&json) {
+        Ok(task_result) => task_result,
+        Err(_) => {
+            return TaskOutcome {
+                value: Err(failure(ErrorCode::HostError, json)),
+                execution_json: None,
+            };
+        }
+    };
+
+    let value = match (task_result.success, task_result.error) {
+        (true, _) => Ok(task_result.result.unwrap_or(Value::Null).to_string()),
+        (false, Some(error)) => Err(error.into()),
+        (false, None) => Err(failure(ErrorCode::TaskError, "Task failed")),
+    };
+
+    TaskOutcome {
+        value,
+        execution_json: serde_json::to_string(&task_result.execution).ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn decoded(output: Result<&str, &str>) -> Result<Value, (String, String)> {
+        v1_output(output.map(str::to_string).map_err(str::to_string))
+            .map_err(|e| (e.error_type, e.message))
+    }
+
+    #[test]
+    fn test_v1_output() {
+        assert_eq!(decoded(Ok(r#"{"result": 42}"#)), Ok(json!(42)));
+        assert_eq!(decoded(Ok("plain")), Ok(json!("plain")));
+        assert_eq!(
+            decoded(Ok(r#"{"result": {"error_type": "x", "message": "y"}}"#)),
+            Err(("x".to_string(), "y".to_string()))
+        );
+        assert_eq!(
+            decoded(Ok(r#"{"error_type": "task_error", "message": "boom"}"#)),
+            Err(("task_error".to_string(), "boom".to_string()))
+        );
+        assert_eq!(
+            decoded(Ok(r#"{"result": {"message": "hello"}}"#)),
+            Ok(json!({"message": "hello"}))
+        );
+        assert_eq!(
+            decoded(Ok(r#"{"error": "No tasks"}"#)),
+            Ok(json!({"error": "No tasks"}))
+        );
+        assert_eq!(
+            decoded(Err("trap")),
+            Err(("task_error".to_string(), "trap".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_v2_output_is_not_sniffed() {
+        let error_shaped = r#"{"error_type": "task_error", "message": "boom"}"#;
+
+        assert_eq!(
+            v2_output(Ok(error_shaped.to_string())).map_err(|e| e.message),
+            Ok(json!({"error_type": "task_error", "message": "boom"}))
+        );
+        assert_eq!(
+            v2_output(Err(failure(ErrorCode::Timeout, "slow")))
+                .map_err(|e| (e.error_type, e.message)),
+            Err(("timeout".to_string(), "slow".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_invocation_round_trip() {
+        let mut invocation = TaskInvocation::new("main", vec![json!(1), json!({"a": [true]})]);
+        invocation
+            .kwargs
+            .insert("name".to_string(), json!("capsule"));
+
+        let wit = wit_invocation(&invocation);
+        assert_eq!(wit.args, vec!["1", r#"{"a":[true]}"#]);
+        assert_eq!(
+            wit.kwargs,
+            vec![("name".to_string(), r#""capsule""#.to_string())]
+        );
+
+        assert_eq!(
+            TaskInvocation::try_from(wit).map_err(|e| e.message),
+            Ok(invocation)
+        );
+
+        let invalid = host_types::TaskInvocation {
+            task_name: "main".to_string(),
+            args: vec!["{".to_string()],
+            kwargs: vec![],
+        };
+        assert_eq!(
+            TaskInvocation::try_from(invalid).map_err(|e| e.code),
+            Err(ErrorCode::InvalidArgs)
+        );
+    }
+
+    #[test]
+    fn test_task_outcome() {
+        let outcome = task_outcome(Err(SubtaskError::InvalidArgs("bad".to_string())));
+        assert!(matches!(
+            outcome.value,
+            Err(TaskFailure {
+                code: ErrorCode::InvalidArgs,
+                ..
+            })
+        ));
+        assert_eq!(outcome.execution_json, None);
+
+        let outcome = task_outcome(Ok("Task failed".to_string()));
+        assert_eq!(
+            outcome.value.map_err(|e| (e.code, e.message)),
+            Err((ErrorCode::HostError, "Task failed".to_string()))
+        );
+    }
+}
@@ -172,11 +172,21 @@ pub const WASI_DEPS: &[(&str, &str, &str)] = &[
         "proxy.wit",
         include_str!("../../../../capsule-wit/deps/http/proxy.wit"),
     ),
+    // versioned capsule host interfaces
+    (
+        "capsule-http",
+        "client.wit",
+        include_str!("../../../../capsule-wit/deps/capsule-http/client.wit"),
+    ),
 ];
 
 pub struct WitManager {}
 
 impl WitManager {
+    pub fn is_current(wit_dir: &Path) -> bool {
+        fs::read_to_string(wit_dir.join("capsule.wit")).is_ok_and(|wit| wit == CAPSULE_WIT)
+    }
+
     pub fn import_wit_deps(wit_dir: &Path) -> Result<(), io::Error> {
         fs::create_dir_all(wit_dir)?;
         fs::write(wit_dir.join("capsule.wit"), CAPSULE_WIT)?;
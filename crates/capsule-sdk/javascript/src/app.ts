@@ -15,7 +15,22 @@ export interface TaskConfig {
   maxRetries?: number;
   allowedFiles?: string[];
   allowedHosts?: string[];
-  envVariables?: string[];
+  envVariables?: string[] | Record<string, string>;
+  scratch?: string;
+  scratchInodes?: number;
+  maxWriteBytes?: string;
+  maxFilesCreated?: number;
+  auditFiles?: boolean;
+  outputs?: boolean;
+  maxOutputBytes?: string;
+  maxConcurrency?: number;
+  kvNamespaces?: string[];
+  kvMaxBytes?: string;
+  sqlDatabases?: string[];
+  sqlMaxRows?: number;
+  sqlTimeout?: string;
+  deterministic?: boolean;
+  seed?: number;
 }
 
 const TASKS: Map<string, TaskInfo<any>> = new Map();
@@ -58,10 +73,53 @@ interface TaskArgs {
   kwargs?: Record<string, any>;
 }
 
+interface TaskInvocation {
+  taskName: string;
+  args: string[];
+  kwargs: [string, string][];
+}
+
+type ErrorCode = "task-error" | "task-not-found" | "invalid-args" | "timeout" | "host-error";
+
+/**
+ * Typed failure returned by the capsule:host/task-runner-v2 interface.
+ */
+class TaskFailure {
+  code: ErrorCode;
+  message: string;
+  traceback?: string;
+
+  constructor(code: ErrorCode, message: string, traceback?: string) {
+    this.code = code;
+    this.message = message;
+    this.traceback = traceback;
+  }
+}
+
+/**
+ * Resolve a task by name, falling back to `main` or the first registered task.
+ */
+function findTask(taskName: string): ((...args: any[]) => any) | undefined {
+  let taskFunc = getTask(taskName);
+
+  if (!taskFunc && taskName !== "main") {
+    taskFunc = getTask("main");
+  }
+
+  if (!taskFunc && TASKS.size > 0) {
+    const firstTaskName = TASKS.keys().next().value;
+    if (firstTaskName) {
+      taskFunc = getTask(firstTaskName);
+    }
+  }
+
+  return taskFunc;
+}
+
 /**
  * Implementation of the capsule:host/task-runner interface.
  *
- * This class is instantiated by capsule-core when the component is loaded.
+ * Kept for hosts that only support the original JSON-string protocol.
  * The Rust host calls `run(argsJson)` to execute a task.
  */
 export class TaskRunner {
@@ -76,29 +134,13 @@ export class TaskRunner {
       const args = data.args ?? [];
       const kwargs = data.kwargs ?? {};
 
-      let taskFunc = getTask(taskName);
-
-      if (!taskFunc && taskName !== "main") {
-        taskFunc = getTask("main");
-      }
-
-      if (!taskFunc && TASKS.size > 0) {
-        const firstTaskName = TASKS.keys().next().value;
-        if (firstTaskName) {
-          taskFunc = getTask(firstTaskName);
-        }
-      }
+      const taskFunc = findTask(taskName);
 
       if (!taskFunc) {
         throw `No tasks or main() function found. Available tasks: ${getTaskNames().join(", ")}`;
       }
 
-      const result = taskFunc(...args, kwargs);
-
-      if (result instanceof Promise) {
-        const asyncResult = await result;
-        return JSON.stringify({ result: asyncResult ?? null });
-      }
+      const result = await taskFunc(...args, kwargs);
 
       return JSON.stringify({ result: result ?? null });
     } catch (e) {
@@ -110,7 +152,51 @@ export class TaskRunner {
   }
 }
 
+/**
+ * Implementation of the capsule:host/task-runner-v2 interface.
+ *
+ * This class is instantiated by capsule-core when the component is loaded.
+ * The Rust host calls `run(invocation)` with JSON-encoded arguments and gets back
+ * the JSON-encoded return value, or a typed failure thrown as the error payload.
+ */
+export class TaskRunnerV2 {
+  async run(invocation: TaskInvocation): Promise<string> {
+    let args: any[];
+    let kwargs: Record<string, any>;
+
+    try {
+      args = invocation.args.map((arg) => JSON.parse(arg));
+      kwargs = Object.fromEntries(
+        invocation.kwargs.map(([key, value]) => [key, JSON.parse(value)])
+      );
+    } catch (e) {
+      throw new TaskFailure("invalid-args", `Invalid argument: ${e instanceof Error ? e.message : String(e)}`);
+    }
+
+    const taskFunc = findTask(invocation.taskName);
+
+    if (!taskFunc) {
+      throw new TaskFailure(
+        "task-not-found",
+        `No tasks or main() function found. Available tasks: ${getTaskNames().join(", ")}`
+      );
+    }
+
+    try {
+      const result = await taskFunc(...args, kwargs);
+      return JSON.stringify(result ?? null);
+    } catch (e) {
+      throw new TaskFailure(
+        "task-error",
+        e instanceof Error ? e.message : String(e),
+        e instanceof Error ? e.stack : undefined
+      );
+    }
+  }
+}
+
 export const exports = new TaskRunner();
+export const exportsV2 = new TaskRunnerV2();
 
 /**
  * Implementation of wasi:http/incoming-handler@0.2.0.
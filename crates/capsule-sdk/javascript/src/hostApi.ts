@@ -40,7 +40,7 @@ export function isWasmMode(): boolean {
 }
 
 /**
- * Call the host's schedule_task function to create a new isolated task instance.
+ * Call the host's typed scheduler.schedule_task function to create a new isolated task instance.
  *
  * This is the bridge between JavaScript code and the Rust host runtime.
  */
@@ -51,12 +51,16 @@ export function callHost(
 ): string {
   if (checkWasm() && hostModule) {
     try {
-      const result = hostModule.scheduleTask(
-        name,
-        JSON.stringify(args),
+      const scheduler = (globalThis as any)["capsule:host/scheduler"];
+      const outcome = scheduler.scheduleTask(
+        {
+          taskName: name,
+          args: args.map((arg) => JSON.stringify(arg ?? null)),
+          kwargs: [],
+        },
         JSON.stringify(config)
       );
-      return result;
+      return JSON.stringify(outcomeResult(outcome));
     } catch (e) {
       const error = e instanceof Error ? e.message : String(e);
       return JSON.stringify({ error: `Host call failed: ${error}` });
@@ -65,3 +69,87 @@ export function callHost(
     return JSON.stringify({ result: `mock_result_for_${name}` });
   }
 }
+
+interface TaskFailure {
+  code: string;
+  message: string;
+  traceback?: string;
+}
+
+interface TaskOutcome {
+  value: { tag: "ok"; val: string } | { tag: "err"; val: TaskFailure };
+  executionJson?: string;
+}
+
+/**
+ * Convert a typed task outcome into the task result object returned to callers.
+ */
+function outcomeResult(outcome: TaskOutcome): Record<string, any> {
+  const execution = outcome.executionJson ? JSON.parse(outcome.executionJson) : null;
+
+  if (outcome.value.tag === "ok") {
+    return { success: true, result: JSON.parse(outcome.value.val), error: null, execution };
+  }
+
+  const failure = outcome.value.val;
+  return {
+    success: false,
+    result: null,
+    error: {
+      error_type: failure.code.replace(/-/g, "_"),
+      message: failure.message,
+      traceback: failure.traceback ?? null,
+    },
+    execution,
+  };
+}
+
+/**
+ * Call the host's schedule_tasks function to run several instances of a task concurrently.
+ *
+ * Returns one JSON result string per entry of `argsList`, in the same order.
+ */
+export function callHostMany(
+  name: string,
+  argsList: any[][],
+  config: Record<string, any>
+): string[] {
+  if (checkWasm() && hostModule) {
+    try {
+      const configJson = JSON.stringify(config);
+      const results = hostModule.scheduleTasks(
+        argsList.map((args) => ({
+          taskName: name,
+          argsJson: JSON.stringify(args),
+          configJson,
+        }))
+      );
+      return results.map((r: { tag: string; val: any }) =>
+        r.tag === "ok"
+          ? r.val
+          : JSON.stringify({ error: `Host call failed: ${r.val?.val ?? r.val}` })
+      );
+    } catch (e) {
+      const error = e instanceof Error ? e.message : String(e);
+      return argsList.map(() => JSON.stringify({ error: `Host call failed: ${error}` }));
+    }
+  } else {
+    return argsList.map(() => JSON.stringify({ result: `mock_result_for_${name}` }));
+  }
+}
+
+/**
+ * Call the host's spawn_task function to start a task without waiting for it.
+ *
+ * Returns the host task-handle resource, or `null` when not running in WASM mode.
+ */
+export function spawnHost(
+  name: string,
+  args: any[],
+  config: Record<string, any>
+): any {
+  if (checkWasm() && hostModule) {
+    return hostModule.spawnTask(name, JSON.stringify(args), JSON.stringify(config));
+  }
+  return null;
+}
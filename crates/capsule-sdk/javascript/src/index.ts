@@ -14,10 +14,14 @@
  * ```
  */
 
-export { task, type TaskOptions } from "./task.js";
-export { TaskRunner, exports, type TaskConfig } from "./app.js";
+export { task, TaskHandle, type TaskOptions, type TaskFunction } from "./task.js";
+export { TaskRunner, TaskRunnerV2, exports, exportsV2, type TaskConfig } from "./app.js";
 export { default as fs, promises as fsPromises } from "./polyfills/fs.js";
 export { isWasmMode } from "./hostApi.js";
+export * as kv from "./kv.js";
+export * as log from "./log.js";
+export * as progress from "./progress.js";
+export * as sql from "./sql.js";
 export { default as os } from "./polyfills/os.js";
 export { default as process } from "./polyfills/process.js";
 
@@ -0,0 +1,161 @@
+/**
+ * Capsule SDK - Key-Value Store
+ *
+ * A small key-value store shared between tasks. Entries are kept by the Rust host
+ * in namespaces, which a task can only use when they are listed in its
+ * `kvNamespaces` option. Values survive across runs, so parent tasks, sub-tasks
+ * and later runs can coordinate through them.
+ *
+ * When running locally, an in-memory store is used instead.
+ */
+
+type Value = string | Uint8Array;
+
+const local: Map<string, { value: Uint8Array; expiresAt: number | null }> = new Map();
+
+export class KvError extends Error {}
+
+function kvModule(): any {
+  const binding = (globalThis as any)["capsule:host/kv"];
+  return typeof binding !== "undefined" && typeof binding.get === "function" ? binding : null;
+}
+
+function toBytes(value: Value): Uint8Array {
+  return typeof value === "string" ? new TextEncoder().encode(value) : value;
+}
+
+function sameBytes(a: Uint8Array | null, b: Uint8Array | null): boolean {
+  if (a === null || b === null) {
+    return a === b;
+  }
+  return a.length === b.length && a.every((byte, i) => byte === b[i]);
+}
+
+function call<T>(fn: () => T): T {
+  try {
+    return fn();
+  } catch (e: any) {
+    const payload = e?.payload;
+    if (payload && typeof payload.tag === "string") {
+      throw new KvError(`${payload.tag}: ${payload.val}`);
+    }
+    throw e;
+  }
+}
+
+function localKey(namespace: string, key: string): string {
+  return JSON.stringify([namespace, key]);
+}
+
+function localGet(namespace: string, key: string): Uint8Array | null {
+  const entry = local.get(localKey(namespace, key));
+  if (!entry) {
+    return null;
+  }
+  if (entry.expiresAt !== null && entry.expiresAt <= Date.now()) {
+    local.delete(localKey(namespace, key));
+    return null;
+  }
+  return entry.value;
+}
+
+function localSet(namespace: string, key: string, value: Uint8Array, ttlMs?: number): void {
+  local.set(localKey(namespace, key), {
+    value,
+    expiresAt: ttlMs === undefined ? null : Date.now() + ttlMs,
+  });
+}
+
+/**
+ * Read a value. Returns `null` if the key does not exist or has expired.
+ */
+export function get(namespace: string, key: string): Uint8Array | null {
+  const host = kvModule();
+  if (!host) {
+    return localGet(namespace, key);
+  }
+  return call(() => host.get(namespace, key)) ?? null;
+}
+
+/**
+ * Read a value as UTF-8 text.
+ */
+export function getText(namespace: string, key: string): string | null {
+  const value = get(namespace, key);
+  return value === null ? null : new TextDecoder().decode(value);
+}
+
+/**
+ * Store a value, replacing any previous one. `ttlMs` sets an optional lifetime.
+ *
+ * Throws a `KvError` if the namespace is not granted or its size quota would be exceeded.
+ */
+export function set(namespace: string, key: string, value: Value, ttlMs?: number): void {
+  const host = kvModule();
+  if (!host) {
+    localSet(namespace, key, toBytes(value), ttlMs);
+    return;
+  }
+  call(() => host.set(namespace, key, toBytes(value), ttlMs === undefined ? undefined : BigInt(ttlMs)));
+}
+
+/**
+ * Remove a key. Returns `false` if it did not exist.
+ */
+export function remove(namespace: string, key: string): boolean {
+  const host = kvModule();
+  if (!host) {
+    const existed = localGet(namespace, key) !== null;
+    local.delete(localKey(namespace, key));
+    return existed;
+  }
+  return call(() => host.delete(namespace, key));
+}
+
+/**
+ * Return the keys of a namespace starting with `prefix`, in sorted order.
+ */
+export function listKeys(namespace: string, prefix: string = ""): string[] {
+  const host = kvModule();
+  if (!host) {
+    return [...local.keys()]
+      .map((k) => JSON.parse(k) as [string, string])
+      .filter(([ns, k]) => ns === namespace && k.startsWith(prefix) && localGet(ns, k) !== null)
+      .map(([, k]) => k)
+      .sort();
+  }
+  return call(() => host.listKeys(namespace, prefix));
+}
+
+/**
+ * Store `value` only if the current value equals `expected`.
+ * Pass `expected = null` to write only when the key does not exist yet.
+ *
+ * Returns `true` if the value was written.
+ */
+export function compareAndSwap(
+  namespace: string,
+  key: string,
+  expected: Value | null,
+  value: Value,
+  ttlMs?: number
+): boolean {
+  const expectedBytes = expected === null ? null : toBytes(expected);
+  const host = kvModule();
+  if (!host) {
+    if (!sameBytes(localGet(namespace, key), expectedBytes)) {
+      return false;
+    }
+    localSet(namespace, key, toBytes(value), ttlMs);
+    return true;
+  }
+  return call(() =>
+    host.compareAndSwap(
+      namespace,
+      key,
+      expectedBytes ?? undefined,
+      toBytes(value),
+      ttlMs === undefined ? undefined : BigInt(ttlMs)
+    )
+  );
+}
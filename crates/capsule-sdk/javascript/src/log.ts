@@ -0,0 +1,62 @@
+/**
+ * Capsule SDK - Structured Logging
+ *
+ * Sends log records with a level and key/value fields to the Rust host.
+ * The host stores them with the task in `.capsule/trace.db`, returns them in
+ * `execution.logs` of the task result and prints them with `--verbose`.
+ *
+ * When running locally, records are printed to stderr instead.
+ */
+
+export type LogLevel = "trace" | "debug" | "info" | "warn" | "error";
+
+export type LogFields = Record<string, unknown>;
+
+function logModule(): any {
+  const binding = (globalThis as any)["capsule:host/log"];
+  return typeof binding !== "undefined" && typeof binding.write === "function" ? binding : null;
+}
+
+function write(level: LogLevel, message: string, fields: LogFields = {}): void {
+  const pairs: [string, string][] = Object.entries(fields).map(([key, value]) => [
+    key,
+    typeof value === "string" ? value : JSON.stringify(value) ?? String(value),
+  ]);
+
+  const host = logModule();
+  if (!host) {
+    const rendered = pairs.map(([key, value]) => ` ${key}=${value}`).join("");
+    console.error(`${level.toUpperCase()} ${message}${rendered}`);
+    return;
+  }
+
+  host.write(level, String(message), pairs);
+}
+
+export function trace(message: string, fields?: LogFields): void {
+  write("trace", message, fields);
+}
+
+export function debug(message: string, fields?: LogFields): void {
+  write("debug", message, fields);
+}
+
+/**
+ * Log an info record.
+ *
+ * @example
+ * ```typescript
+ * log.info("fetched page", { url, status: 200 });
+ * ```
+ */
+export function info(message: string, fields?: LogFields): void {
+  write("info", message, fields);
+}
+
+export function warn(message: string, fields?: LogFields): void {
+  write("warn", message, fields);
+}
+
+export function error(message: string, fields?: LogFields): void {
+  write("error", message, fields);
+}
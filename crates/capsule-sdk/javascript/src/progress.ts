@@ -0,0 +1,60 @@
+/**
+ * Capsule SDK - Progress and Metrics
+ *
+ * Reports task progress and custom metrics to the Rust host.
+ * Progress updates the CLI spinner and is streamed to worker clients that set
+ * `stream_progress`. Metrics are returned in `execution.metrics` of the task
+ * result and stored with the task in `.capsule/trace.db`.
+ *
+ * When running locally, progress is printed to stderr and metrics are kept in memory.
+ */
+
+const localMetrics = new Map<string, number>();
+
+function progressModule(): any {
+  const binding = (globalThis as any)["capsule:host/progress"];
+  return typeof binding !== "undefined" && typeof binding.report === "function" ? binding : null;
+}
+
+/**
+ * Report how far the task has progressed.
+ *
+ * @example
+ * ```typescript
+ * docs.forEach((doc, i) => progress.report(i + 1, docs.length, "embedding"));
+ * ```
+ */
+export function report(current: number, total?: number, message?: string): void {
+  const host = progressModule();
+  if (!host) {
+    const step = total !== undefined ? `${current}/${total}` : `${current}`;
+    console.error(`PROGRESS ${step}${message ? ` ${message}` : ""}`);
+    return;
+  }
+
+  host.report(
+    BigInt(Math.trunc(current)),
+    total !== undefined ? BigInt(Math.trunc(total)) : undefined,
+    message,
+  );
+}
+
+export function setMetric(name: string, value: number): void {
+  const host = progressModule();
+  if (!host) {
+    if (Number.isFinite(value)) localMetrics.set(name, value);
+    return;
+  }
+
+  host.setMetric(name, value);
+}
+
+export function addMetric(name: string, delta: number = 1): void {
+  const host = progressModule();
+  if (!host) {
+    setMetric(name, (localMetrics.get(name) ?? 0) + delta);
+    return;
+  }
+
+  host.addMetric(name, delta);
+}
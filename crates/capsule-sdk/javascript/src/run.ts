@@ -11,12 +11,15 @@ import { existsSync, writeFileSync, unlinkSync } from 'fs';
 import { tmpdir } from 'os';
 import { join } from 'path';
 import { randomUUID } from 'crypto';
-import { HostRequest } from './task';
+import { Artifact, HostRequest } from './task';
 
 export interface RunnerOptions {
   file: string;
   args?: string[];
+  kwargs?: Record<string, unknown>;
   mounts?: string[];
+  outputsDir?: string;
+  stdin?: string | Uint8Array;
   cwd?: string;
   capsulePath?: string;
 }
@@ -24,7 +27,7 @@ export interface RunnerOptions {
 export interface RunnerResult {
   success: boolean;
   result: string | number | boolean | object | null;
-  error: { error_type: string; message: string } | null;
+  error: { error_type: string; message: string; traceback?: string | null } | null;
   execution: {
     task_name: string;
     duration_ms: number;
@@ -32,6 +35,10 @@ export interface RunnerResult {
     fuel_consumed: number;
     ram_used: number;
     host_requests: HostRequest[];
+    artifacts?: Artifact[];
+    stdout?: string;
+    stderr?: string;
+    output_truncated?: boolean;
   };
 }
 
@@ -96,7 +103,7 @@ function getWorker(capsulePath: string, cwd: string): ChildProcess {
 
       const request = pending.get(response.id);
 
-      if (!request) continue;
+      if (!request || 'event' in response || 'progress' in response) continue;
       pending.delete(response.id);
 
       if (pending.size === 0) workerStdout.get(key)?.unref?.();
@@ -152,10 +159,22 @@ function writeArgsFile(args: string[]): string {
   return path;
 }
 
+function writeKwargsFile(kwargs: Record<string, unknown>): string {
+  const path = join(tmpdir(), `capsule-kwargs-${randomUUID()}.json`);
+  writeFileSync(path, JSON.stringify(kwargs), 'utf-8');
+  return path;
+}
+
+function stdinPayload(stdin?: string | Uint8Array): { stdin?: string; stdin_base64?: string } {
+  if (stdin === undefined) return {};
+  if (typeof stdin === 'string') return { stdin };
+  return { stdin_base64: Buffer.from(stdin).toString('base64') };
+}
+
 // --- run() via persistent worker ---
 
 function runViaWorker(options: RunnerOptions): Promise<RunnerResult> {
-  const { file, args = [], mounts = [], cwd, capsulePath = 'capsule' } = options;
+  const { file, args = [], kwargs, mounts = [], outputsDir, stdin, cwd, capsulePath = 'capsule' } = options;
   const resolvedCwd = cwd || process.cwd();
   const key = workerKey(capsulePath, resolvedCwd);
   const id = randomUUID();
@@ -166,7 +185,15 @@ function runViaWorker(options: RunnerOptions): Promise<RunnerResult> {
 
     workerStdout.get(key)?.ref?.();
 
-    const request = JSON.stringify({ id, file, args, mounts }) + '\n';
+    const request = JSON.stringify({
+      id,
+      file,
+      args,
+      kwargs,
+      mounts,
+      outputs_dir: outputsDir,
+      ...stdinPayload(stdin),
+    }) + '\n';
     worker.stdin!.write(request, (err) => {
       if (err) {
         pending.delete(id);
@@ -180,7 +207,7 @@ function runViaWorker(options: RunnerOptions): Promise<RunnerResult> {
 // --- run() via subprocess (fallback) ---
 
 function runViaSubprocess(options: RunnerOptions): Promise<RunnerResult> {
-  const { file, args = [], mounts = [], cwd, capsulePath = 'capsule' } = options;
+  const { file, args = [], kwargs, mounts = [], outputsDir, stdin, cwd, capsulePath = 'capsule' } = options;
   const command = getCapsuleCommand(capsulePath);
 
   const resolvedFile = resolve(cwd || process.cwd(), file);
@@ -188,6 +215,7 @@ function runViaSubprocess(options: RunnerOptions): Promise<RunnerResult> {
   const isWasm = WASM_EXTENSIONS.has(ext);
   const subcommand = isWasm ? 'exec' : 'run';
   const mountFlags = mounts.flatMap(m => ['--mount', m]);
+  if (outputsDir) mountFlags.push('--outputs-dir', outputsDir);
 
   const serializedArgs = JSON.stringify(args);
   const useArgsFile = Buffer.byteLength(serializedArgs, 'utf-8') > ARGS_FILE_THRESHOLD;
@@ -202,7 +230,12 @@ function runViaSubprocess(options: RunnerOptions): Promise<RunnerResult> {
     argsFlags = args;
   }
 
-  const cmdArgs = [subcommand, resolvedFile, '--json', ...mountFlags, ...argsFlags];
+  const kwargsFilePath = kwargs && Object.keys(kwargs).length > 0 ? writeKwargsFile(kwargs) : null;
+  const kwargsFlags = kwargsFilePath ? ['--kwargs-file', kwargsFilePath] : [];
+
+  const stdinFlags = stdin !== undefined ? ['--stdin-file', '-'] : [];
+
+  const cmdArgs = [subcommand, resolvedFile, '--json', ...mountFlags, ...stdinFlags, ...kwargsFlags, ...argsFlags];
 
   let executable = command;
   let executionArgs = cmdArgs;
@@ -213,9 +246,11 @@ function runViaSubprocess(options: RunnerOptions): Promise<RunnerResult> {
   }
 
   return new Promise((resolve, reject) => {
-    execFile(executable, executionArgs, { cwd, encoding: 'utf-8' }, (error, stdout, stderr) => {
-      if (argsFilePath) {
-        try { unlinkSync(argsFilePath); } catch { }
+    const child = execFile(executable, executionArgs, { cwd, encoding: 'utf-8' }, (error, stdout, stderr) => {
+      for (const path of [argsFilePath, kwargsFilePath]) {
+        if (path) {
+          try { unlinkSync(path); } catch { }
+        }
       }
 
       if (error && !stdout) {
@@ -234,6 +269,8 @@ function runViaSubprocess(options: RunnerOptions): Promise<RunnerResult> {
         reject(new Error(`Failed to parse Capsule output: ${stdout}`));
       }
     });
+
+    child.stdin?.end(stdin ?? '');
   });
 }
 
@@ -0,0 +1,90 @@
+/**
+ * Capsule SDK - SQL Databases
+ *
+ * Runs parameterised queries against SQLite databases granted to the task with
+ * `sqlDatabases`. The Rust host opens the files, enforces read-only grants and
+ * stops queries that return too many rows or run too long.
+ *
+ * Only available inside Capsule tasks.
+ */
+
+export type SqlValue = null | number | bigint | string | Uint8Array;
+
+export interface QueryResult {
+  columns: string[];
+  rows: SqlValue[][];
+  truncated: boolean;
+}
+
+export class SqlError extends Error {}
+
+function sqlModule(): any {
+  const binding = (globalThis as any)["capsule:host/sql"];
+  if (typeof binding === "undefined" || typeof binding.query !== "function") {
+    throw new SqlError("SQL databases are only available inside Capsule tasks");
+  }
+  return binding;
+}
+
+function toWit(value: SqlValue | boolean): { tag: string; val?: unknown } {
+  if (value === null || value === undefined) return { tag: "null" };
+  if (typeof value === "boolean") return { tag: "integer", val: BigInt(value ? 1 : 0) };
+  if (typeof value === "bigint") return { tag: "integer", val: value };
+  if (typeof value === "number") {
+    return Number.isInteger(value) ? { tag: "integer", val: BigInt(value) } : { tag: "real", val: value };
+  }
+  if (typeof value === "string") return { tag: "text", val: value };
+  if (value instanceof Uint8Array) return { tag: "blob", val: value };
+  throw new TypeError(`Unsupported SQL parameter type: ${typeof value}`);
+}
+
+function fromWit(value: { tag: string; val?: any }): SqlValue {
+  if (value.tag === "null") return null;
+  if (value.tag === "integer") {
+    const n = value.val as bigint;
+    return n >= BigInt(Number.MIN_SAFE_INTEGER) && n <= BigInt(Number.MAX_SAFE_INTEGER) ? Number(n) : n;
+  }
+  return value.val;
+}
+
+function call<T>(fn: () => T): T {
+  try {
+    return fn();
+  } catch (e: any) {
+    const payload = e?.payload;
+    if (payload && typeof payload.tag === "string") {
+      throw new SqlError(`${payload.tag}: ${payload.val}`);
+    }
+    throw e;
+  }
+}
+
+/**
+ * A granted database, addressed by its path or alias in `sqlDatabases`.
+ *
+ * @example
+ * ```typescript
+ * const db = sql.connect("data/app.db");
+ * const { rows } = db.query("SELECT name FROM users WHERE age > ?", [30]);
+ * ```
+ */
+export class Connection {
+  constructor(readonly database: string) {}
+
+  query(statement: string, params: (SqlValue | boolean)[] = []): QueryResult {
+    const result = call(() => sqlModule().query(this.database, statement, params.map(toWit)));
+    return {
+      columns: result.columns,
+      rows: result.rows.map((row: any[]) => row.map(fromWit)),
+      truncated: result.truncated,
+    };
+  }
+
+  execute(statement: string, params: (SqlValue | boolean)[] = []): number {
+    return Number(call(() => sqlModule().execute(this.database, statement, params.map(toWit))));
+  }
+}
+
+export function connect(database: string): Connection {
+  return new Connection(database);
+}
@@ -6,13 +6,13 @@
  */
 
 import { registerTask, type TaskConfig } from "./app.js";
-import { isWasmMode, callHost } from "./hostApi.js";
+import { isWasmMode, callHost, callHostMany, spawnHost } from "./hostApi.js";
 
 export interface AllowedFile {
   /** Path to the directory, e.g., "./data" */
   path: string;
-  /** Access mode: "read-only" / "ro" or "read-write" / "rw" (default) */
-  mode?: "read-only" | "read-write" | "ro" | "rw";
+  /** Access mode: "read-only" / "ro", "read-write" / "rw" (default) or "copy-on-write" / "cow" */
+  mode?: "read-only" | "read-write" | "copy-on-write" | "ro" | "rw" | "cow";
 }
 
 export interface TaskOptions {
@@ -34,14 +34,44 @@ export interface TaskOptions {
   allowedFiles?: (string | AllowedFile)[];
   /** Allowed hosts for HTTP requests */
   allowedHosts?: string[];
-  /** Environment variables available from your .env file for the task */
-  envVariables?: string[];
+  /** Environment variables for the task: host variable names, or a map of values like { API_KEY: "$HOST_KEY" } */
+  envVariables?: string[] | Record<string, string>;
+  /** Size of the private /tmp scratch directory, e.g., "64MB" */
+  scratch?: string;
+  /** Maximum number of files and directories in the scratch directory */
+  scratchInodes?: number;
+  /** Total bytes the task may write to its mounts, e.g., "100MB" */
+  maxWriteBytes?: string;
+  /** Maximum number of files and directories the task may create in its mounts */
+  maxFilesCreated?: number;
+  /** Record the task's file operations in its result and in the trace database */
+  auditFiles?: boolean;
+  /** Give the task a writable /outputs directory collected as artifacts after it finishes */
+  outputs?: boolean;
+  /** Total size allowed for the files in /outputs, e.g., "50MB" */
+  maxOutputBytes?: string;
+  /** Maximum number of sub-tasks this task runs at once with `.map()` (default: 8) */
+  maxConcurrency?: number;
+  /** Key-value namespaces the task may read and write with `kv` - e.g., ["jobs"] */
+  kvNamespaces?: string[];
+  /** Total size allowed for keys and values in each granted namespace - e.g., "10MB" */
+  kvMaxBytes?: string;
+  /** SQLite files the task may open with `sql` - e.g., ["./data/app.db:ro"] */
+  sqlDatabases?: string[];
+  /** Maximum number of rows a query returns (default: 10000) */
+  sqlMaxRows?: number;
+  /** Maximum duration of a single query, e.g., "2s" (default: 5s) */
+  sqlTimeout?: string;
+  /** Give the task a virtual clock and seeded randomness so runs can be replayed */
+  deterministic?: boolean;
+  /** Seed for deterministic mode (implies deterministic) */
+  seed?: number;
 }
 
 interface TaskResult<T> {
   success: boolean;
   result: T;
-  error: { error_type: string; message: string } | null;
+  error: { error_type: string; message: string; traceback?: string | null } | null;
   execution: TaskExecution;
 }
 
@@ -52,6 +82,36 @@ interface TaskExecution {
     fuel_consumed: number;
     ram_used: number;
     host_requests: HostRequest[];
+    overlay_changes?: OverlayChange[];
+    file_access?: FileAccessSummary | null;
+    artifacts?: Artifact[];
+    stdout?: string;
+    stderr?: string;
+    output_truncated?: boolean;
+}
+
+export interface OverlayChange {
+  mount: string;
+  path: string;
+  kind: "added" | "modified" | "deleted";
+}
+
+export interface Artifact {
+  path: string;
+  size: number;
+  hash: string;
+  host_path?: string | null;
+}
+
+export interface FileAccessSummary {
+  files_opened: number;
+  files_created: number;
+  files_deleted: number;
+  files_renamed: number;
+  bytes_read: number;
+  bytes_written: number;
+  paths_read: string[];
+  paths_written: string[];
 }
 
 export interface HostRequest {
@@ -118,28 +178,95 @@ function normalizeAllowedFile(entry: string | AllowedFile): string {
     case "ro":
     case "read-only":
       return `${entry.path}:ro`;
+    case "cow":
+    case "copy-on-write":
+      return `${entry.path}:cow`;
     default:
       throw new Error(
         `Invalid allowed_files mode '${(entry as any).mode}' for path '${entry.path}'. ` +
-        `Use 'read-only' or 'read-write'.`
+        `Use 'read-only', 'read-write' or 'copy-on-write'.`
       );
   }
 }
 
+function parseResult<T>(resultJson: string): TaskResult<T> {
+  try {
+    return JSON.parse(resultJson) as TaskResult<T>;
+  } catch (e) {
+    if (e instanceof SyntaxError) {
+      return resultJson as unknown as TaskResult<T>;
+    }
+    throw e;
+  }
+}
+
+function hostError<T>(e: unknown): TaskResult<T> {
+  const payload = (e as any)?.payload;
+  const error = payload?.val ?? (e instanceof Error ? e.message : String(e));
+  return { error: `Host call failed: ${error}` } as unknown as TaskResult<T>;
+}
+
+/**
+ * Handle to a task started with `.spawn()`. The task runs concurrently with the caller
+ * until `wait()` returns its result or `cancel()` stops it.
+ */
+export class TaskHandle<TReturn> {
+  private result: TaskResult<TReturn> | undefined;
+
+  constructor(private handle: any, private local?: Promise<TaskResult<TReturn>>) {
+    local?.then((result) => {
+      this.result = result;
+    });
+  }
+
+  /** Return the task result if it has finished, or `undefined` while it is still running */
+  poll(): TaskResult<TReturn> | undefined {
+    if (!this.handle) return this.result;
+
+    const result = this.handle.poll();
+    if (result === undefined) return undefined;
+    return result.tag === "ok" ? parseResult<TReturn>(result.val) : hostError<TReturn>({ payload: result.val });
+  }
+
+  /** Wait for the task to finish and return its result */
+  async wait(): Promise<TaskResult<TReturn>> {
+    if (!this.handle) return this.local as Promise<TaskResult<TReturn>>;
+
+    try {
+      return parseResult<TReturn>(this.handle.wait());
+    } catch (e) {
+      return hostError<TReturn>(e);
+    }
+  }
+
+  /** Stop the task. Returns false if it had already finished */
+  cancel(): boolean {
+    if (!this.handle) return false;
+    return this.handle.cancel();
+  }
+}
+
+export type TaskFunction<TArgs extends unknown[], TOutput, TReturn> = ((...args: TArgs) => TOutput) & {
+  /** Run the task once per entry of `inputs`, concurrently, and return the results in order */
+  map(inputs: TArgs[]): Promise<TaskResult<TReturn>[]>;
+  /** Start the task without waiting for it */
+  spawn(...args: TArgs): TaskHandle<TReturn>;
+};
+
 export function task<TArgs extends unknown[], TReturn>(
   options: TaskOptions,
   fn: (...args: TArgs) => Promise<TReturn>
-): (...args: TArgs) => Promise<TaskResult<TReturn>>;
+): TaskFunction<TArgs, Promise<TaskResult<TReturn>>, TReturn>;
 
 export function task<TArgs extends unknown[], TReturn>(
   options: TaskOptions,
   fn: (...args: TArgs) => TReturn
-): (...args: TArgs) => TaskResult<TReturn>;
+): TaskFunction<TArgs, TaskResult<TReturn>, TReturn>;
 
 export function task<TArgs extends unknown[], TReturn>(
   options: TaskOptions,
   fn: (...args: TArgs) => TReturn | Promise<TReturn>
-): (...args: TArgs) => TaskResult<TReturn> | Promise<TaskResult<TReturn>> {
+): TaskFunction<TArgs, TaskResult<TReturn> | Promise<TaskResult<TReturn>>, TReturn> {
   const taskName = options.name;
   let compute = options.compute?.toString().toUpperCase() ?? "MEDIUM";
   let allowedHosts = options.allowedHosts ?? [];
@@ -153,6 +280,21 @@ export function task<TArgs extends unknown[], TReturn>(
     allowedFiles: options.allowedFiles?.map(normalizeAllowedFile),
     allowedHosts,
     envVariables: options.envVariables,
+    scratch: options.scratch,
+    scratchInodes: options.scratchInodes,
+    maxWriteBytes: options.maxWriteBytes,
+    maxFilesCreated: options.maxFilesCreated,
+    auditFiles: options.auditFiles,
+    outputs: options.outputs,
+    maxOutputBytes: options.maxOutputBytes,
+    maxConcurrency: options.maxConcurrency,
+    kvNamespaces: options.kvNamespaces,
+    kvMaxBytes: options.kvMaxBytes,
+    sqlDatabases: options.sqlDatabases,
+    sqlMaxRows: options.sqlMaxRows,
+    sqlTimeout: options.sqlTimeout,
+    deterministic: options.deterministic,
+    seed: options.seed,
   };
 
   const wrapper = (...args: TArgs): TaskResult<TReturn> | Promise<TaskResult<TReturn>> => {
@@ -202,7 +344,23 @@ export function task<TArgs extends unknown[], TReturn>(
     }
   };
 
+  const map = async (inputs: TArgs[]): Promise<TaskResult<TReturn>[]> => {
+    if (!isWasmMode()) {
+      return Promise.all(inputs.map((args) => wrapper(...args)));
+    }
+
+    return callHostMany(taskName, inputs, taskConfig).map((resultJson) => parseResult<TReturn>(resultJson));
+  };
+
+  const spawn = (...args: TArgs): TaskHandle<TReturn> => {
+    if (!isWasmMode()) {
+      return new TaskHandle<TReturn>(null, Promise.resolve(wrapper(...args)));
+    }
+
+    return new TaskHandle<TReturn>(spawnHost(taskName, args, taskConfig));
+  };
+
   registerTask(taskName, fn, taskConfig);
 
-  return wrapper;
+  return Object.assign(wrapper, { map, spawn });
 }
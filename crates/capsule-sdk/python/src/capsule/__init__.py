@@ -1,6 +1,11 @@
 from .decorator import task
+from .host_api import TaskHandle
 from . import app
 from . import http
+from . import kv
+from . import log
+from . import progress
+from . import sql
 from .app import TaskRunner
 from .run import run, RunnerOptions, RunnerResult, ExecutionInfo, ErrorInfo
 from .worker import run_with_worker, close_all
@@ -1,13 +1,15 @@
 """
 Capsule SDK Export Module for task-runner interface
 
-This module implements the `capsule:host/task-runner` export interface
-that the Wasm component provide.
+This module implements the `capsule:host/task-runner-v2` export interface
+that the Wasm component provide, and the original `capsule:host/task-runner`
+interface for older hosts.
 """
 
 import asyncio
 import inspect
 import json
+import traceback
 
 
 class _WasmEventLoop(asyncio.SelectorEventLoop):
@@ -71,11 +73,38 @@ def get_task_config(name: str):
         return task_info["config"]
     return {}
 
+def _find_task(task_name: str):
+    """Resolve a task by name, falling back to `main` or the first registered task."""
+    task_func = get_task(task_name)
+
+    if task_func is None and task_name != "main":
+        task_func = get_task("main")
+
+    if task_func is None and _main_module is not None:
+        if hasattr(_main_module, 'main') and callable(_main_module.main):
+            task_func = _main_module.main
+
+    if task_func is None and _TASKS:
+        first_task_name = next(iter(_TASKS.keys()))
+        task_func = get_task(first_task_name)
+
+    return task_func
+
+def _call_task(task_func, args: list, kwargs: dict):
+    """Call a task function, driving it to completion if it is a coroutine."""
+    if inspect.iscoroutinefunction(task_func):
+        loop = _WasmEventLoop()
+        try:
+            return loop.run_until_complete(task_func(*args, **kwargs))
+        finally:
+            loop.close()
+    return task_func(*args, **kwargs)
+
 class TaskRunner:
     """
     Implementation of the capsule:host/task-runner interface.
 
-    This class is instantiated by capsule-core when the component is loaded.
+    Kept for hosts that only support the original JSON-string protocol.
     The Rust host calls `run(args_json)` to execute a task.
     """
     def run(self, args_json: str) -> str:
@@ -99,32 +128,14 @@ class TaskRunner:
             args = data.get("args", [])
             kwargs = data.get("kwargs", {})
 
-            task_func = get_task(task_name)
-
-            if task_func is None and task_name != "main":
-                task_func = get_task("main")
-
-            if task_func is None and _main_module is not None:
-                if hasattr(_main_module, 'main') and callable(_main_module.main):
-                    task_func = _main_module.main
-
-            if task_func is None and _TASKS:
-                first_task_name = next(iter(_TASKS.keys()))
-                task_func = get_task(first_task_name)
+            task_func = _find_task(task_name)
 
             if task_func is None:
                 return json.dumps({
                     "error": f"No tasks or main() function found. Available tasks: {list(_TASKS.keys())}"
                 })
 
-            if inspect.iscoroutinefunction(task_func):
-                loop = _WasmEventLoop()
-                try:
-                    result = loop.run_until_complete(task_func(*args, **kwargs))
-                finally:
-                    loop.close()
-            else:
-                result = task_func(*args, **kwargs)
+            result = _call_task(task_func, args, kwargs)
 
             return json.dumps({"result": result})
 
@@ -135,4 +146,43 @@ class TaskRunner:
             })
 
 
+class TaskRunnerV2:
+    """
+    Implementation of the capsule:host/task-runner-v2 interface.
+
+    This class is instantiated by capsule-core when the component is loaded.
+    The Rust host calls `run(invocation)` with a typed task invocation where
+    each argument is JSON-encoded, and gets back the JSON-encoded return value
+    or a typed task failure.
+    """
+    def run(self, invocation) -> str:
+        from wit_world.imports.types import ErrorCode, TaskFailure
+        from wit_world.types import Err
+
+        try:
+            args = [json.loads(arg) for arg in invocation.args]
+            kwargs = {key: json.loads(value) for key, value in invocation.kwargs}
+        except ValueError as e:
+            raise Err(TaskFailure(ErrorCode.INVALID_ARGS, f"Invalid argument: {e}", None))
+
+        task_func = _find_task(invocation.task_name)
+
+        if task_func is None:
+            raise Err(TaskFailure(
+                ErrorCode.TASK_NOT_FOUND,
+                f"No tasks or main() function found. Available tasks: {list(_TASKS.keys())}",
+                None,
+            ))
+
+        try:
+            result = _call_task(task_func, args, kwargs)
+        except Exception as e:
+            raise Err(TaskFailure(ErrorCode.TASK_ERROR, str(e), traceback.format_exc()))
+
+        try:
+            return json.dumps(result)
+        except (TypeError, ValueError) as e:
+            raise Err(TaskFailure(ErrorCode.TASK_ERROR, f"Task result is not JSON serializable: {e}", None))
+
+
 exports = TaskRunner()
@@ -6,7 +6,7 @@ This module export a decorator to mark a function as a Capsule task.
 
 import json
 import functools
-from .host_api import call_host, is_wasm_mode
+from .host_api import TaskHandle, call_host, call_host_many, is_wasm_mode, spawn_host
 from . import app
 
 
@@ -19,13 +19,15 @@ def _normalize_allowed_file(entry):
         return path
     if mode in ("ro", "read-only"):
         return f"{path}:ro"
+    if mode in ("cow", "copy-on-write"):
+        return f"{path}:cow"
     raise ValueError(
         f"Invalid allowed_files mode '{mode}' for path '{path}'. "
-        f"Use 'read-only' or 'read-write'."
+        f"Use 'read-only', 'read-write' or 'copy-on-write'."
     )
 
 
-def task(name=None, compute="MEDIUM", ram=None, timeout=None, max_retries=None, allowed_files=None, allowed_hosts=None, env_variables=None):
+def task(name=None, compute="MEDIUM", ram=None, timeout=None, max_retries=None, allowed_files=None, allowed_hosts=None, env_variables=None, scratch=None, scratch_inodes=None, max_write_bytes=None, max_files_created=None, audit_files=None, outputs=None, max_output_bytes=None, max_concurrency=None, kv_namespaces=None, kv_max_bytes=None, sql_databases=None, sql_max_rows=None, sql_timeout=None, deterministic=None, seed=None):
     """
     Decorator to mark a function as a Capsule task.
 
@@ -37,7 +39,22 @@ def task(name=None, compute="MEDIUM", ram=None, timeout=None, max_retries=None,
         max_retries: Maximum number of retries (default: 1)
         allowed_files: List of files/folders accessible in sandbox - e.g., ["./data"]
         allowed_hosts: List of allowed hosts for HTTP requests - e.g., ["https://api.openai.com"]
-        env_variables: List of environment variables accessible in sandbox - e.g., ["ANTHROPIC_API_KEY"]
+        env_variables: Environment variables accessible in sandbox - e.g., ["ANTHROPIC_API_KEY"], or a dict of values like {"API_KEY": "$HOST_KEY", "REGION": "${AWS_REGION:-us-east-1}"}
+        scratch: Size of the private /tmp scratch directory - e.g., "64MB"
+        scratch_inodes: Maximum number of files and directories in the scratch directory
+        max_write_bytes: Total bytes the task may write to its mounts - e.g., "100MB"
+        max_files_created: Maximum number of files and directories the task may create in its mounts
+        audit_files: Record the task's file operations in its result and in the trace database
+        outputs: Give the task a writable /outputs directory collected as artifacts after it finishes
+        max_output_bytes: Total size allowed for the files in /outputs - e.g., "50MB"
+        max_concurrency: Maximum number of sub-tasks this task runs at once with `.map()` (default: 8)
+        kv_namespaces: Key-value namespaces the task may read and write with `capsule.kv` - e.g., ["jobs"]
+        kv_max_bytes: Total size allowed for keys and values in each granted namespace - e.g., "10MB"
+        sql_databases: SQLite files the task may open with `capsule.sql` - e.g., ["./data/app.db:ro"]
+        sql_max_rows: Maximum number of rows a query returns (default: 10000)
+        sql_timeout: Maximum duration of a single query - e.g., "2s" (default: 5s)
+        deterministic: Give the task a virtual clock and seeded randomness so runs can be replayed
+        seed: Seed for deterministic mode (implies deterministic=True)
 
     In WASM mode:
     - The function is registered in the task registry with its config
@@ -67,6 +84,36 @@ def task(name=None, compute="MEDIUM", ram=None, timeout=None, max_retries=None,
             task_config["allowed_hosts"] = allowed_hosts
         if env_variables is not None:
             task_config["env_variables"] = env_variables
+        if scratch is not None:
+            task_config["scratch"] = scratch
+        if scratch_inodes is not None:
+            task_config["scratch_inodes"] = scratch_inodes
+        if max_write_bytes is not None:
+            task_config["max_write_bytes"] = max_write_bytes
+        if max_files_created is not None:
+            task_config["max_files_created"] = max_files_created
+        if audit_files is not None:
+            task_config["audit_files"] = audit_files
+        if outputs is not None:
+            task_config["outputs"] = outputs
+        if max_output_bytes is not None:
+            task_config["max_output_bytes"] = max_output_bytes
+        if max_concurrency is not None:
+            task_config["max_concurrency"] = max_concurrency
+        if kv_namespaces is not None:
+            task_config["kv_namespaces"] = kv_namespaces
+        if kv_max_bytes is not None:
+            task_config["kv_max_bytes"] = kv_max_bytes
+        if sql_databases is not None:
+            task_config["sql_databases"] = sql_databases
+        if sql_max_rows is not None:
+            task_config["sql_max_rows"] = sql_max_rows
+        if sql_timeout is not None:
+            task_config["sql_timeout"] = sql_timeout
+        if deterministic is not None:
+            task_config["deterministic"] = deterministic
+        if seed is not None:
+            task_config["seed"] = seed
 
         @functools.wraps(func)
         def wrapper(*args, **kwargs):
@@ -74,7 +121,7 @@ def task(name=None, compute="MEDIUM", ram=None, timeout=None, max_retries=None,
                 return func(*args, **kwargs)
 
             args_data = list(args)
-            result_json = call_host(task_name, args_data, task_config)
+            result_json = call_host(task_name, args_data, task_config, kwargs)
 
             try:
                 result = json.loads(result_json)
@@ -82,6 +129,29 @@ def task(name=None, compute="MEDIUM", ram=None, timeout=None, max_retries=None,
             except json.JSONDecodeError:
                 return result_json
 
+        def map_(inputs):
+            args_list = [list(item) if isinstance(item, tuple) else [item] for item in inputs]
+
+            if not is_wasm_mode():
+                return [func(*args_data) for args_data in args_list]
+
+            results = []
+            for result_json in call_host_many(task_name, args_list, task_config):
+                try:
+                    results.append(json.loads(result_json))
+                except json.JSONDecodeError:
+                    results.append(result_json)
+            return results
+
+        def spawn(*args):
+            if not is_wasm_mode():
+                return TaskHandle(result=func(*args))
+
+            return spawn_host(task_name, list(args), task_config)
+
+        wrapper.map = map_
+        wrapper.spawn = spawn
+
         app.register_task(task_name, func, task_config)
 
         return wrapper
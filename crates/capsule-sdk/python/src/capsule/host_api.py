@@ -28,7 +28,7 @@ def _check_wasm():
 def is_wasm_mode():
     return _check_wasm()
 
-def call_host(name: str, args: list, config: dict) -> str:
+def call_host(name: str, args: list, config: dict, kwargs: dict = None) -> str:
     """
     Call the host's schedule_task function to create a new isolated task instance.
 
@@ -42,21 +42,147 @@ def call_host(name: str, args: list, config: dict) -> str:
             - ram: e.g., "512MB", "2GB"
             - timeout: e.g., "30s", "5m"
             - max_retries: int
+        kwargs: Keyword arguments to pass to the task
 
     Returns:
         JSON string with the task result
 
     In WASM mode:
-        Calls the Rust host's schedule_task function via WIT bindings
+        Calls the Rust host's typed scheduler.schedule_task function via WIT bindings
 
     In local mode:
         Returns mocked result
     """
     if _check_wasm() and _host is not None:
         try:
-            result = _host.schedule_task(name, json.dumps(args), json.dumps(config))
-            return result
+            from wit_world.imports import scheduler, types
+
+            invocation = types.TaskInvocation(
+                name,
+                [json.dumps(arg) for arg in args],
+                [(key, json.dumps(value)) for key, value in (kwargs or {}).items()],
+            )
+            outcome = scheduler.schedule_task(invocation, json.dumps(config))
+            return json.dumps(_outcome_result(outcome))
         except Exception as e:
             return json.dumps({"error": f"Host call failed: {str(e)}"})
     else:
         return json.dumps({"result": f"mock_result_for_{name}"})
+
+
+def _outcome_result(outcome) -> dict:
+    """Convert a typed task outcome into the task result dict returned to callers."""
+    from wit_world.types import Ok
+
+    result = {
+        "success": isinstance(outcome.value, Ok),
+        "result": None,
+        "error": None,
+        "execution": json.loads(outcome.execution_json) if outcome.execution_json else None,
+    }
+
+    if isinstance(outcome.value, Ok):
+        result["result"] = json.loads(outcome.value.value)
+    else:
+        failure = outcome.value.value
+        result["error"] = {
+            "error_type": failure.code.name.lower(),
+            "message": failure.message,
+            "traceback": failure.traceback,
+        }
+
+    return result
+
+
+def call_host_many(name: str, args_list: list, config: dict) -> list:
+    """
+    Call the host's schedule_tasks function to run several instances of a task concurrently.
+
+    The host runs at most `max_concurrency` instances at once (set in the calling
+    task's config) and returns one JSON result string per entry of `args_list`,
+    in the same order.
+
+    In local mode:
+        Returns mocked results
+    """
+    if _check_wasm() and _host is not None:
+        try:
+            from wit_world.types import Ok
+
+            requests = [
+                _host.TaskRequest(name, json.dumps(args), json.dumps(config))
+                for args in args_list
+            ]
+            results = _host.schedule_tasks(requests)
+        except Exception as e:
+            return [json.dumps({"error": f"Host call failed: {str(e)}"}) for _ in args_list]
+
+        return [
+            r.value if isinstance(r, Ok) else json.dumps({"error": f"Host call failed: {r.value}"})
+            for r in results
+        ]
+    else:
+        return [json.dumps({"result": f"mock_result_for_{name}"}) for _ in args_list]
+
+
+def _parse_result(result_json):
+    try:
+        return json.loads(result_json)
+    except (json.JSONDecodeError, TypeError):
+        return result_json
+
+
+class TaskHandle:
+    """
+    Handle to a task started with `.spawn()`.
+
+    The task runs concurrently with the caller until `wait()` returns its result
+    or `cancel()` stops it. Cancelled tasks are marked as interrupted.
+    """
+
+    def __init__(self, handle=None, result=None):
+        self._handle = handle
+        self._result = result
+
+    def poll(self):
+        """Return the task result if it has finished, or None while it is still running."""
+        if self._handle is None:
+            return self._result
+
+        from wit_world.types import Ok
+
+        result = self._handle.poll()
+        if result is None:
+            return None
+        if isinstance(result, Ok):
+            return _parse_result(result.value)
+        return {"error": f"Host call failed: {result.value}"}
+
+    def wait(self):
+        """Block until the task finishes and return its result."""
+        if self._handle is None:
+            return self._result
+
+        try:
+            return _parse_result(self._handle.wait())
+        except Exception as e:
+            return {"error": f"Host call failed: {str(e)}"}
+
+    def cancel(self) -> bool:
+        """Stop the task. Returns False if it had already finished."""
+        if self._handle is None:
+            return False
+        return self._handle.cancel()
+
+
+def spawn_host(name: str, args: list, config: dict) -> TaskHandle:
+    """
+    Call the host's spawn_task function to start a task without waiting for it.
+
+    In local mode:
+        Returns a handle holding a mocked result
+    """
+    if _check_wasm() and _host is not None:
+        return TaskHandle(_host.spawn_task(name, json.dumps(args), json.dumps(config)))
+    else:
+        return TaskHandle(result={"result": f"mock_result_for_{name}"})
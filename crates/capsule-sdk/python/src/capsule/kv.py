@@ -0,0 +1,139 @@
+"""
+Capsule SDK - Key-Value Store
+
+This module provides a small key-value store shared between tasks.
+Entries are kept by the Rust host in namespaces, which a task can only use
+when they are listed in its `kv_namespaces` config. Values survive across runs,
+so parent tasks, sub-tasks and later runs can coordinate through them.
+
+When running locally, an in-memory store is used instead.
+"""
+
+import time
+from typing import List, Optional, Union
+
+_local = {}
+
+
+class KvError(RuntimeError):
+    """Raised when the host rejects a key-value operation."""
+
+
+def _get_kv():
+    """Get the WIT kv module."""
+    try:
+        from wit_world.imports import kv as kv_module
+        return kv_module
+    except ImportError:
+        return None
+
+
+def _to_bytes(value: Union[str, bytes]) -> bytes:
+    return value.encode("utf-8") if isinstance(value, str) else bytes(value)
+
+
+def _call(func, *args):
+    try:
+        return func(*args)
+    except Exception as e:
+        error = getattr(e, "value", e)
+        raise KvError(f"{type(error).__name__}: {getattr(error, 'value', error)}") from None
+
+
+def _local_get(namespace: str, key: str) -> Optional[bytes]:
+    entry = _local.get((namespace, key))
+    if entry is None:
+        return None
+    value, expires_at = entry
+    if expires_at is not None and expires_at <= time.monotonic():
+        del _local[(namespace, key)]
+        return None
+    return value
+
+
+def _local_set(namespace: str, key: str, value: bytes, ttl_ms: Optional[int]):
+    expires_at = time.monotonic() + ttl_ms / 1000 if ttl_ms is not None else None
+    _local[(namespace, key)] = (value, expires_at)
+
+
+def get(namespace: str, key: str) -> Optional[bytes]:
+    """
+    Read a value.
+
+    Returns:
+        The stored bytes, or None if the key does not exist or has expired
+    """
+    host = _get_kv()
+    if host is None:
+        return _local_get(namespace, key)
+    return _call(host.get, namespace, key)
+
+
+def set(namespace: str, key: str, value: Union[str, bytes], ttl_ms: Optional[int] = None):
+    """
+    Store a value, replacing any previous one.
+
+    Args:
+        namespace: Granted namespace to write to
+        key: Entry key
+        value: Bytes or text (stored as UTF-8)
+        ttl_ms: Optional lifetime in milliseconds after which the entry expires
+
+    Raises:
+        KvError: If the namespace is not granted or its size quota would be exceeded
+    """
+    host = _get_kv()
+    if host is None:
+        _local_set(namespace, key, _to_bytes(value), ttl_ms)
+        return
+    _call(host.set, namespace, key, _to_bytes(value), ttl_ms)
+
+
+def delete(namespace: str, key: str) -> bool:
+    """Remove a key. Returns False if it did not exist."""
+    host = _get_kv()
+    if host is None:
+        existed = _local_get(namespace, key) is not None
+        _local.pop((namespace, key), None)
+        return existed
+    return _call(host.delete, namespace, key)
+
+
+def list_keys(namespace: str, prefix: str = "") -> List[str]:
+    """Return the keys of a namespace starting with `prefix`, in sorted order."""
+    host = _get_kv()
+    if host is None:
+        return sorted(
+            k for (ns, k) in list(_local)
+            if ns == namespace and k.startswith(prefix) and _local_get(ns, k) is not None
+        )
+    return _call(host.list_keys, namespace, prefix)
+
+
+def compare_and_swap(
+    namespace: str,
+    key: str,
+    expected: Optional[Union[str, bytes]],
+    value: Union[str, bytes],
+    ttl_ms: Optional[int] = None,
+) -> bool:
+    """
+    Store `value` only if the current value equals `expected`.
+
+    Pass `expected=None` to write only when the key does not exist yet.
+
+    Returns:
+        True if the value was written, False if the current value did not match
+
+    Example:
+        if kv.compare_and_swap("locks", "daily-report", None, task_id, ttl_ms=60_000):
+            build_report()
+    """
+    expected_bytes = _to_bytes(expected) if expected is not None else None
+    host = _get_kv()
+    if host is None:
+        if _local_get(namespace, key) != expected_bytes:
+            return False
+        _local_set(namespace, key, _to_bytes(value), ttl_ms)
+        return True
+    return _call(host.compare_and_swap, namespace, key, expected_bytes, _to_bytes(value), ttl_ms)
@@ -0,0 +1,95 @@
+"""
+Capsule SDK - Structured Logging
+
+This module sends log records with a level and key/value fields to the Rust host.
+The host stores them with the task in `.capsule/trace.db`, returns them in
+`execution.logs` of the task result and prints them with `--verbose`.
+
+When running locally, records are printed to stderr instead.
+"""
+
+import logging
+import sys
+from typing import Any
+
+
+def _get_log():
+    """Get the WIT log module."""
+    try:
+        from wit_world.imports import log as log_module
+        return log_module
+    except ImportError:
+        return None
+
+
+def _write(level: str, message: str, fields: dict):
+    pairs = [(str(k), v if isinstance(v, str) else repr(v)) for k, v in fields.items()]
+
+    host = _get_log()
+    if host is None:
+        rendered = "".join(f" {k}={v}" for k, v in pairs)
+        print(f"{level.upper()} {message}{rendered}", file=sys.stderr)
+        return
+
+    host.write(getattr(host.Level, level.upper()), str(message), pairs)
+
+
+def trace(message: str, **fields: Any):
+    """Log a trace record. Keyword arguments become fields."""
+    _write("trace", message, fields)
+
+
+def debug(message: str, **fields: Any):
+    """Log a debug record. Keyword arguments become fields."""
+    _write("debug", message, fields)
+
+
+def info(message: str, **fields: Any):
+    """
+    Log an info record. Keyword arguments become fields.
+
+    Example:
+        log.info("fetched page", url=url, status=200)
+    """
+    _write("info", message, fields)
+
+
+def warn(message: str, **fields: Any):
+    """Log a warning record. Keyword arguments become fields."""
+    _write("warn", message, fields)
+
+
+def error(message: str, **fields: Any):
+    """Log an error record. Keyword arguments become fields."""
+    _write("error", message, fields)
+
+
+class Handler(logging.Handler):
+    """
+    Forward records from the standard `logging` module to the host.
+
+    Values passed with `extra={...}` become fields.
+
+    Example:
+        logging.getLogger().addHandler(capsule.log.Handler())
+    """
+
+    _RESERVED = set(vars(logging.makeLogRecord({}))) | {"message", "asctime"}
+
+    def emit(self, record: logging.LogRecord):
+        if record.levelno >= logging.ERROR:
+            level = "error"
+        elif record.levelno >= logging.WARNING:
+            level = "warn"
+        elif record.levelno >= logging.INFO:
+            level = "info"
+        else:
+            level = "debug"
+
+        fields = {k: v for k, v in vars(record).items() if k not in self._RESERVED}
+        fields["logger"] = record.name
+
+        try:
+            _write(level, record.getMessage(), fields)
+        except Exception:
+            self.handleError(record)
@@ -0,0 +1,63 @@
+"""
+Capsule SDK - Progress and Metrics
+
+This module reports task progress and custom metrics to the Rust host.
+Progress updates the CLI spinner and is streamed to worker clients that set
+`stream_progress`. Metrics are returned in `execution.metrics` of the task
+result and stored with the task in `.capsule/trace.db`.
+
+When running locally, progress is printed to stderr and metrics are kept in memory.
+"""
+
+import math
+import sys
+from typing import Optional
+
+_local_metrics: dict = {}
+
+
+def _get_progress():
+    """Get the WIT progress module."""
+    try:
+        from wit_world.imports import progress as progress_module
+        return progress_module
+    except ImportError:
+        return None
+
+
+def report(current: int, total: Optional[int] = None, message: Optional[str] = None):
+    """
+    Report how far the task has progressed.
+
+    Example:
+        for i, doc in enumerate(docs):
+            progress.report(i + 1, len(docs), "embedding")
+    """
+    host = _get_progress()
+    if host is None:
+        step = f"{current}/{total}" if total is not None else str(current)
+        print(f"PROGRESS {step} {message or ''}".rstrip(), file=sys.stderr)
+        return
+
+    host.report(int(current), None if total is None else int(total), message)
+
+
+def set_metric(name: str, value: float):
+    """Set a metric to a value. Non-finite values are ignored."""
+    host = _get_progress()
+    if host is None:
+        if math.isfinite(value):
+            _local_metrics[name] = float(value)
+        return
+
+    host.set_metric(name, float(value))
+
+
+def add_metric(name: str, delta: float = 1):
+    """Add a delta to a metric, starting from 0."""
+    host = _get_progress()
+    if host is None:
+        set_metric(name, _local_metrics.get(name, 0.0) + delta)
+        return
+
+    host.add_metric(name, float(delta))
@@ -32,6 +32,30 @@ class HostRequest(TypedDict):
     status: int
 
 
+class OverlayChange(TypedDict):
+    mount: str
+    path: str
+    kind: str
+
+
+class FileAccessSummary(TypedDict):
+    files_opened: int
+    files_created: int
+    files_deleted: int
+    files_renamed: int
+    bytes_read: int
+    bytes_written: int
+    paths_read: list[str]
+    paths_written: list[str]
+
+
+class Artifact(TypedDict):
+    path: str
+    size: int
+    hash: str
+    host_path: Optional[str]
+
+
 class ExecutionInfo(TypedDict):
     task_name: str
     duration_ms: int
@@ -39,9 +63,19 @@ class ExecutionInfo(TypedDict):
     fuel_consumed: int
     ram_used: int
     host_requests: list[HostRequest]
+    overlay_changes: list[OverlayChange]
+    file_access: Optional[FileAccessSummary]
+    artifacts: list[Artifact]
+    stdout: str
+    stderr: str
+    output_truncated: bool
 
 
-class ErrorInfo(TypedDict):
+class _ErrorDetails(TypedDict, total=False):
+    traceback: Optional[str]
+
+
+class ErrorInfo(_ErrorDetails):
     error_type: str
     message: str
 
@@ -57,7 +91,10 @@ async def run(
     *,
     file: str,
     args: Optional[list[str]] = None,
+    kwargs: Optional[dict[str, Any]] = None,
     mounts: Optional[list[str]] = None,
+    outputs_dir: Optional[str] = None,
+    stdin: Optional[str | bytes] = None,
     cwd: Optional[str] = None,
     capsule_path: str = "capsule",
 ) -> RunnerResult:
@@ -69,7 +106,10 @@ async def run(
     Args:
         file: Path to the source file or pre-built .wasm artifact
         args: Arguments to pass to the task's main function
+        kwargs: Keyword arguments to pass to the task's main function
         mounts: Mount specs (HOST[::GUEST][:ro|:rw])
+        outputs_dir: Host directory that receives the files the task writes to /outputs
+        stdin: Data the task reads from standard input (max 16 MiB)
         cwd: Working directory (used to resolve relative paths)
         capsule_path: Path to the capsule CLI binary
 
@@ -104,7 +144,10 @@ async def run(
             return await run_with_worker(
                 file=file,
                 args=args,
+                kwargs=kwargs,
                 mounts=mounts,
+                outputs_dir=outputs_dir,
+                stdin=stdin,
                 cwd=cwd,
                 capsule_path=capsule_path,
             )
@@ -117,7 +160,10 @@ async def run(
         resolved_file=resolved_file,
         ext=ext,
         args=args,
+        kwargs=kwargs,
         mounts=mounts,
+        outputs_dir=outputs_dir,
+        stdin=stdin,
         cwd=cwd,
         capsule_path=capsule_path,
     )
@@ -128,12 +174,17 @@ async def _run_subprocess(
     resolved_file: str,
     ext: str,
     args: list[str],
+    kwargs: Optional[dict[str, Any]],
     mounts: list[str],
+    outputs_dir: Optional[str],
+    stdin: Optional[str | bytes],
     cwd: Optional[str],
     capsule_path: str,
 ) -> RunnerResult:
     subcommand = "exec" if ext in _WASM_EXTENSIONS else "run"
     mount_flags = [flag for m in mounts for flag in ("--mount", m)]
+    if outputs_dir is not None:
+        mount_flags += ["--outputs-dir", outputs_dir]
 
     serialized_args = json.dumps(args).encode("utf-8")
     args_file_path: Optional[str] = None
@@ -148,21 +199,41 @@ async def _run_subprocess(
     else:
         args_flags = args
 
-    cmd = [capsule_path, subcommand, resolved_file, "--json", *mount_flags, *args_flags]
+    kwargs_file_path: Optional[str] = None
+    kwargs_flags: list[str] = []
+
+    if kwargs:
+        kwargs_file_path = os.path.join(
+            tempfile.gettempdir(), f"capsule-kwargs-{uuid.uuid4().hex}.json"
+        )
+        with open(kwargs_file_path, "w", encoding="utf-8") as f:
+            json.dump(kwargs, f)
+        kwargs_flags = ["--kwargs-file", kwargs_file_path]
+
+    temp_files = [path for path in (args_file_path, kwargs_file_path) if path]
+    stdin_flags = ["--stdin-file", "-"] if stdin is not None else []
+
+    cmd = [
+        capsule_path, subcommand, resolved_file, "--json",
+        *mount_flags, *stdin_flags, *kwargs_flags, *args_flags,
+    ]
 
     try:
         process = await asyncio.create_subprocess_exec(
             *cmd,
+            stdin=asyncio.subprocess.PIPE,
             stdout=asyncio.subprocess.PIPE,
             stderr=asyncio.subprocess.PIPE,
             cwd=cwd,
         )
 
-        stdout, stderr = await process.communicate()
+        if isinstance(stdin, str):
+            stdin = stdin.encode("utf-8")
+        stdout, stderr = await process.communicate(input=stdin or b"")
 
-        if args_file_path:
+        for path in temp_files:
             try:
-                os.unlink(args_file_path)
+                os.unlink(path)
             except OSError:
                 pass
 
@@ -179,9 +250,9 @@ async def _run_subprocess(
             )
 
     except FileNotFoundError:
-        if args_file_path:
+        for path in temp_files:
             try:
-                os.unlink(args_file_path)
+                os.unlink(path)
             except OSError:
                 pass
         raise FileNotFoundError(
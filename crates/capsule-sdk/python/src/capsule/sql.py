@@ -0,0 +1,105 @@
+"""
+Capsule SDK - SQL Databases
+
+This module runs parameterised queries against SQLite databases granted to the
+task with `sql_databases`. The Rust host opens the files, enforces read-only
+grants and stops queries that return too many rows or run too long.
+
+When running locally, the databases are opened with the `sqlite3` module instead.
+"""
+
+from dataclasses import dataclass, field
+from typing import Any, List, Sequence
+
+
+class SqlError(RuntimeError):
+    """Raised when the host rejects or fails a query."""
+
+
+@dataclass
+class QueryResult:
+    columns: List[str]
+    rows: List[tuple] = field(default_factory=list)
+    truncated: bool = False
+
+    def dicts(self) -> List[dict]:
+        """Return the rows as dictionaries keyed by column name."""
+        return [dict(zip(self.columns, row)) for row in self.rows]
+
+
+def _get_sql():
+    """Get the WIT sql module."""
+    try:
+        from wit_world.imports import sql as sql_module
+        return sql_module
+    except ImportError:
+        return None
+
+
+def _to_wit(host, value: Any):
+    if value is None:
+        return host.SqlValue_Null()
+    if isinstance(value, (bool, int)):
+        return host.SqlValue_Integer(int(value))
+    if isinstance(value, float):
+        return host.SqlValue_Real(value)
+    if isinstance(value, str):
+        return host.SqlValue_Text(value)
+    if isinstance(value, (bytes, bytearray, memoryview)):
+        return host.SqlValue_Blob(bytes(value))
+    raise TypeError(f"Unsupported SQL parameter type: {type(value).__name__}")
+
+
+def _call(func, *args):
+    try:
+        return func(*args)
+    except Exception as e:
+        error = getattr(e, "value", e)
+        raise SqlError(f"{type(error).__name__}: {getattr(error, 'value', error)}") from None
+
+
+class Connection:
+    """
+    A granted database, addressed by its path or alias in `sql_databases`.
+
+    Example:
+        db = sql.connect("data/app.db")
+        rows = db.query("SELECT name FROM users WHERE age > ?", [30]).rows
+    """
+
+    def __init__(self, database: str):
+        self.database = database
+        self._local = None
+
+    def _local_connection(self):
+        if self._local is None:
+            import sqlite3
+            self._local = sqlite3.connect(self.database)
+        return self._local
+
+    def query(self, statement: str, params: Sequence[Any] = ()) -> QueryResult:
+        """Run a statement and return its rows."""
+        host = _get_sql()
+        if host is None:
+            cursor = self._local_connection().execute(statement, tuple(params))
+            columns = [c[0] for c in cursor.description or []]
+            return QueryResult(columns, [tuple(row) for row in cursor.fetchall()])
+
+        result = _call(host.query, self.database, statement, [_to_wit(host, p) for p in params])
+        rows = [tuple(getattr(v, "value", None) for v in row) for row in result.rows]
+        return QueryResult(list(result.columns), rows, result.truncated)
+
+    def execute(self, statement: str, params: Sequence[Any] = ()) -> int:
+        """Run a statement and return the number of rows it changed."""
+        host = _get_sql()
+        if host is None:
+            conn = self._local_connection()
+            with conn:
+                return conn.execute(statement, tuple(params)).rowcount
+
+        return _call(host.execute, self.database, statement, [_to_wit(host, p) for p in params])
+
+
+def connect(database: str) -> Connection:
+    """Open a database granted to the task."""
+    return Connection(database)
@@ -7,10 +7,11 @@ concurrently — each call gets its own response future keyed by a unique id.
 """
 
 import asyncio
+import base64
 import json
 import os
 import uuid
-from typing import Optional
+from typing import Any, Optional
 
 from .run import RunnerResult, _WASM_EXTENSIONS
 
@@ -50,6 +51,8 @@ class _WorkerClient:
                     break
                 try:
                     response = json.loads(line.decode("utf-8"))
+                    if "event" in response or "progress" in response:
+                        continue
                     req_id = response.get("id")
                     future = self._pending.pop(req_id, None)
                     if future and not future.done():
@@ -90,11 +93,28 @@ class _WorkerClient:
             if dead:
                 await self._start()
 
-    async def send(self, file: str, args: list[str], mounts: list[str]) -> str:
+    async def send(
+        self,
+        file: str,
+        args: list[str],
+        mounts: list[str],
+        outputs_dir: Optional[str] = None,
+        stdin: Optional[str | bytes] = None,
+        kwargs: Optional[dict[str, Any]] = None,
+    ) -> str:
         await self._ensure_running()
 
         req_id = uuid.uuid4().hex
-        request = json.dumps({"id": req_id, "file": file, "args": args, "mounts": mounts})
+        payload = {"id": req_id, "file": file, "args": args, "mounts": mounts}
+        if kwargs:
+            payload["kwargs"] = kwargs
+        if outputs_dir is not None:
+            payload["outputs_dir"] = outputs_dir
+        if isinstance(stdin, str):
+            payload["stdin"] = stdin
+        elif stdin is not None:
+            payload["stdin_base64"] = base64.b64encode(stdin).decode("ascii")
+        request = json.dumps(payload)
 
         future: asyncio.Future[str] = asyncio.get_running_loop().create_future()
         self._pending[req_id] = future
@@ -152,7 +172,10 @@ async def run_with_worker(
     *,
     file: str,
     args: Optional[list[str]] = None,
+    kwargs: Optional[dict[str, Any]] = None,
     mounts: Optional[list[str]] = None,
+    outputs_dir: Optional[str] = None,
+    stdin: Optional[str | bytes] = None,
     cwd: Optional[str] = None,
     capsule_path: str = "capsule",
 ) -> RunnerResult:
@@ -163,7 +186,10 @@ async def run_with_worker(
     Args:
         file: Path to the source file or pre-built .wasm artifact
         args: Arguments to pass to the task's main function
+        kwargs: Keyword arguments to pass to the task's main function
         mounts: Mount specs forwarded to the worker (HOST[::GUEST][:ro|:rw])
+        outputs_dir: Host directory that receives the files the task writes to /outputs
+        stdin: Data the task reads from standard input (max 16 MiB)
         cwd: Working directory for resolving relative paths
         capsule_path: Path to the capsule CLI binary
 
@@ -187,7 +213,7 @@ async def run_with_worker(
     client = await _get_client(capsule_path, cwd or os.getcwd())
 
     try:
-        raw = await client.send(resolved_file, args, mounts)
+        raw = await client.send(resolved_file, args, mounts, outputs_dir, stdin, kwargs)
     except RuntimeError:
         key = (capsule_path, cwd or os.getcwd())
         _clients.pop(key, None)
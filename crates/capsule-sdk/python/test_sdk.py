@@ -5,8 +5,12 @@ This tests:
 1. Task registration with config
 2. Task execution in local mode
 3. Config storage and retrieval
+4. Keyword arguments reaching the task runners
 """
 
+import asyncio
+import importlib
+import json
 import sys
 import os
 
@@ -61,11 +65,114 @@ def test_task_config_defaults():
     print("✓ Config defaults test passed")
 
 
+def test_task_runner_kwargs():
+    """Test that the JSON task runner passes nested kwargs through unchanged."""
+
+    @task(name="kwargs_task")
+    def kwargs_task(prefix, options=None):
+        return {"prefix": prefix, "options": options}
+
+    options = {"retries": 3, "tags": ["a", "b"], "nested": {"enabled": True}}
+    output = app.TaskRunner().run(json.dumps({
+        "task_name": "kwargs_task",
+        "args": ["run"],
+        "kwargs": {"options": options},
+    }))
+
+    assert json.loads(output) == {"result": {"prefix": "run", "options": options}}
+
+    print("✓ Task runner kwargs test passed")
+
+
+def test_run_subprocess_kwargs():
+    """Test that kwargs are written to the --kwargs-file passed to the CLI."""
+
+    run_module = importlib.import_module("capsule.run")
+    captured = {}
+
+    class FakeProcess:
+        returncode = 0
+
+        async def communicate(self, input=None):
+            return b'{"success": true, "result": null}\n', b""
+
+    async def fake_exec(*cmd, **_options):
+        captured["cmd"] = list(cmd)
+        with open(cmd[cmd.index("--kwargs-file") + 1], encoding="utf-8") as f:
+            captured["kwargs"] = json.load(f)
+        return FakeProcess()
+
+    kwargs = {"limit": 10, "filters": {"tags": ["x"], "active": None}}
+    original = run_module.asyncio.create_subprocess_exec
+    run_module.asyncio.create_subprocess_exec = fake_exec
+
+    try:
+        result = asyncio.run(run_module._run_subprocess(
+            resolved_file="main.py",
+            ext=".py",
+            args=["a"],
+            kwargs=kwargs,
+            mounts=[],
+            outputs_dir=None,
+            stdin=None,
+            cwd=None,
+            capsule_path="capsule",
+        ))
+    finally:
+        run_module.asyncio.create_subprocess_exec = original
+
+    kwargs_file = captured["cmd"][captured["cmd"].index("--kwargs-file") + 1]
+
+    assert result == {"success": True, "result": None}
+    assert captured["cmd"][:4] == ["capsule", "run", "main.py", "--json"]
+    assert captured["cmd"][-1] == "a"
+    assert "--stdin-file" not in captured["cmd"]
+    assert captured["kwargs"] == kwargs
+    assert not os.path.exists(kwargs_file), "kwargs file should be removed"
+
+    print("✓ Subprocess kwargs test passed")
+
+
+def test_worker_request_kwargs():
+    """Test that worker requests carry kwargs as a JSON object."""
+
+    worker_module = importlib.import_module("capsule.worker")
+    client = worker_module._WorkerClient("capsule", None)
+    requests = []
+
+    class FakeStdin:
+        def write(self, data):
+            requests.append(json.loads(data.decode("utf-8")))
+
+        async def drain(self):
+            request = requests[-1]
+            client._pending.pop(request["id"]).set_result(json.dumps(request))
+
+    class FakeProcess:
+        returncode = None
+        stdin = FakeStdin()
+
+    client._process = FakeProcess()
+    kwargs = {"mode": "fast", "weights": [0.5, 1.5], "extra": {"depth": 2}}
+
+    asyncio.run(client.send("main.py", ["a"], [], kwargs=kwargs))
+    asyncio.run(client.send("main.py", ["b"], []))
+
+    assert requests[0]["kwargs"] == kwargs
+    assert requests[0]["args"] == ["a"]
+    assert "kwargs" not in requests[1]
+
+    print("✓ Worker request kwargs test passed")
+
+
 if __name__ == "__main__":
     print("Testing Capsule SDK...\n")
 
     test_task_registration()
     test_task_execution_local()
     test_task_config_defaults()
+    test_task_runner_kwargs()
+    test_run_subprocess_kwargs()
+    test_worker_request_kwargs()
 
     print("\n✨ All tests passed!")